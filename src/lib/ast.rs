@@ -1,4 +1,5 @@
 pub mod expression;
+pub mod pattern;
 pub mod statement;
 
 use expression::Expression;
@@ -9,7 +10,7 @@ pub trait NodeInterface: Display {
     fn token_literal(&self) -> String;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Node {
     Statement(Statement),
     Expression(Expression),
@@ -27,20 +28,20 @@ impl NodeInterface for Node {
 impl Display for Node {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Expression(expression) => write!(f, "{}", expression.to_string()),
-            Self::Statement(stmt) => write!(f, "{}", stmt.to_string()),
+            Self::Expression(expression) => write!(f, "{}", expression),
+            Self::Statement(stmt) => write!(f, "{}", stmt),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Program {
     pub statements: Vec<Statement>,
 }
 
 impl NodeInterface for Program {
     fn token_literal(&self) -> String {
-        if self.statements.len() > 0 {
+        if !self.statements.is_empty() {
             self.statements[0].token_literal()
         } else {
             "".to_string()
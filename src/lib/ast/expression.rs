@@ -1,21 +1,66 @@
+use super::pattern::Pattern;
 use super::statement;
 use crate::ast::NodeInterface;
+use crate::object::bigint::BigInt;
 use crate::token::Token;
 use std::fmt::Display;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Expression {
     Identifier(Identifier),
     IntegerLiteral(IntegerLiteral),
+    BigIntLiteral(BigIntLiteral),
+    FloatLiteral(FloatLiteral),
+    StringLiteral(StringLiteral),
     Prefix(Prefix),
     Infix(Infix),
     Boolean(Boolean),
     If(If),
     FunctionLiteral(FunctionLiteral),
     Call(Call),
+    ArrayLiteral(ArrayLiteral),
+    TupleLiteral(TupleLiteral),
+    HashLiteral(HashLiteral),
+    StructLiteral(StructLiteral),
+    Index(Index),
+    Member(Member),
+    OptionalIndex(OptionalIndex),
+    OptionalMember(OptionalMember),
+    Null(Null),
+    Range(Range),
+    Assign(Assign),
+    Match(Match),
 }
 
 impl Expression {
+    pub fn token(&self) -> &Token {
+        match self {
+            Self::Identifier(idnt) => &idnt.token,
+            Self::IntegerLiteral(int) => &int.token,
+            Self::BigIntLiteral(int) => &int.token,
+            Self::FloatLiteral(float) => &float.token,
+            Self::StringLiteral(string) => &string.token,
+            Self::Prefix(prefix) => &prefix.token,
+            Self::Infix(infix) => &infix.token,
+            Self::Boolean(boolean) => &boolean.token,
+            Self::If(if_exp) => &if_exp.token,
+            Self::FunctionLiteral(function) => &function.token,
+            Self::Call(call) => &call.token,
+            Self::ArrayLiteral(array) => &array.token,
+            Self::TupleLiteral(tuple) => &tuple.token,
+            Self::HashLiteral(hash) => &hash.token,
+            Self::StructLiteral(literal) => &literal.token,
+            Self::Index(index) => &index.token,
+            Self::Member(member) => &member.token,
+            Self::OptionalIndex(index) => &index.token,
+            Self::OptionalMember(member) => &member.token,
+            Self::Null(null) => &null.token,
+            Self::Range(range) => &range.token,
+            Self::Assign(assign) => &assign.token,
+            Self::Match(match_exp) => &match_exp.token,
+        }
+    }
+
     pub fn identifier(&self) -> Option<&Identifier> {
         match self {
             Self::Identifier(idnt) => Some(idnt),
@@ -30,16 +75,37 @@ impl Expression {
         }
     }
 
+    pub fn big_int_literal(&self) -> Option<&BigIntLiteral> {
+        match self {
+            Self::BigIntLiteral(int) => Some(int),
+            _ => None,
+        }
+    }
+
+    pub fn float_literal(&self) -> Option<&FloatLiteral> {
+        match self {
+            Self::FloatLiteral(float) => Some(float),
+            _ => None,
+        }
+    }
+
+    pub fn string_literal(&self) -> Option<&StringLiteral> {
+        match self {
+            Self::StringLiteral(string) => Some(string),
+            _ => None,
+        }
+    }
+
     pub fn prefix_expression(&self) -> Option<&Prefix> {
         match self {
-            Self::Prefix(prefix) => Some(&prefix),
+            Self::Prefix(prefix) => Some(prefix),
             _ => None,
         }
     }
 
     pub fn infix_expression(&self) -> Option<&Infix> {
         match self {
-            Self::Infix(infix) => Some(&infix),
+            Self::Infix(infix) => Some(infix),
             _ => None,
         }
     }
@@ -71,24 +137,116 @@ impl Expression {
             _ => None,
         }
     }
+
+    pub fn array_literal(&self) -> Option<&ArrayLiteral> {
+        match self {
+            Self::ArrayLiteral(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn tuple_literal(&self) -> Option<&TupleLiteral> {
+        match self {
+            Self::TupleLiteral(tuple) => Some(tuple),
+            _ => None,
+        }
+    }
+
+    pub fn hash_literal(&self) -> Option<&HashLiteral> {
+        match self {
+            Self::HashLiteral(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    pub fn index_expression(&self) -> Option<&Index> {
+        match self {
+            Self::Index(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    pub fn range(&self) -> Option<&Range> {
+        match self {
+            Self::Range(range) => Some(range),
+            _ => None,
+        }
+    }
+
+    pub fn optional_index_expression(&self) -> Option<&OptionalIndex> {
+        match self {
+            Self::OptionalIndex(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    pub fn struct_literal(&self) -> Option<&StructLiteral> {
+        match self {
+            Self::StructLiteral(literal) => Some(literal),
+            _ => None,
+        }
+    }
+
+    pub fn member_expression(&self) -> Option<&Member> {
+        match self {
+            Self::Member(member) => Some(member),
+            _ => None,
+        }
+    }
+
+    pub fn optional_member_expression(&self) -> Option<&OptionalMember> {
+        match self {
+            Self::OptionalMember(member) => Some(member),
+            _ => None,
+        }
+    }
+
+    pub fn assign_expression(&self) -> Option<&Assign> {
+        match self {
+            Self::Assign(assign) => Some(assign),
+            _ => None,
+        }
+    }
+
+    pub fn match_expression(&self) -> Option<&Match> {
+        match self {
+            Self::Match(match_exp) => Some(match_exp),
+            _ => None,
+        }
+    }
 }
 
 impl Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Identifier(idnt) => write!(f, "{}", idnt.to_string()),
-            Self::IntegerLiteral(int) => write!(f, "{}", int.to_string()),
-            Self::Prefix(prefix) => write!(f, "{}", prefix.to_string()),
-            Self::Infix(infix) => write!(f, "{}", infix.to_string()),
-            Self::Boolean(boolean) => write!(f, "{}", boolean.to_string()),
-            Self::If(if_exp) => write!(f, "{}", if_exp.to_string()),
-            Self::FunctionLiteral(function) => write!(f, "{}", function.to_string()),
-            Self::Call(call) => write!(f, "{}", call.to_string()),
+            Self::Identifier(idnt) => write!(f, "{}", idnt),
+            Self::IntegerLiteral(int) => write!(f, "{}", int),
+            Self::BigIntLiteral(int) => write!(f, "{}", int),
+            Self::FloatLiteral(float) => write!(f, "{}", float),
+            Self::StringLiteral(string) => write!(f, "{}", string),
+            Self::Prefix(prefix) => write!(f, "{}", prefix),
+            Self::Infix(infix) => write!(f, "{}", infix),
+            Self::Boolean(boolean) => write!(f, "{}", boolean),
+            Self::If(if_exp) => write!(f, "{}", if_exp),
+            Self::FunctionLiteral(function) => write!(f, "{}", function),
+            Self::Call(call) => write!(f, "{}", call),
+            Self::ArrayLiteral(array) => write!(f, "{}", array),
+            Self::TupleLiteral(tuple) => write!(f, "{}", tuple),
+            Self::HashLiteral(hash) => write!(f, "{}", hash),
+            Self::Index(index) => write!(f, "{}", index),
+            Self::OptionalIndex(index) => write!(f, "{}", index),
+            Self::OptionalMember(member) => write!(f, "{}", member),
+            Self::StructLiteral(literal) => write!(f, "{}", literal),
+            Self::Member(member) => write!(f, "{}", member),
+            Self::Null(null) => write!(f, "{}", null),
+            Self::Range(range) => write!(f, "{}", range),
+            Self::Assign(assign) => write!(f, "{}", assign),
+            Self::Match(match_exp) => write!(f, "{}", match_exp),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Identifier {
     pub token: Token,
     pub value: String,
@@ -106,7 +264,7 @@ impl Display for Identifier {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IntegerLiteral {
     pub token: Token,
     pub value: i64,
@@ -124,7 +282,62 @@ impl NodeInterface for IntegerLiteral {
     }
 }
 
-#[derive(Debug)]
+/// An integer literal too large for an `i64`.
+#[derive(Debug, Clone)]
+pub struct BigIntLiteral {
+    pub token: Token,
+    pub value: BigInt,
+}
+
+impl Display for BigIntLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.token.literal)
+    }
+}
+
+impl NodeInterface for BigIntLiteral {
+    fn token_literal(&self) -> String {
+        self.token.to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FloatLiteral {
+    pub token: Token,
+    pub value: f64,
+}
+
+impl Display for FloatLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.token.literal)
+    }
+}
+
+impl NodeInterface for FloatLiteral {
+    fn token_literal(&self) -> String {
+        self.token.to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StringLiteral {
+    pub token: Token,
+    pub value: String,
+}
+
+impl Display for StringLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.value)
+    }
+}
+
+impl NodeInterface for StringLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Prefix {
     pub token: Token,
     pub operator: String,
@@ -133,17 +346,17 @@ pub struct Prefix {
 
 impl Display for Prefix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}{})", self.operator, self.right.to_string())
+        write!(f, "({}{})", self.operator, self.right)
     }
 }
 
 impl NodeInterface for Prefix {
     fn token_literal(&self) -> String {
-        format!("{}", self.token.literal)
+        self.token.literal.to_string()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Infix {
     pub token: Token,
     pub left: Box<Expression>,
@@ -153,23 +366,17 @@ pub struct Infix {
 
 impl NodeInterface for Infix {
     fn token_literal(&self) -> String {
-        format!("{}", self.token.literal)
+        self.token.literal.to_string()
     }
 }
 
 impl Display for Infix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "({} {} {})",
-            self.left.to_string(),
-            self.operator,
-            self.right.to_string()
-        )
+        write!(f, "({} {} {})", self.left, self.operator, self.right)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Boolean {
     pub token: Token,
     pub value: bool,
@@ -177,7 +384,7 @@ pub struct Boolean {
 
 impl NodeInterface for Boolean {
     fn token_literal(&self) -> String {
-        format!("{}", self.token.literal)
+        self.token.literal.to_string()
     }
 }
 
@@ -187,7 +394,7 @@ impl Display for Boolean {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct If {
     pub token: Token,
     pub condition: Box<Expression>,
@@ -218,7 +425,7 @@ impl Display for If {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FunctionLiteral {
     pub token: Token,
     pub parameters: Vec<Identifier>,
@@ -250,7 +457,7 @@ impl Display for FunctionLiteral {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Call {
     pub token: Token,
     pub function: Box<Expression>,
@@ -280,3 +487,292 @@ impl Display for Call {
         write!(f, "{}", out)
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct ArrayLiteral {
+    pub token: Token,
+    pub elements: Vec<Expression>,
+}
+
+impl NodeInterface for ArrayLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for ArrayLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let elements: Vec<String> = self.elements.iter().map(|e| e.to_string()).collect();
+
+        write!(f, "[{}]", elements.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TupleLiteral {
+    pub token: Token,
+    pub elements: Vec<Expression>,
+}
+
+impl NodeInterface for TupleLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for TupleLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let elements: Vec<String> = self.elements.iter().map(|e| e.to_string()).collect();
+
+        match elements.as_slice() {
+            [element] => write!(f, "({},)", element),
+            _ => write!(f, "({})", elements.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HashLiteral {
+    pub token: Token,
+    pub pairs: Vec<(Expression, Expression)>,
+}
+
+impl NodeInterface for HashLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for HashLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pairs: Vec<String> = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect();
+
+        write!(f, "{{{}}}", pairs.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Index {
+    pub token: Token,
+    pub left: Box<Expression>,
+    pub index: Box<Expression>,
+}
+
+impl NodeInterface for Index {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}[{}])", self.left, self.index)
+    }
+}
+
+/// `left?[index]`, which is null without evaluating `index` when `left` is.
+#[derive(Debug, Clone)]
+pub struct OptionalIndex {
+    pub token: Token,
+    pub left: Box<Expression>,
+    pub index: Box<Expression>,
+}
+
+impl NodeInterface for OptionalIndex {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for OptionalIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}?[{}])", self.left, self.index)
+    }
+}
+
+/// `Point { x: 1, y: 2 }`, whose token is the struct's name.
+#[derive(Debug, Clone)]
+pub struct StructLiteral {
+    pub token: Token,
+    pub name: Identifier,
+    pub fields: Vec<(Identifier, Expression)>,
+}
+
+impl NodeInterface for StructLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for StructLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+
+        write!(f, "{} {{ {} }}", self.name, fields.join(", "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub token: Token,
+    pub left: Box<Expression>,
+    pub member: Identifier,
+}
+
+impl NodeInterface for Member {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for Member {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}.{})", self.left, self.member)
+    }
+}
+
+/// `left?.member`, which is null when `left` is.
+#[derive(Debug, Clone)]
+pub struct OptionalMember {
+    pub token: Token,
+    pub left: Box<Expression>,
+    pub member: Identifier,
+}
+
+impl NodeInterface for OptionalMember {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for OptionalMember {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}?.{})", self.left, self.member)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Null {
+    pub token: Token,
+}
+
+impl NodeInterface for Null {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for Null {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.token.literal)
+    }
+}
+
+/// `start..end` or `start..=end`, where either bound may be left out except
+/// the end of an inclusive range.
+#[derive(Debug, Clone)]
+pub struct Range {
+    pub token: Token,
+    pub start: Option<Box<Expression>>,
+    pub end: Option<Box<Expression>>,
+    pub inclusive: bool,
+}
+
+impl NodeInterface for Range {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(")?;
+        if let Some(start) = &self.start {
+            write!(f, "{}", start)?;
+        }
+        write!(f, "{}", self.token.literal)?;
+        if let Some(end) = &self.end {
+            write!(f, "{}", end)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// `target = value`, or a compound form such as `target += value`. The target
+/// is an identifier, index or member expression.
+#[derive(Debug, Clone)]
+pub struct Assign {
+    pub token: Token,
+    pub target: Box<Expression>,
+    pub operator: String,
+    pub value: Box<Expression>,
+}
+
+impl Assign {
+    /// The infix operator a compound assignment applies, `+` for `+=`.
+    pub fn compound_operator(&self) -> Option<&str> {
+        self.operator.strip_suffix('=').filter(|op| !op.is_empty())
+    }
+}
+
+impl NodeInterface for Assign {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for Assign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({} {} {})", self.target, self.operator, self.value)
+    }
+}
+
+/// `match (value) { pattern => result, ... }`, whose value is the result of
+/// the first arm whose pattern matches and whose guard, if any, holds.
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub token: Token,
+    pub subject: Box<Expression>,
+    pub arms: Vec<MatchArm>,
+}
+
+impl NodeInterface for Match {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Display for Match {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let arms: Vec<String> = self.arms.iter().map(|arm| arm.to_string()).collect();
+
+        write!(f, "match {} {{ {} }}", self.subject, arms.join(", "))
+    }
+}
+
+/// `pattern if guard => body`. Names the pattern binds are in scope in the
+/// guard and the body only.
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expression>,
+    pub body: Expression,
+}
+
+impl Display for MatchArm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)?;
+        if let Some(guard) = &self.guard {
+            write!(f, " if {}", guard)?;
+        }
+        write!(f, " => {}", self.body)
+    }
+}
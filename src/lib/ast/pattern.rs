@@ -0,0 +1,174 @@
+use super::expression::{Expression, Identifier};
+use crate::ast::NodeInterface;
+use crate::token::Token;
+use std::fmt::Display;
+
+/// The left-hand side of a `let`, binding the parts of a value to names.
+/// `match` arms also test values with literal and hash patterns.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Identifier(Identifier),
+    /// `_`, which matches anything and binds nothing.
+    Wildcard(Token),
+    /// A number, string, boolean or `null`, matching values equal to it.
+    Literal(Box<Expression>),
+    Tuple(TuplePattern),
+    Array(ArrayPattern),
+    Hash(HashPattern),
+}
+
+impl Pattern {
+    pub fn token(&self) -> &Token {
+        match self {
+            Self::Identifier(idnt) => &idnt.token,
+            Self::Wildcard(token) => token,
+            Self::Literal(literal) => literal.token(),
+            Self::Tuple(tuple) => &tuple.token,
+            Self::Array(array) => &array.token,
+            Self::Hash(hash) => &hash.token,
+        }
+    }
+
+    /// The names the pattern binds, in the order a match binds them.
+    pub fn bindings(&self) -> Vec<&Identifier> {
+        match self {
+            Self::Identifier(idnt) => vec![idnt],
+            Self::Wildcard(_) | Self::Literal(_) => vec![],
+            Self::Tuple(tuple) => tuple.elements.iter().flat_map(|e| e.bindings()).collect(),
+            Self::Array(array) => array
+                .elements
+                .iter()
+                .chain(array.rest.as_deref())
+                .flat_map(|e| e.bindings())
+                .collect(),
+            Self::Hash(hash) => hash.pairs.iter().flat_map(|(_, p)| p.bindings()).collect(),
+        }
+    }
+
+    /// Whether the pattern only takes values apart, without literal or hash
+    /// patterns that could fail to match a value of the right shape.
+    pub fn is_destructuring(&self) -> bool {
+        match self {
+            Self::Identifier(_) | Self::Wildcard(_) => true,
+            Self::Literal(_) | Self::Hash(_) => false,
+            Self::Tuple(tuple) => tuple.elements.iter().all(|e| e.is_destructuring()),
+            Self::Array(array) => array
+                .elements
+                .iter()
+                .chain(array.rest.as_deref())
+                .all(|e| e.is_destructuring()),
+        }
+    }
+
+    pub fn identifier(&self) -> Option<&Identifier> {
+        match self {
+            Self::Identifier(idnt) => Some(idnt),
+            _ => None,
+        }
+    }
+
+    pub fn tuple_pattern(&self) -> Option<&TuplePattern> {
+        match self {
+            Self::Tuple(tuple) => Some(tuple),
+            _ => None,
+        }
+    }
+
+    pub fn array_pattern(&self) -> Option<&ArrayPattern> {
+        match self {
+            Self::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn literal_pattern(&self) -> Option<&Expression> {
+        match self {
+            Self::Literal(literal) => Some(literal.as_ref()),
+            _ => None,
+        }
+    }
+
+    pub fn hash_pattern(&self) -> Option<&HashPattern> {
+        match self {
+            Self::Hash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+}
+
+impl NodeInterface for Pattern {
+    fn token_literal(&self) -> String {
+        self.token().literal.to_string()
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Identifier(idnt) => write!(f, "{}", idnt),
+            Self::Wildcard(_) => write!(f, "_"),
+            Self::Literal(literal) => write!(f, "{}", literal),
+            Self::Tuple(tuple) => write!(f, "{}", tuple),
+            Self::Array(array) => write!(f, "{}", array),
+            Self::Hash(hash) => write!(f, "{}", hash),
+        }
+    }
+}
+
+/// `(a, b)`, matching a tuple with exactly as many elements.
+#[derive(Debug, Clone)]
+pub struct TuplePattern {
+    pub token: Token,
+    pub elements: Vec<Pattern>,
+}
+
+impl Display for TuplePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let elements: Vec<String> = self.elements.iter().map(|e| e.to_string()).collect();
+
+        match elements.as_slice() {
+            [element] => write!(f, "({},)", element),
+            _ => write!(f, "({})", elements.join(", ")),
+        }
+    }
+}
+
+/// `[a, b, ...rest]`, matching an array with exactly as many elements, or at
+/// least as many when there is a rest pattern to take the remainder.
+#[derive(Debug, Clone)]
+pub struct ArrayPattern {
+    pub token: Token,
+    pub elements: Vec<Pattern>,
+    pub rest: Option<Box<Pattern>>,
+}
+
+impl Display for ArrayPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut elements: Vec<String> = self.elements.iter().map(|e| e.to_string()).collect();
+        if let Some(rest) = &self.rest {
+            elements.push(format!("...{}", rest));
+        }
+
+        write!(f, "[{}]", elements.join(", "))
+    }
+}
+
+/// `{"key": pattern}`, matching a hash that has each of the keys, with
+/// values matching their patterns. Other keys are ignored.
+#[derive(Debug, Clone)]
+pub struct HashPattern {
+    pub token: Token,
+    pub pairs: Vec<(Expression, Pattern)>,
+}
+
+impl Display for HashPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pairs: Vec<String> = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, value))
+            .collect();
+
+        write!(f, "{{{}}}", pairs.join(", "))
+    }
+}
@@ -1,17 +1,37 @@
 use super::expression;
+use super::pattern::Pattern;
 use crate::ast::NodeInterface;
 use crate::token::Token;
 use std::fmt::Display;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Statement {
     Let(Let),
     Return(Return),
     Expression(Expression),
     Block(Block),
+    Struct(Struct),
+    While(While),
+    For(For),
+    Break(Break),
+    Continue(Continue),
 }
 
 impl Statement {
+    pub fn token(&self) -> &Token {
+        match self {
+            Self::Let(let_statement) => &let_statement.token,
+            Self::Return(return_statement) => &return_statement.token,
+            Self::Expression(exp) => &exp.token,
+            Self::Block(block) => &block.token,
+            Self::Struct(declaration) => &declaration.token,
+            Self::While(while_statement) => &while_statement.token,
+            Self::For(for_statement) => &for_statement.token,
+            Self::Break(break_statement) => &break_statement.token,
+            Self::Continue(continue_statement) => &continue_statement.token,
+        }
+    }
+
     pub fn let_statement(&self) -> Option<&Let> {
         match self {
             Self::Let(let_statement) => Some(let_statement),
@@ -33,6 +53,41 @@ impl Statement {
         }
     }
 
+    pub fn struct_statement(&self) -> Option<&Struct> {
+        match self {
+            Self::Struct(declaration) => Some(declaration),
+            _ => None,
+        }
+    }
+
+    pub fn while_statement(&self) -> Option<&While> {
+        match self {
+            Self::While(while_statement) => Some(while_statement),
+            _ => None,
+        }
+    }
+
+    pub fn for_statement(&self) -> Option<&For> {
+        match self {
+            Self::For(for_statement) => Some(for_statement),
+            _ => None,
+        }
+    }
+
+    pub fn break_statement(&self) -> Option<&Break> {
+        match self {
+            Self::Break(break_statement) => Some(break_statement),
+            _ => None,
+        }
+    }
+
+    pub fn continue_statement(&self) -> Option<&Continue> {
+        match self {
+            Self::Continue(continue_statement) => Some(continue_statement),
+            _ => None,
+        }
+    }
+
     pub fn block_statement(&self) -> Option<&Block> {
         match self {
             Self::Block(exp) => Some(exp),
@@ -46,6 +101,11 @@ impl NodeInterface for Statement {
         match self {
             Self::Let(let_statement) => let_statement.token_literal(),
             Self::Return(return_statement) => return_statement.token_literal(),
+            Self::Struct(declaration) => declaration.token_literal(),
+            Self::While(while_statement) => while_statement.token_literal(),
+            Self::For(for_statement) => for_statement.token_literal(),
+            Self::Break(break_statement) => break_statement.token_literal(),
+            Self::Continue(continue_statement) => continue_statement.token_literal(),
             _ => "".to_string(),
         }
     }
@@ -54,18 +114,24 @@ impl NodeInterface for Statement {
 impl Display for Statement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Expression(expr) => write!(f, "{}", expr.to_string()),
-            Self::Let(stmt) => write!(f, "{}", stmt.to_string()),
-            Self::Return(stmt) => write!(f, "{}", stmt.to_string()),
-            Self::Block(stmt) => write!(f, "{}", stmt.to_string()),
+            Self::Expression(expr) => write!(f, "{}", expr),
+            Self::Let(stmt) => write!(f, "{}", stmt),
+            Self::Return(stmt) => write!(f, "{}", stmt),
+            Self::Block(stmt) => write!(f, "{}", stmt),
+            Self::Struct(stmt) => write!(f, "{}", stmt),
+            Self::While(stmt) => write!(f, "{}", stmt),
+            Self::For(stmt) => write!(f, "{}", stmt),
+            Self::Break(stmt) => write!(f, "{}", stmt),
+            Self::Continue(stmt) => write!(f, "{}", stmt),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Let {
     pub token: Token,
-    pub name: expression::Identifier,
+    pub mutable: bool,
+    pub name: Pattern,
     pub value: Option<expression::Expression>,
 }
 
@@ -79,11 +145,11 @@ impl Display for Let {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut out = String::new();
 
-        out.push_str(&format!(
-            "{} {} = ",
-            self.token_literal(),
-            self.name.to_string()
-        ));
+        out.push_str(&format!("{} ", self.token_literal()));
+        if self.mutable {
+            out.push_str("mut ");
+        }
+        out.push_str(&format!("{} = ", self.name));
 
         if let Some(val) = &self.value {
             out.push_str(&val.to_string());
@@ -95,7 +161,151 @@ impl Display for Let {
     }
 }
 
-#[derive(Debug)]
+/// `struct Point { x, y }`, declaring a record type and its field names.
+#[derive(Debug, Clone)]
+pub struct Struct {
+    pub token: Token,
+    pub name: expression::Identifier,
+    pub fields: Vec<expression::Identifier>,
+}
+
+impl NodeInterface for Struct {
+    fn token_literal(&self) -> String {
+        self.token.to_string()
+    }
+}
+
+impl Display for Struct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<String> = self.fields.iter().map(|field| field.to_string()).collect();
+
+        write!(
+            f,
+            "{} {} {{ {} }}",
+            self.token_literal(),
+            self.name,
+            fields.join(", ")
+        )
+    }
+}
+
+/// `while (condition) { ... }`, optionally labelled as in `'outer: while`
+/// so that `break` and `continue` in nested loops can refer to it.
+#[derive(Debug, Clone)]
+pub struct While {
+    pub token: Token,
+    pub label: Option<String>,
+    pub condition: expression::Expression,
+    pub body: Block,
+}
+
+impl NodeInterface for While {
+    fn token_literal(&self) -> String {
+        self.token.to_string()
+    }
+}
+
+impl Display for While {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(label) = &self.label {
+            write!(f, "'{}: ", label)?;
+        }
+
+        write!(
+            f,
+            "{} {} {{ {} }}",
+            self.token_literal(),
+            self.condition,
+            self.body
+        )
+    }
+}
+
+/// `for (x in xs) { ... }`, running the body once for each element of an
+/// array or tuple, character of a string, number in a range or key of a hash.
+/// With two variables, as in `for (k, v in h)`, the first is bound to the
+/// index, or for a hash the key, and the second to the value. The variables
+/// are bound afresh for every iteration in a scope of the body's own.
+#[derive(Debug, Clone)]
+pub struct For {
+    pub token: Token,
+    pub label: Option<String>,
+    pub variables: Vec<Pattern>,
+    pub iterable: expression::Expression,
+    pub body: Block,
+}
+
+impl NodeInterface for For {
+    fn token_literal(&self) -> String {
+        self.token.to_string()
+    }
+}
+
+impl Display for For {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(label) = &self.label {
+            write!(f, "'{}: ", label)?;
+        }
+
+        let variables: Vec<String> = self.variables.iter().map(|v| v.to_string()).collect();
+
+        write!(
+            f,
+            "{} ({} in {}) {{ {} }}",
+            self.token_literal(),
+            variables.join(", "),
+            self.iterable,
+            self.body
+        )
+    }
+}
+
+/// `break` leaves the innermost loop, or with a label the loop it names.
+#[derive(Debug, Clone)]
+pub struct Break {
+    pub token: Token,
+    pub label: Option<String>,
+}
+
+impl NodeInterface for Break {
+    fn token_literal(&self) -> String {
+        self.token.to_string()
+    }
+}
+
+impl Display for Break {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} '{};", self.token_literal(), label),
+            None => write!(f, "{};", self.token_literal()),
+        }
+    }
+}
+
+/// `continue` starts the next iteration of the innermost loop, or with a
+/// label of the loop it names.
+#[derive(Debug, Clone)]
+pub struct Continue {
+    pub token: Token,
+    pub label: Option<String>,
+}
+
+impl NodeInterface for Continue {
+    fn token_literal(&self) -> String {
+        self.token.to_string()
+    }
+}
+
+impl Display for Continue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} '{};", self.token_literal(), label),
+            None => write!(f, "{};", self.token_literal()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Return {
     pub token: Token,
     pub return_value: Option<expression::Expression>,
@@ -123,7 +333,7 @@ impl Display for Return {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Expression {
     pub token: Token,
     pub expression: expression::Expression,
@@ -137,11 +347,11 @@ impl NodeInterface for Expression {
 
 impl Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.expression.to_string())
+        write!(f, "{}", self.expression)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Block {
     pub token: Token,
     pub statements: Vec<Statement>,
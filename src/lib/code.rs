@@ -0,0 +1,333 @@
+use crate::token::Token;
+
+pub type Instructions = Vec<u8>;
+
+/// Bits of the `OpRange` operand: which bounds were pushed, and whether the
+/// end is inclusive.
+pub const RANGE_START: usize = 1;
+pub const RANGE_END: usize = 2;
+pub const RANGE_INCLUSIVE: usize = 4;
+
+/// Bits of the `OpUnpack` flags operand: whether an array rather than a tuple
+/// is expected, and whether a rest pattern takes the remaining elements.
+pub const UNPACK_ARRAY: usize = 1;
+pub const UNPACK_REST: usize = 2;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Opcode {
+    Constant,
+    Pop,
+
+    Add,
+    Sub,
+    Mul,
+    Div,
+
+    True,
+    False,
+    Null,
+
+    Equal,
+    NotEqual,
+    GreaterThan,
+
+    Minus,
+    Bang,
+
+    JumpNotTruthy,
+    Jump,
+
+    GetGlobal,
+    SetGlobal,
+    GetLocal,
+    SetLocal,
+    GetFree,
+
+    Call,
+    TailCall,
+    ReturnValue,
+    Return,
+    Closure,
+    CurrentClosure,
+
+    GetNative,
+
+    Array,
+    Index,
+    Hash,
+
+    JumpNull,
+    JumpNotNull,
+    Member,
+
+    Range,
+
+    Tuple,
+    Unpack,
+
+    Struct,
+
+    GreaterEqual,
+    JumpTruthy,
+    JumpFalsy,
+
+    Mod,
+    Pow,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    BitNot,
+
+    Dup,
+    SetIndex,
+    SetMember,
+    Iter,
+    IterNext,
+    Match,
+    NoMatch,
+    ArrayExtend,
+    Dup2,
+    Bury,
+    Cell,
+    Deref,
+    SetCell,
+}
+
+const OPCODES: [Opcode; 62] = [
+    Opcode::Constant,
+    Opcode::Pop,
+    Opcode::Add,
+    Opcode::Sub,
+    Opcode::Mul,
+    Opcode::Div,
+    Opcode::True,
+    Opcode::False,
+    Opcode::Null,
+    Opcode::Equal,
+    Opcode::NotEqual,
+    Opcode::GreaterThan,
+    Opcode::Minus,
+    Opcode::Bang,
+    Opcode::JumpNotTruthy,
+    Opcode::Jump,
+    Opcode::GetGlobal,
+    Opcode::SetGlobal,
+    Opcode::GetLocal,
+    Opcode::SetLocal,
+    Opcode::GetFree,
+    Opcode::Call,
+    Opcode::TailCall,
+    Opcode::ReturnValue,
+    Opcode::Return,
+    Opcode::Closure,
+    Opcode::CurrentClosure,
+    Opcode::GetNative,
+    Opcode::Array,
+    Opcode::Index,
+    Opcode::Hash,
+    Opcode::JumpNull,
+    Opcode::JumpNotNull,
+    Opcode::Member,
+    Opcode::Range,
+    Opcode::Tuple,
+    Opcode::Unpack,
+    Opcode::Struct,
+    Opcode::GreaterEqual,
+    Opcode::JumpTruthy,
+    Opcode::JumpFalsy,
+    Opcode::Mod,
+    Opcode::Pow,
+    Opcode::BitAnd,
+    Opcode::BitOr,
+    Opcode::BitXor,
+    Opcode::ShiftLeft,
+    Opcode::ShiftRight,
+    Opcode::BitNot,
+    Opcode::Dup,
+    Opcode::SetIndex,
+    Opcode::SetMember,
+    Opcode::Iter,
+    Opcode::IterNext,
+    Opcode::Match,
+    Opcode::NoMatch,
+    Opcode::ArrayExtend,
+    Opcode::Dup2,
+    Opcode::Bury,
+    Opcode::Cell,
+    Opcode::Deref,
+    Opcode::SetCell,
+];
+
+pub struct Definition {
+    pub name: &'static str,
+    pub operand_widths: &'static [usize],
+}
+
+impl Opcode {
+    pub fn from_byte(byte: u8) -> Option<Opcode> {
+        OPCODES.get(byte as usize).copied()
+    }
+
+    pub fn definition(&self) -> Definition {
+        let (name, operand_widths): (&'static str, &'static [usize]) = match self {
+            Self::Constant => ("OpConstant", &[2]),
+            Self::Pop => ("OpPop", &[]),
+            Self::Add => ("OpAdd", &[]),
+            Self::Sub => ("OpSub", &[]),
+            Self::Mul => ("OpMul", &[]),
+            Self::Div => ("OpDiv", &[]),
+            Self::True => ("OpTrue", &[]),
+            Self::False => ("OpFalse", &[]),
+            Self::Null => ("OpNull", &[]),
+            Self::Equal => ("OpEqual", &[]),
+            Self::NotEqual => ("OpNotEqual", &[]),
+            Self::GreaterThan => ("OpGreaterThan", &[]),
+            Self::Minus => ("OpMinus", &[]),
+            Self::Bang => ("OpBang", &[]),
+            Self::JumpNotTruthy => ("OpJumpNotTruthy", &[2]),
+            Self::Jump => ("OpJump", &[2]),
+            Self::GetGlobal => ("OpGetGlobal", &[2]),
+            Self::SetGlobal => ("OpSetGlobal", &[2]),
+            Self::GetLocal => ("OpGetLocal", &[1]),
+            Self::SetLocal => ("OpSetLocal", &[1]),
+            Self::GetFree => ("OpGetFree", &[1]),
+            Self::Call => ("OpCall", &[1]),
+            Self::TailCall => ("OpTailCall", &[1]),
+            Self::ReturnValue => ("OpReturnValue", &[]),
+            Self::Return => ("OpReturn", &[]),
+            Self::Closure => ("OpClosure", &[2, 1]),
+            Self::CurrentClosure => ("OpCurrentClosure", &[]),
+            Self::GetNative => ("OpGetNative", &[1]),
+            Self::Array => ("OpArray", &[2]),
+            Self::Index => ("OpIndex", &[]),
+            Self::Hash => ("OpHash", &[2]),
+            Self::JumpNull => ("OpJumpNull", &[2]),
+            Self::JumpNotNull => ("OpJumpNotNull", &[2]),
+            Self::Member => ("OpMember", &[]),
+            Self::Range => ("OpRange", &[1]),
+            Self::Tuple => ("OpTuple", &[2]),
+            Self::Unpack => ("OpUnpack", &[2, 1]),
+            Self::Struct => ("OpStruct", &[2]),
+            Self::GreaterEqual => ("OpGreaterEqual", &[]),
+            Self::JumpTruthy => ("OpJumpTruthy", &[2]),
+            Self::JumpFalsy => ("OpJumpFalsy", &[2]),
+            Self::Mod => ("OpMod", &[]),
+            Self::Pow => ("OpPow", &[]),
+            Self::BitAnd => ("OpBitAnd", &[]),
+            Self::BitOr => ("OpBitOr", &[]),
+            Self::BitXor => ("OpBitXor", &[]),
+            Self::ShiftLeft => ("OpShiftLeft", &[]),
+            Self::ShiftRight => ("OpShiftRight", &[]),
+            Self::BitNot => ("OpBitNot", &[]),
+            Self::Dup => ("OpDup", &[]),
+            Self::SetIndex => ("OpSetIndex", &[]),
+            Self::SetMember => ("OpSetMember", &[]),
+            Self::Iter => ("OpIter", &[]),
+            Self::IterNext => ("OpIterNext", &[2, 1]),
+            Self::Match => ("OpMatch", &[2, 2]),
+            Self::NoMatch => ("OpNoMatch", &[]),
+            Self::ArrayExtend => ("OpArrayExtend", &[2]),
+            Self::Dup2 => ("OpDup2", &[]),
+            Self::Bury => ("OpBury", &[1]),
+            Self::Cell => ("OpCell", &[]),
+            Self::Deref => ("OpDeref", &[]),
+            Self::SetCell => ("OpSetCell", &[]),
+        };
+
+        Definition {
+            name,
+            operand_widths,
+        }
+    }
+}
+
+/// Encodes an instruction. Panics if an operand does not fit its width; the
+/// compiler uses `try_make` to report that instead.
+pub fn make(op: Opcode, operands: &[usize]) -> Instructions {
+    try_make(op, operands).unwrap_or_else(|err| panic!("{}", err))
+}
+
+pub fn try_make(op: Opcode, operands: &[usize]) -> Result<Instructions, String> {
+    let def = op.definition();
+
+    let mut instruction = vec![op as u8];
+
+    for (operand, width) in operands.iter().zip(def.operand_widths.iter()) {
+        match width {
+            2 => match u16::try_from(*operand) {
+                Ok(operand) => instruction.extend_from_slice(&operand.to_be_bytes()),
+                Err(_) => return Err(operand_too_large(def.name, *operand, u16::MAX)),
+            },
+            1 => match u8::try_from(*operand) {
+                Ok(operand) => instruction.push(operand),
+                Err(_) => return Err(operand_too_large(def.name, *operand, u8::MAX.into())),
+            },
+            _ => (),
+        }
+    }
+
+    Ok(instruction)
+}
+
+fn operand_too_large(name: &str, operand: usize, max: u16) -> String {
+    format!(
+        "operand {} of {} is larger than the limit of {}",
+        operand, name, max
+    )
+}
+
+pub fn read_operands(def: &Definition, ins: &[u8]) -> (Vec<usize>, usize) {
+    let mut operands = Vec::with_capacity(def.operand_widths.len());
+    let mut offset = 0;
+
+    for width in def.operand_widths.iter() {
+        match width {
+            2 => operands.push(read_u16(&ins[offset..]) as usize),
+            1 => operands.push(ins[offset] as usize),
+            _ => (),
+        }
+
+        offset += width;
+    }
+
+    (operands, offset)
+}
+
+pub fn read_u16(ins: &[u8]) -> u16 {
+    u16::from_be_bytes([ins[0], ins[1]])
+}
+
+/// Maps instruction offsets back to the token each instruction was compiled
+/// from. Entries are kept sorted by offset.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    entries: Vec<(usize, Token)>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, offset: usize, token: Token) {
+        self.entries.push((offset, token));
+    }
+
+    pub fn token_at(&self, offset: usize) -> Option<&Token> {
+        self.entries
+            .binary_search_by_key(&offset, |(o, _)| *o)
+            .ok()
+            .map(|i| &self.entries[i].1)
+    }
+
+    pub fn truncate(&mut self, offset: usize) {
+        self.entries.retain(|(o, _)| *o < offset);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,987 @@
+pub mod symbol_table;
+
+use crate::ast::expression::{self, Expression};
+use crate::ast::pattern::Pattern;
+use crate::ast::statement::{self, Statement};
+use crate::ast::Program;
+use crate::code::{self, Instructions, Opcode, SourceMap};
+use crate::object::environment::AssignError;
+use crate::object::native::NativeRegistry;
+use crate::object::{CompiledFunction, Matcher, Object};
+use crate::token::Token;
+use std::rc::Rc;
+use symbol_table::{Symbol, SymbolScope, SymbolTable};
+
+pub type CompilerError<T> = Result<T, String>;
+
+/// How many elements of an array literal are pushed before they are
+/// gathered into the array, well within the VM's stack.
+const ARRAY_CHUNK_LEN: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct Bytecode {
+    pub instructions: Instructions,
+    pub constants: Vec<Object>,
+    pub source_map: SourceMap,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct EmittedInstruction {
+    opcode: Opcode,
+    position: usize,
+}
+
+/// A loop being compiled: where `continue` jumps to, and the `break` jumps
+/// to point at its end once that is known. A `for` loop keeps its iterator
+/// on the stack, which jumps out of it have to drop.
+#[derive(Debug)]
+struct Loop {
+    label: Option<String>,
+    start: usize,
+    breaks: Vec<usize>,
+    iterator: bool,
+}
+
+#[derive(Debug, Default)]
+struct CompilationScope {
+    instructions: Instructions,
+    source_map: SourceMap,
+    last_instruction: Option<EmittedInstruction>,
+    previous_instruction: Option<EmittedInstruction>,
+    loops: Vec<Loop>,
+}
+
+#[derive(Debug)]
+pub struct Compiler {
+    constants: Vec<Object>,
+    symbol_table: SymbolTable,
+    scopes: Vec<CompilationScope>,
+    current_token: Option<Token>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self::new_with_state(SymbolTable::new(), vec![])
+    }
+
+    pub fn new_with_state(symbol_table: SymbolTable, constants: Vec<Object>) -> Self {
+        Self {
+            constants,
+            symbol_table,
+            scopes: vec![CompilationScope::default()],
+            current_token: None,
+        }
+    }
+
+    /// Makes the registry's natives resolvable by name. The VM running the
+    /// result must be given the same registry.
+    pub fn register_natives(&mut self, natives: &NativeRegistry) {
+        for (index, native) in natives.iter().enumerate() {
+            self.symbol_table.define_native(index, &native.name);
+        }
+    }
+
+    pub fn compile(&mut self, program: &Program) -> CompilerError<()> {
+        for stmt in program.statements.iter() {
+            self.compile_statement(stmt)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn bytecode(&self) -> Bytecode {
+        Bytecode {
+            instructions: self.current_instructions().clone(),
+            constants: self.constants.clone(),
+            source_map: self.current_scope().source_map.clone(),
+        }
+    }
+
+    pub fn into_state(self) -> (SymbolTable, Vec<Object>) {
+        (self.symbol_table, self.constants)
+    }
+
+    fn compile_statement(&mut self, stmt: &Statement) -> CompilerError<()> {
+        let outer = self.current_token.replace(stmt.token().clone());
+        let result = self.compile_statement_node(stmt);
+        self.current_token = outer;
+
+        result
+    }
+
+    fn compile_statement_node(&mut self, stmt: &Statement) -> CompilerError<()> {
+        match stmt {
+            Statement::Expression(stmt) => {
+                self.compile_expression(&stmt.expression)?;
+                self.emit(Opcode::Pop, &[])?;
+            }
+            Statement::Block(block) => {
+                for stmt in block.statements.iter() {
+                    self.compile_statement(stmt)?;
+                }
+            }
+            // Functions see their own binding so that `let f = fn() { f() }`
+            // can recurse; any other value is compiled before the names are
+            // defined so `let a = a + 1` still reads the previous binding.
+            Statement::Let(stmt) => match (&stmt.name, &stmt.value) {
+                (Pattern::Identifier(ident), Some(Expression::FunctionLiteral(function)))
+                    if !stmt.mutable =>
+                {
+                    let symbol = self.symbol_table.define(&ident.value);
+                    self.compile_function(function, Some(&ident.value))?;
+                    self.store_symbol(&symbol)?;
+                }
+                // The body refers to the variable rather than to the function
+                // itself, so it sees the variable reassigned. A cell must exist
+                // before the function can capture it.
+                (Pattern::Identifier(ident), Some(Expression::FunctionLiteral(function))) => {
+                    let symbol = self.symbol_table.define_mutable(&ident.value);
+                    match symbol.cell {
+                        true => {
+                            self.emit(Opcode::Null, &[])?;
+                            self.store_symbol(&symbol)?;
+                            self.compile_function(function, None)?;
+                            self.load_symbol(&symbol)?;
+                            self.emit(Opcode::SetCell, &[])?;
+                        }
+                        false => {
+                            self.compile_function(function, None)?;
+                            self.store_symbol(&symbol)?;
+                        }
+                    }
+                }
+                (pattern, value) => {
+                    match value {
+                        Some(value) => self.compile_expression(value)?,
+                        None => {
+                            self.emit(Opcode::Null, &[])?;
+                        }
+                    }
+
+                    self.compile_pattern(pattern, stmt.mutable)?;
+                }
+            },
+            Statement::Struct(stmt) => {
+                let ty = self.add_constant(Object::StructType(Rc::new(stmt.into())));
+                self.emit(Opcode::Constant, &[ty])?;
+
+                let symbol = self.symbol_table.define(&stmt.name.value);
+                self.store_symbol(&symbol)?;
+            }
+            Statement::While(stmt) => {
+                let start = self.current_instructions().len();
+                self.compile_expression(&stmt.condition)?;
+                let exit = self.emit(Opcode::JumpNotTruthy, &[9999])?;
+
+                self.compile_loop_body(stmt.label.clone(), start, false, &stmt.body)?;
+                self.change_operand(exit, self.current_instructions().len())?;
+            }
+            // The loop variables are scoped to the body and bound afresh each
+            // iteration, so each closure keeps its own iteration's values.
+            Statement::For(stmt) => {
+                self.compile_expression(&stmt.iterable)?;
+                self.emit(Opcode::Iter, &[])?;
+
+                let start = self.current_instructions().len();
+                let exit = self.emit(Opcode::IterNext, &[9999, stmt.variables.len()])?;
+
+                self.symbol_table.enter_block();
+                let result = stmt
+                    .variables
+                    .iter()
+                    .try_for_each(|variable| self.compile_pattern(variable, false))
+                    .and_then(|_| {
+                        self.compile_loop_body(stmt.label.clone(), start, true, &stmt.body)
+                    });
+                self.symbol_table.leave_block();
+                result?;
+
+                self.change_operand(exit, self.current_instructions().len())?;
+            }
+            Statement::Break(stmt) => {
+                let index = self.find_loop(&stmt.label, "break")?;
+                self.pop_iterators(index)?;
+
+                let jump = self.emit(Opcode::Jump, &[9999])?;
+                self.current_scope_mut().loops[index].breaks.push(jump);
+            }
+            Statement::Continue(stmt) => {
+                let index = self.find_loop(&stmt.label, "continue")?;
+                self.pop_iterators(index + 1)?;
+
+                let start = self.current_scope().loops[index].start;
+                self.emit(Opcode::Jump, &[start])?;
+            }
+            Statement::Return(stmt) => {
+                match &stmt.return_value {
+                    Some(value) if self.scopes.len() > 1 => self.compile_tail_expression(value)?,
+                    Some(value) => self.compile_expression(value)?,
+                    None => {
+                        self.emit(Opcode::Null, &[])?;
+                    }
+                }
+
+                self.emit(Opcode::ReturnValue, &[])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compiles the body of a loop starting at `start`, followed by the jump
+    /// back to it, and points its `break`s past that jump.
+    fn compile_loop_body(
+        &mut self,
+        label: Option<String>,
+        start: usize,
+        iterator: bool,
+        body: &statement::Block,
+    ) -> CompilerError<()> {
+        self.current_scope_mut().loops.push(Loop {
+            label,
+            start,
+            breaks: Vec::new(),
+            iterator,
+        });
+        let result = self.compile_body(&body.statements, false);
+        let compiled = self.current_scope_mut().loops.pop();
+        result?;
+
+        self.emit(Opcode::Jump, &[start])?;
+
+        let end = self.current_instructions().len();
+        for jump in compiled.map(|l| l.breaks).unwrap_or_default() {
+            self.change_operand(jump, end)?;
+        }
+
+        Ok(())
+    }
+
+    /// The position among the enclosing loops of the one a `break` or
+    /// `continue` with `label` refers to: the one with that label, or the
+    /// innermost one without a label.
+    fn find_loop(&self, label: &Option<String>, keyword: &str) -> CompilerError<usize> {
+        let loops = &self.current_scope().loops;
+
+        let found = match label {
+            Some(name) => loops.iter().rposition(|l| l.label.as_ref() == Some(name)),
+            None => loops.len().checked_sub(1),
+        };
+
+        found.ok_or(match label {
+            Some(name) => format!("undeclared loop label '{}", name),
+            None => format!("{} outside of a loop", keyword),
+        })
+    }
+
+    /// Drops the iterators of the `for` loops from the `from`th enclosing loop
+    /// inwards, ahead of a jump out of them.
+    fn pop_iterators(&mut self, from: usize) -> CompilerError<()> {
+        let count = self.current_scope().loops[from..]
+            .iter()
+            .filter(|l| l.iterator)
+            .count();
+
+        for _ in 0..count {
+            self.emit(Opcode::Pop, &[])?;
+        }
+
+        Ok(())
+    }
+
+    /// Binds the value on top of the stack to `pattern`. `OpUnpack` leaves
+    /// the first element on top, so elements are bound left to right.
+    fn compile_pattern(&mut self, pattern: &Pattern, mutable: bool) -> CompilerError<()> {
+        match pattern {
+            Pattern::Identifier(ident) => {
+                let symbol = match mutable {
+                    true => self.symbol_table.define_mutable(&ident.value),
+                    false => self.symbol_table.define(&ident.value),
+                };
+                self.store_symbol(&symbol)?;
+            }
+            // The parser only allows literal and hash patterns in `match` arms.
+            Pattern::Wildcard(_) | Pattern::Literal(_) | Pattern::Hash(_) => {
+                self.emit(Opcode::Pop, &[])?;
+            }
+            Pattern::Tuple(tuple) => {
+                self.emit(Opcode::Unpack, &[tuple.elements.len(), 0])?;
+                for element in tuple.elements.iter() {
+                    self.compile_pattern(element, mutable)?;
+                }
+            }
+            Pattern::Array(array) => {
+                let mut flags = code::UNPACK_ARRAY;
+                if array.rest.is_some() {
+                    flags |= code::UNPACK_REST;
+                }
+
+                self.emit(Opcode::Unpack, &[array.elements.len(), flags])?;
+                for element in array.elements.iter() {
+                    self.compile_pattern(element, mutable)?;
+                }
+                if let Some(rest) = &array.rest {
+                    self.compile_pattern(rest, mutable)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Binds a newly defined symbol to the value on top of the stack, in a
+    /// new cell if the symbol is held in one.
+    fn store_symbol(&mut self, symbol: &Symbol) -> CompilerError<()> {
+        if symbol.cell {
+            self.emit(Opcode::Cell, &[])?;
+        }
+
+        match symbol.scope {
+            SymbolScope::Global => self.emit(Opcode::SetGlobal, &[symbol.index])?,
+            _ => self.emit(Opcode::SetLocal, &[symbol.index])?,
+        };
+
+        Ok(())
+    }
+
+    fn compile_expression(&mut self, exp: &Expression) -> CompilerError<()> {
+        let outer = self.current_token.replace(exp.token().clone());
+        let result = self.compile_expression_node(exp);
+        self.current_token = outer;
+
+        result
+    }
+
+    fn compile_expression_node(&mut self, exp: &Expression) -> CompilerError<()> {
+        match exp {
+            Expression::IntegerLiteral(int) => {
+                let constant = self.add_constant(Object::Integer(int.value));
+                self.emit(Opcode::Constant, &[constant])?;
+            }
+            Expression::BigIntLiteral(int) => {
+                let constant = self.add_constant(Object::BigInt(int.value.clone()));
+                self.emit(Opcode::Constant, &[constant])?;
+            }
+            Expression::FloatLiteral(float) => {
+                let constant = self.add_constant(Object::Float(float.value));
+                self.emit(Opcode::Constant, &[constant])?;
+            }
+            Expression::StringLiteral(string) => {
+                let constant = self.add_constant(Object::String(string.value.clone()));
+                self.emit(Opcode::Constant, &[constant])?;
+            }
+            Expression::Boolean(boolean) => {
+                match boolean.value {
+                    true => self.emit(Opcode::True, &[])?,
+                    false => self.emit(Opcode::False, &[])?,
+                };
+            }
+            Expression::Identifier(ident) => {
+                let symbol = self
+                    .symbol_table
+                    .resolve(&ident.value)
+                    .ok_or(format!("identifier not found: {}", ident.value))?;
+
+                self.load_symbol(&symbol)?;
+                if symbol.cell {
+                    self.emit(Opcode::Deref, &[])?;
+                }
+            }
+            Expression::Prefix(prefix) => {
+                self.compile_expression(&prefix.right)?;
+
+                match prefix.operator.as_str() {
+                    "!" => self.emit(Opcode::Bang, &[])?,
+                    "-" => self.emit(Opcode::Minus, &[])?,
+                    "~" => self.emit(Opcode::BitNot, &[])?,
+                    op => return Err(format!("unknown operator {}", op)),
+                };
+            }
+            Expression::Infix(infix) => self.compile_infix_expression(infix)?,
+            Expression::Assign(assign) => {
+                let slots = self.compile_target(&assign.target)?;
+                match assign.compound_operator() {
+                    Some(operator) => {
+                        self.compile_target_value(&assign.target)?;
+                        self.compile_expression(&assign.value)?;
+                        self.emit_binary_operator(operator)?;
+                    }
+                    None => self.compile_expression(&assign.value)?,
+                }
+
+                // The copy left behind is the value of the assignment.
+                self.emit(Opcode::Dup, &[])?;
+                if slots > 0 {
+                    self.emit(Opcode::Bury, &[slots + 1])?;
+                }
+                self.compile_store(&assign.target)?;
+            }
+            Expression::If(if_exp) => self.compile_if_expression(if_exp, false)?,
+            Expression::Match(match_exp) => self.compile_match_expression(match_exp)?,
+            Expression::FunctionLiteral(function) => self.compile_function(function, None)?,
+            Expression::Call(call) => self.compile_call_expression(call, Opcode::Call)?,
+            // Long literals are built a chunk at a time so the elements
+            // never need more than a chunk of stack space.
+            Expression::ArrayLiteral(array) => {
+                let mut chunks = array.elements.chunks(ARRAY_CHUNK_LEN);
+
+                let first = chunks.next().unwrap_or_default();
+                for element in first {
+                    self.compile_expression(element)?;
+                }
+                self.emit(Opcode::Array, &[first.len()])?;
+
+                for chunk in chunks {
+                    for element in chunk {
+                        self.compile_expression(element)?;
+                    }
+                    self.emit(Opcode::ArrayExtend, &[chunk.len()])?;
+                }
+            }
+            Expression::TupleLiteral(tuple) => {
+                for element in tuple.elements.iter() {
+                    self.compile_expression(element)?;
+                }
+
+                self.emit(Opcode::Tuple, &[tuple.elements.len()])?;
+            }
+            // The struct type is pushed first, then each field's name and value.
+            Expression::StructLiteral(literal) => {
+                self.compile_expression(&Expression::Identifier(literal.name.clone()))?;
+
+                for (name, value) in literal.fields.iter() {
+                    let name = self.add_constant(Object::String(name.value.clone()));
+                    self.emit(Opcode::Constant, &[name])?;
+                    self.compile_expression(value)?;
+                }
+
+                self.emit(Opcode::Struct, &[literal.fields.len() * 2])?;
+            }
+            Expression::HashLiteral(hash) => {
+                for (key, value) in hash.pairs.iter() {
+                    self.compile_expression(key)?;
+                    self.compile_expression(value)?;
+                }
+
+                self.emit(Opcode::Hash, &[hash.pairs.len() * 2])?;
+            }
+            Expression::Index(index) => {
+                self.compile_expression(&index.left)?;
+                self.compile_expression(&index.index)?;
+                self.emit(Opcode::Index, &[])?;
+            }
+            Expression::Null(_) => {
+                self.emit(Opcode::Null, &[])?;
+            }
+            Expression::Range(range) => {
+                let mut flags = 0;
+                if let Some(start) = &range.start {
+                    self.compile_expression(start)?;
+                    flags |= code::RANGE_START;
+                }
+                if let Some(end) = &range.end {
+                    self.compile_expression(end)?;
+                    flags |= code::RANGE_END;
+                }
+                if range.inclusive {
+                    flags |= code::RANGE_INCLUSIVE;
+                }
+
+                self.emit(Opcode::Range, &[flags])?;
+            }
+            // A null left operand is jumped over the lookup and left on the
+            // stack as the result.
+            Expression::OptionalIndex(index) => {
+                self.compile_expression(&index.left)?;
+                let jump_null = self.emit(Opcode::JumpNull, &[9999])?;
+
+                self.compile_expression(&index.index)?;
+                self.emit(Opcode::Index, &[])?;
+
+                let after = self.current_instructions().len();
+                self.change_operand(jump_null, after)?;
+            }
+            Expression::Member(member) => {
+                self.compile_expression(&member.left)?;
+
+                let name = self.add_constant(Object::String(member.member.value.clone()));
+                self.emit(Opcode::Constant, &[name])?;
+                self.emit(Opcode::Member, &[])?;
+            }
+            Expression::OptionalMember(member) => {
+                self.compile_expression(&member.left)?;
+                let jump_null = self.emit(Opcode::JumpNull, &[9999])?;
+
+                let name = self.add_constant(Object::String(member.member.value.clone()));
+                self.emit(Opcode::Constant, &[name])?;
+                self.emit(Opcode::Member, &[])?;
+
+                let after = self.current_instructions().len();
+                self.change_operand(jump_null, after)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compiles an expression whose value is returned from the enclosing
+    /// function: a call becomes `OpTailCall`, and an `if` passes tail position
+    /// on to the last statement of both branches.
+    fn compile_tail_expression(&mut self, exp: &Expression) -> CompilerError<()> {
+        let outer = self.current_token.replace(exp.token().clone());
+
+        let result = match exp {
+            Expression::Call(call) => self.compile_call_expression(call, Opcode::TailCall),
+            Expression::If(if_exp) => self.compile_if_expression(if_exp, true),
+            _ => self.compile_expression_node(exp),
+        };
+
+        self.current_token = outer;
+        result
+    }
+
+    fn compile_call_expression(
+        &mut self,
+        call: &expression::Call,
+        op: Opcode,
+    ) -> CompilerError<()> {
+        self.compile_expression(&call.function)?;
+
+        for arg in call.arguments.iter() {
+            self.compile_expression(arg)?;
+        }
+
+        self.emit(op, &[call.arguments.len()])?;
+
+        Ok(())
+    }
+
+    fn compile_infix_expression(&mut self, infix: &expression::Infix) -> CompilerError<()> {
+        // The left operand is kept as the result if it decides it, otherwise
+        // the right one is evaluated in its place.
+        let short_circuit = match infix.operator.as_str() {
+            "??" => Some(Opcode::JumpNotNull),
+            "||" => Some(Opcode::JumpTruthy),
+            "&&" => Some(Opcode::JumpFalsy),
+            _ => None,
+        };
+        if let Some(op) = short_circuit {
+            self.compile_expression(&infix.left)?;
+            let jump = self.emit(op, &[9999])?;
+
+            self.compile_expression(&infix.right)?;
+
+            let after = self.current_instructions().len();
+            self.change_operand(jump, after)?;
+            return Ok(());
+        }
+
+        // There are no less-than instructions, `a < b` is compiled as `b > a`.
+        let flipped = match infix.operator.as_str() {
+            "<" => Some(Opcode::GreaterThan),
+            "<=" => Some(Opcode::GreaterEqual),
+            _ => None,
+        };
+        if let Some(op) = flipped {
+            self.compile_expression(&infix.right)?;
+            self.compile_expression(&infix.left)?;
+            self.emit(op, &[])?;
+            return Ok(());
+        }
+
+        self.compile_expression(&infix.left)?;
+        self.compile_expression(&infix.right)?;
+
+        self.emit_binary_operator(&infix.operator)
+    }
+
+    fn emit_binary_operator(&mut self, operator: &str) -> CompilerError<()> {
+        match operator {
+            "+" => self.emit(Opcode::Add, &[])?,
+            "-" => self.emit(Opcode::Sub, &[])?,
+            "*" => self.emit(Opcode::Mul, &[])?,
+            "/" => self.emit(Opcode::Div, &[])?,
+            "%" => self.emit(Opcode::Mod, &[])?,
+            "**" => self.emit(Opcode::Pow, &[])?,
+            "&" => self.emit(Opcode::BitAnd, &[])?,
+            "|" => self.emit(Opcode::BitOr, &[])?,
+            "^" => self.emit(Opcode::BitXor, &[])?,
+            "<<" => self.emit(Opcode::ShiftLeft, &[])?,
+            ">>" => self.emit(Opcode::ShiftRight, &[])?,
+            ">" => self.emit(Opcode::GreaterThan, &[])?,
+            ">=" => self.emit(Opcode::GreaterEqual, &[])?,
+            "==" => self.emit(Opcode::Equal, &[])?,
+            "!=" => self.emit(Opcode::NotEqual, &[])?,
+            op => return Err(format!("unknown operator {}", op)),
+        };
+
+        Ok(())
+    }
+
+    /// Pushes the container and key of each element an assignment target goes
+    /// through, outermost first, so each is evaluated once. Returns how many
+    /// values that leaves on the stack.
+    fn compile_target(&mut self, target: &Expression) -> CompilerError<usize> {
+        match target {
+            Expression::Identifier(_) => Ok(0),
+            Expression::Index(index) => {
+                let slots = self.compile_target(&index.left)?;
+                self.compile_target_value(&index.left)?;
+                self.compile_expression(&index.index)?;
+                Ok(slots + 2)
+            }
+            Expression::Member(member) => {
+                let slots = self.compile_target(&member.left)?;
+                self.compile_target_value(&member.left)?;
+
+                let name = self.add_constant(Object::String(member.member.value.clone()));
+                self.emit(Opcode::Constant, &[name])?;
+                Ok(slots + 2)
+            }
+            target => Err(format!("invalid assignment target: {}", target)),
+        }
+    }
+
+    /// Pushes the current value of a target `compile_target` has pushed.
+    fn compile_target_value(&mut self, target: &Expression) -> CompilerError<()> {
+        match target {
+            Expression::Index(_) => {
+                self.emit(Opcode::Dup2, &[])?;
+                self.emit(Opcode::Index, &[])?;
+            }
+            Expression::Member(_) => {
+                self.emit(Opcode::Dup2, &[])?;
+                self.emit(Opcode::Member, &[])?;
+            }
+            target => self.compile_expression(target)?,
+        }
+
+        Ok(())
+    }
+
+    /// Stores the value on top of the stack in an assignment target whose
+    /// containers and keys `compile_target` left under it. An element is set
+    /// by `OpSetIndex` or `OpSetMember`, which leave an updated copy of the
+    /// container to be stored in turn in the container's target.
+    fn compile_store(&mut self, target: &Expression) -> CompilerError<()> {
+        match target {
+            Expression::Identifier(ident) => {
+                let symbol = self.symbol_table.resolve(&ident.value).ok_or(format!(
+                    "{} {} at {}",
+                    AssignError::Undefined,
+                    ident.value,
+                    ident.token.position
+                ))?;
+
+                // Only globals outside any block are mutable without a cell.
+                match (symbol.mutable, symbol.cell) {
+                    (true, true) => {
+                        self.load_symbol(&symbol)?;
+                        self.emit(Opcode::SetCell, &[])?;
+                    }
+                    (true, false) => {
+                        self.emit(Opcode::SetGlobal, &[symbol.index])?;
+                    }
+                    _ => {
+                        return Err(format!(
+                            "{} {} at {}",
+                            AssignError::Immutable,
+                            ident.value,
+                            ident.token.position
+                        ))
+                    }
+                }
+            }
+            Expression::Index(index) => {
+                self.emit(Opcode::Bury, &[2])?;
+                self.emit(Opcode::SetIndex, &[])?;
+                self.compile_store(&index.left)?;
+            }
+            Expression::Member(member) => {
+                self.emit(Opcode::Bury, &[2])?;
+                self.emit(Opcode::SetMember, &[])?;
+                self.compile_store(&member.left)?;
+            }
+            target => return Err(format!("invalid assignment target: {}", target)),
+        }
+
+        Ok(())
+    }
+
+    /// The subject stays on the stack while arms are tried in turn, and is
+    /// dropped once one is chosen. Each arm's bindings are scoped to it.
+    fn compile_match_expression(&mut self, match_exp: &expression::Match) -> CompilerError<()> {
+        self.compile_expression(&match_exp.subject)?;
+
+        let mut ends = Vec::new();
+        for arm in match_exp.arms.iter() {
+            self.symbol_table.enter_block();
+            let result = self.compile_match_arm(arm);
+            self.symbol_table.leave_block();
+            let next_arm = result?;
+
+            ends.push(self.emit(Opcode::Jump, &[9999])?);
+
+            let next = self.current_instructions().len();
+            for jump in next_arm {
+                self.change_operand(jump, next)?;
+            }
+        }
+
+        self.emit(Opcode::NoMatch, &[])?;
+
+        let end = self.current_instructions().len();
+        for jump in ends {
+            self.change_operand(jump, end)?;
+        }
+
+        Ok(())
+    }
+
+    /// Compiles an arm up to its body, returning the jumps to take to the
+    /// next arm when the pattern or guard fails.
+    fn compile_match_arm(&mut self, arm: &expression::MatchArm) -> CompilerError<Vec<usize>> {
+        let matcher = self.add_constant(Object::Matcher(Rc::new(Matcher::from(&arm.pattern))));
+        let mut next_arm = vec![self.emit(Opcode::Match, &[9999, matcher])?];
+
+        for ident in arm.pattern.bindings() {
+            let symbol = self.symbol_table.define(&ident.value);
+            self.store_symbol(&symbol)?;
+        }
+
+        if let Some(guard) = &arm.guard {
+            self.compile_expression(guard)?;
+            next_arm.push(self.emit(Opcode::JumpNotTruthy, &[9999])?);
+        }
+
+        self.emit(Opcode::Pop, &[])?;
+        self.compile_expression(&arm.body)?;
+
+        Ok(next_arm)
+    }
+
+    fn compile_if_expression(&mut self, if_exp: &expression::If, tail: bool) -> CompilerError<()> {
+        self.compile_expression(&if_exp.condition)?;
+
+        // Jump targets are patched once the branches have been emitted.
+        let jump_not_truthy = self.emit(Opcode::JumpNotTruthy, &[9999])?;
+
+        self.compile_block_expression(&if_exp.consequence, tail)?;
+
+        let jump = self.emit(Opcode::Jump, &[9999])?;
+
+        let after_consequence = self.current_instructions().len();
+        self.change_operand(jump_not_truthy, after_consequence)?;
+
+        match &if_exp.alternative {
+            Some(alternative) => self.compile_block_expression(alternative, tail)?,
+            None => {
+                self.emit(Opcode::Null, &[])?;
+            }
+        }
+
+        let after_alternative = self.current_instructions().len();
+        self.change_operand(jump, after_alternative)?;
+
+        Ok(())
+    }
+
+    /// Compiles a block whose value is used, leaving exactly one value on the
+    /// stack: the last expression statement's value, or null.
+    fn compile_block_expression(
+        &mut self,
+        block: &statement::Block,
+        tail: bool,
+    ) -> CompilerError<()> {
+        let start = self.current_instructions().len();
+
+        self.compile_body(&block.statements, tail)?;
+
+        if self.last_instruction_is(Opcode::Pop, start) {
+            self.remove_last_pop();
+        } else {
+            self.emit(Opcode::Null, &[])?;
+        }
+
+        Ok(())
+    }
+
+    fn compile_body(&mut self, statements: &[Statement], tail: bool) -> CompilerError<()> {
+        for (i, stmt) in statements.iter().enumerate() {
+            match stmt {
+                Statement::Expression(exp) if tail && i + 1 == statements.len() => {
+                    let outer = self.current_token.replace(exp.token.clone());
+                    self.compile_tail_expression(&exp.expression)?;
+                    self.emit(Opcode::Pop, &[])?;
+                    self.current_token = outer;
+                }
+                _ => self.compile_statement(stmt)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compile_function(
+        &mut self,
+        function: &expression::FunctionLiteral,
+        name: Option<&str>,
+    ) -> CompilerError<()> {
+        self.enter_scope();
+
+        if let Some(name) = name {
+            self.symbol_table.define_function_name(name);
+        }
+
+        for param in function.parameters.iter() {
+            self.symbol_table.define(&param.value);
+        }
+
+        self.compile_body(&function.body.statements, true)?;
+
+        if self.last_instruction_is(Opcode::Pop, 0) {
+            self.replace_last_pop_with_return();
+        }
+        if !self.last_instruction_is(Opcode::ReturnValue, 0) {
+            self.emit(Opcode::Return, &[])?;
+        }
+
+        let free_symbols = self.symbol_table.free_symbols.clone();
+        let num_locals = self.symbol_table.num_definitions;
+        let scope = self.leave_scope();
+
+        for symbol in free_symbols.iter() {
+            self.load_symbol(symbol)?;
+        }
+
+        let compiled = CompiledFunction {
+            instructions: scope.instructions,
+            num_locals,
+            num_parameters: function.parameters.len(),
+            source_map: scope.source_map,
+        };
+
+        let constant = self.add_constant(Object::CompiledFunction(Rc::new(compiled)));
+        self.emit(Opcode::Closure, &[constant, free_symbols.len()])?;
+
+        Ok(())
+    }
+
+    /// Pushes what the symbol's slot holds: its cell, for a symbol held in
+    /// one, which is what closures capture.
+    fn load_symbol(&mut self, symbol: &Symbol) -> CompilerError<()> {
+        match symbol.scope {
+            SymbolScope::Global => self.emit(Opcode::GetGlobal, &[symbol.index])?,
+            SymbolScope::Local => self.emit(Opcode::GetLocal, &[symbol.index])?,
+            SymbolScope::Free => self.emit(Opcode::GetFree, &[symbol.index])?,
+            SymbolScope::Function => self.emit(Opcode::CurrentClosure, &[])?,
+            SymbolScope::Native => self.emit(Opcode::GetNative, &[symbol.index])?,
+        };
+
+        Ok(())
+    }
+
+    fn add_constant(&mut self, obj: Object) -> usize {
+        self.constants.push(obj);
+        self.constants.len() - 1
+    }
+
+    fn emit(&mut self, op: Opcode, operands: &[usize]) -> CompilerError<usize> {
+        let ins = code::try_make(op, operands)?;
+        let position = self.add_instruction(ins);
+
+        let scope = self.current_scope_mut();
+        scope.previous_instruction = scope.last_instruction;
+        scope.last_instruction = Some(EmittedInstruction {
+            opcode: op,
+            position,
+        });
+
+        Ok(position)
+    }
+
+    fn add_instruction(&mut self, ins: Instructions) -> usize {
+        let token = self.current_token.clone();
+        let scope = self.current_scope_mut();
+        let position = scope.instructions.len();
+        scope.instructions.extend(ins);
+
+        if let Some(token) = token {
+            scope.source_map.add(position, token);
+        }
+
+        position
+    }
+
+    /// Reports whether the last emitted instruction is `op` and was emitted at
+    /// or after `since`.
+    fn last_instruction_is(&self, op: Opcode, since: usize) -> bool {
+        match self.current_scope().last_instruction {
+            Some(last) => last.opcode == op && last.position >= since,
+            None => false,
+        }
+    }
+
+    fn remove_last_pop(&mut self) {
+        let scope = self.current_scope_mut();
+
+        if let Some(last) = scope.last_instruction {
+            scope.instructions.truncate(last.position);
+            scope.source_map.truncate(last.position);
+            scope.last_instruction = scope.previous_instruction;
+        }
+    }
+
+    fn replace_last_pop_with_return(&mut self) {
+        let scope = self.current_scope_mut();
+
+        if let Some(last) = scope.last_instruction.as_mut() {
+            scope.instructions[last.position] = Opcode::ReturnValue as u8;
+            last.opcode = Opcode::ReturnValue;
+        }
+    }
+
+    fn change_operand(&mut self, position: usize, operand: usize) -> CompilerError<()> {
+        let instructions = &mut self.current_scope_mut().instructions;
+        let op = Opcode::from_byte(instructions[position]).expect("patching a known opcode");
+        let new_instruction = code::try_make(op, &[operand])?;
+
+        instructions[position..position + new_instruction.len()].copy_from_slice(&new_instruction);
+
+        Ok(())
+    }
+
+    fn current_scope(&self) -> &CompilationScope {
+        self.scopes.last().expect("compiler always has a scope")
+    }
+
+    fn current_scope_mut(&mut self) -> &mut CompilationScope {
+        self.scopes.last_mut().expect("compiler always has a scope")
+    }
+
+    fn current_instructions(&self) -> &Instructions {
+        &self.current_scope().instructions
+    }
+
+    fn enter_scope(&mut self) {
+        self.scopes.push(CompilationScope::default());
+
+        let outer = std::mem::take(&mut self.symbol_table);
+        self.symbol_table = SymbolTable::new_enclosed(outer);
+    }
+
+    fn leave_scope(&mut self) -> CompilationScope {
+        let scope = self.scopes.pop().expect("compiler always has a scope");
+
+        let table = std::mem::take(&mut self.symbol_table);
+        self.symbol_table = table.outer.map(|outer| *outer).unwrap_or_default();
+
+        scope
+    }
+}
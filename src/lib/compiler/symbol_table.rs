@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolScope {
+    Global,
+    Local,
+    Free,
+    Function,
+    Native,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub scope: SymbolScope,
+    pub index: usize,
+    pub mutable: bool,
+    /// Whether the variable is held in an `Object::Cell`, so that closures
+    /// capturing it share it. Mutable locals are, and so are mutable globals
+    /// defined in a block, which closures capture like locals.
+    pub cell: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    pub outer: Option<Box<SymbolTable>>,
+    pub free_symbols: Vec<Symbol>,
+    pub num_definitions: usize,
+
+    store: HashMap<String, Symbol>,
+    // The bindings each open block has shadowed, to restore when it ends.
+    blocks: Vec<Vec<(String, Option<Symbol>)>>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_enclosed(outer: SymbolTable) -> Self {
+        Self {
+            outer: Some(Box::new(outer)),
+            ..Self::default()
+        }
+    }
+
+    pub fn define(&mut self, name: &str) -> Symbol {
+        self.define_binding(name, false)
+    }
+
+    /// Defines a name that assignments may rebind, from a `let mut`.
+    pub fn define_mutable(&mut self, name: &str) -> Symbol {
+        self.define_binding(name, true)
+    }
+
+    fn define_binding(&mut self, name: &str, mutable: bool) -> Symbol {
+        let scope = match self.outer {
+            Some(_) => SymbolScope::Local,
+            None => SymbolScope::Global,
+        };
+
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope,
+            index: self.num_definitions,
+            mutable,
+            cell: mutable && (scope == SymbolScope::Local || !self.blocks.is_empty()),
+        };
+
+        let shadowed = self.store.insert(name.to_string(), symbol.clone());
+        if let Some(block) = self.blocks.last_mut() {
+            block.push((name.to_string(), shadowed));
+        }
+        self.num_definitions += 1;
+
+        symbol
+    }
+
+    /// Starts a block, such as the body of a `for` loop, whose definitions go
+    /// out of scope again at `leave_block`. Their slots are not reused.
+    pub fn enter_block(&mut self) {
+        self.blocks.push(Vec::new());
+    }
+
+    pub fn leave_block(&mut self) {
+        let block = self.blocks.pop().unwrap_or_default();
+
+        for (name, shadowed) in block.into_iter().rev() {
+            match shadowed {
+                Some(symbol) => self.store.insert(name, symbol),
+                None => self.store.remove(&name),
+            };
+        }
+    }
+
+    pub fn define_function_name(&mut self, name: &str) -> Symbol {
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope: SymbolScope::Function,
+            index: 0,
+            mutable: false,
+            cell: false,
+        };
+
+        self.store.insert(name.to_string(), symbol.clone());
+
+        symbol
+    }
+
+    pub fn define_native(&mut self, index: usize, name: &str) -> Symbol {
+        let symbol = Symbol {
+            name: name.to_string(),
+            scope: SymbolScope::Native,
+            index,
+            mutable: false,
+            cell: false,
+        };
+
+        self.store.insert(name.to_string(), symbol.clone());
+
+        symbol
+    }
+
+    pub fn resolve(&mut self, name: &str) -> Option<Symbol> {
+        if let Some(symbol) = self.store.get(name) {
+            return Some(symbol.clone());
+        }
+
+        let outer = self.outer.as_mut()?;
+        let symbol = outer.resolve(name)?;
+
+        // A global defined in a block is captured like a local, since its
+        // slot is overwritten each time the block runs again.
+        match symbol.scope {
+            SymbolScope::Global if !outer.in_block(name) => Some(symbol),
+            SymbolScope::Native => Some(symbol),
+            _ => Some(self.define_free(symbol)),
+        }
+    }
+
+    fn in_block(&self, name: &str) -> bool {
+        self.blocks
+            .iter()
+            .flatten()
+            .any(|(defined, _)| defined == name)
+    }
+
+    fn define_free(&mut self, original: Symbol) -> Symbol {
+        let symbol = Symbol {
+            name: original.name.clone(),
+            scope: SymbolScope::Free,
+            index: self.free_symbols.len(),
+            mutable: original.mutable,
+            cell: original.cell,
+        };
+
+        self.free_symbols.push(original);
+        self.store.insert(symbol.name.clone(), symbol.clone());
+
+        symbol
+    }
+}
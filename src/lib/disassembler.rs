@@ -0,0 +1,143 @@
+use crate::code::{self, Opcode, SourceMap};
+use crate::compiler::Bytecode;
+use crate::object::Object;
+use crate::token::Token;
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedInstruction {
+    pub offset: usize,
+    pub opcode: Opcode,
+    pub operands: Vec<usize>,
+    pub token: Option<Token>,
+}
+
+/// Decodes an instruction stream one instruction at a time, attaching the
+/// token each instruction was compiled from when the source map has one.
+pub fn decode(
+    instructions: &[u8],
+    source_map: &SourceMap,
+) -> Result<Vec<DecodedInstruction>, String> {
+    let mut decoded = Vec::new();
+    let mut offset = 0;
+
+    while offset < instructions.len() {
+        let opcode = Opcode::from_byte(instructions[offset])
+            .ok_or(format!("opcode {} undefined", instructions[offset]))?;
+        let def = opcode.definition();
+
+        let width: usize = def.operand_widths.iter().sum();
+        if offset + 1 + width > instructions.len() {
+            return Err(format!(
+                "truncated operands for {} at {:04}",
+                def.name, offset
+            ));
+        }
+
+        let (operands, read) = code::read_operands(&def, &instructions[offset + 1..]);
+
+        decoded.push(DecodedInstruction {
+            offset,
+            opcode,
+            operands,
+            token: source_map.token_at(offset).cloned(),
+        });
+
+        offset += 1 + read;
+    }
+
+    Ok(decoded)
+}
+
+/// Renders bare instructions, one per line, as `offset name operands`.
+pub fn disassemble_instructions(instructions: &[u8]) -> String {
+    match decode(instructions, &SourceMap::new()) {
+        Ok(decoded) => decoded
+            .iter()
+            .map(|ins| format!("{}\n", format_instruction(ins)))
+            .collect(),
+        Err(err) => format!("ERROR: {}\n", err),
+    }
+}
+
+/// Renders a whole program. Constant operands are resolved against the
+/// constant pool, the functions created by `OpClosure` are listed beneath it,
+/// and every line ends with the source token it came from.
+pub fn disassemble(bytecode: &Bytecode) -> String {
+    let mut out = String::new();
+    write_listing(
+        &mut out,
+        &bytecode.instructions,
+        &bytecode.source_map,
+        &bytecode.constants,
+        0,
+    );
+    out
+}
+
+fn write_listing(
+    out: &mut String,
+    instructions: &[u8],
+    source_map: &SourceMap,
+    constants: &[Object],
+    depth: usize,
+) {
+    let indent = INDENT.repeat(depth);
+
+    let decoded = match decode(instructions, source_map) {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            out.push_str(&format!("{}ERROR: {}\n", indent, err));
+            return;
+        }
+    };
+
+    for ins in decoded.iter() {
+        let constant = match ins.opcode {
+            Opcode::Constant | Opcode::Closure => constants.get(ins.operands[0]),
+            _ => None,
+        };
+
+        let mut line = format!("{}{:<28}", indent, format_instruction(ins));
+
+        match constant {
+            Some(Object::CompiledFunction(function)) => {
+                line.push_str(&format!("; {} locals={}", function, function.num_locals))
+            }
+            Some(Object::String(value)) => line.push_str(&format!("; {:?}", value)),
+            Some(constant) => line.push_str(&format!("; {}", constant)),
+            None if ins.opcode == Opcode::Constant || ins.opcode == Opcode::Closure => {
+                line.push_str("; <missing constant>")
+            }
+            None => (),
+        }
+
+        if let Some(token) = &ins.token {
+            line = format!("{:<55} | {}", line, token.literal);
+        }
+
+        out.push_str(line.trim_end());
+        out.push('\n');
+
+        if let Some(Object::CompiledFunction(function)) = constant {
+            write_listing(
+                out,
+                &function.instructions,
+                &function.source_map,
+                constants,
+                depth + 1,
+            );
+        }
+    }
+}
+
+fn format_instruction(ins: &DecodedInstruction) -> String {
+    let mut text = format!("{:04} {}", ins.offset, ins.opcode.definition().name);
+
+    for operand in ins.operands.iter() {
+        text.push_str(&format!(" {}", operand));
+    }
+
+    text
+}
@@ -0,0 +1,1397 @@
+pub mod limits;
+
+use crate::ast::expression::{self, Expression};
+use crate::ast::pattern::Pattern;
+use crate::ast::statement::{self, Statement};
+use crate::ast::Program;
+use crate::object::bigint::BigInt;
+use crate::object::environment::{AssignError, Env};
+use crate::object::heap::{Heap, HeapStats};
+use crate::object::iter::Iter;
+use crate::object::native::NativeRegistry;
+use crate::object::{self, HashKey, Matcher, Object, Range};
+use limits::{LimitExceeded, Limits, Meter};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+#[derive(Debug)]
+pub struct Evaluator {
+    env: Env,
+    // Declared after `env` so it is dropped last and can free the cycles the
+    // global environment leaves behind.
+    heap: Heap,
+    natives: NativeRegistry,
+    meter: Meter,
+    exceeded: Option<LimitExceeded>,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        let mut heap = Heap::new();
+
+        Self {
+            env: heap.new_environment(),
+            heap,
+            natives: NativeRegistry::new(),
+            meter: Meter::default(),
+            exceeded: None,
+        }
+    }
+
+    pub fn environment(&self) -> &Env {
+        &self.env
+    }
+
+    /// Exposes a host function to scripts. Bindings made by the script shadow
+    /// natives of the same name.
+    pub fn register_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Object]) -> Result<Object, String> + 'static,
+    {
+        self.natives.register(name, arity, function);
+    }
+
+    /// Calls a function value from the host, e.g. one read out of the global
+    /// environment.
+    pub fn call_function(&mut self, function: Object, args: Vec<Object>) -> Object {
+        self.exceeded = None;
+        self.apply_function(function, args)
+    }
+
+    /// Installs new limits and refills the fuel and allocation budgets.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.meter.set_limits(limits);
+    }
+
+    pub fn limits(&self) -> &Limits {
+        self.meter.limits()
+    }
+
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.meter.remaining_fuel()
+    }
+
+    pub fn remaining_allocations(&self) -> Option<usize> {
+        self.meter.remaining_allocations()
+    }
+
+    /// Restores the full fuel and allocation budgets of the current limits.
+    pub fn refill(&mut self) {
+        self.meter.refill();
+    }
+
+    /// The limit that aborted the last program or call, if one did. The
+    /// returned error object carries the same message.
+    pub fn limit_exceeded(&self) -> Option<LimitExceeded> {
+        self.exceeded
+    }
+
+    pub fn heap_stats(&self) -> HeapStats {
+        self.heap.stats()
+    }
+
+    /// Runs a full collection now rather than waiting for the allocation
+    /// threshold, returning the number of environments freed.
+    pub fn collect_garbage(&mut self) -> usize {
+        self.heap.collect()
+    }
+
+    pub fn eval_program(&mut self, program: &Program) -> Object {
+        self.exceeded = None;
+
+        let env = Rc::clone(&self.env);
+        let mut result = Object::Null;
+
+        for stmt in program.statements.iter() {
+            result = self.eval_statement(stmt, &env);
+
+            match result {
+                Object::ReturnValue(value) => {
+                    return match *value {
+                        Object::TailCall(function, args) => self.apply_function(*function, args),
+                        value => value,
+                    }
+                }
+                Object::Error(_) => return result,
+                _ => (),
+            }
+        }
+
+        result
+    }
+
+    fn eval_statement(&mut self, stmt: &Statement, env: &Env) -> Object {
+        match stmt {
+            Statement::Expression(stmt) => self.eval_expression(&stmt.expression, env),
+            Statement::Block(block) => self.eval_block_statement(block, env),
+            Statement::Let(stmt) => self.eval_let_statement(stmt, env),
+            Statement::Struct(stmt) => {
+                let ty = Object::StructType(Rc::new(stmt.into()));
+                env.borrow_mut().set(stmt.name.value.clone(), ty);
+                Object::Null
+            }
+            Statement::While(stmt) => self.eval_while_statement(stmt, env),
+            Statement::For(stmt) => self.eval_for_statement(stmt, env),
+            Statement::Break(stmt) => Object::Break(stmt.label.clone()),
+            Statement::Continue(stmt) => Object::Continue(stmt.label.clone()),
+            Statement::Return(stmt) => {
+                let value = match &stmt.return_value {
+                    Some(exp) => self.eval_tail_expression(exp, env),
+                    None => Object::Null,
+                };
+
+                if value.is_error() {
+                    return value;
+                }
+
+                Object::ReturnValue(Box::new(value))
+            }
+        }
+    }
+
+    fn eval_block_statement(&mut self, block: &statement::Block, env: &Env) -> Object {
+        let mut result = Object::Null;
+
+        for stmt in block.statements.iter() {
+            result = self.eval_statement(stmt, env);
+
+            if matches!(
+                result,
+                Object::ReturnValue(_) | Object::Error(_) | Object::Break(_) | Object::Continue(_)
+            ) {
+                return result;
+            }
+        }
+
+        result
+    }
+
+    /// Evaluates a block whose value is the value of the enclosing function,
+    /// so that a call in its last statement becomes a tail call.
+    fn eval_tail_block_statement(&mut self, block: &statement::Block, env: &Env) -> Object {
+        let mut result = Object::Null;
+        let last = block.statements.len().saturating_sub(1);
+
+        for (i, stmt) in block.statements.iter().enumerate() {
+            result = match stmt {
+                Statement::Expression(stmt) if i == last => {
+                    self.eval_tail_expression(&stmt.expression, env)
+                }
+                _ => self.eval_statement(stmt, env),
+            };
+
+            if matches!(
+                result,
+                Object::ReturnValue(_) | Object::Error(_) | Object::Break(_) | Object::Continue(_)
+            ) {
+                return result;
+            }
+        }
+
+        result
+    }
+
+    /// Evaluates an expression in tail position. Calls are not applied but
+    /// returned as `Object::TailCall` for `apply_function` to run in place of
+    /// the current call, which keeps recursive loops in constant stack space.
+    fn eval_tail_expression(&mut self, exp: &Expression, env: &Env) -> Object {
+        match exp {
+            Expression::Call(call) => self.eval_call_expression(call, env, true),
+            Expression::If(if_exp) => {
+                let condition = self.eval_expression(&if_exp.condition, env);
+                if condition.is_error() {
+                    return condition;
+                }
+
+                if condition.is_truthy() {
+                    self.eval_tail_block_statement(&if_exp.consequence, env)
+                } else if let Some(alternative) = &if_exp.alternative {
+                    self.eval_tail_block_statement(alternative, env)
+                } else {
+                    Object::Null
+                }
+            }
+            _ => self.eval_expression(exp, env),
+        }
+    }
+
+    fn eval_while_statement(&mut self, stmt: &statement::While, env: &Env) -> Object {
+        loop {
+            let condition = self.eval_expression(&stmt.condition, env);
+            if condition.is_error() {
+                return condition;
+            }
+
+            if !condition.is_truthy() {
+                return Object::Null;
+            }
+
+            match self.eval_block_statement(&stmt.body, env) {
+                Object::Break(label) if targets_loop(&label, &stmt.label) => return Object::Null,
+                Object::Continue(label) if targets_loop(&label, &stmt.label) => (),
+                result @ (Object::ReturnValue(_)
+                | Object::Error(_)
+                | Object::Break(_)
+                | Object::Continue(_)) => return result,
+                _ => (),
+            }
+        }
+    }
+
+    fn eval_for_statement(&mut self, stmt: &statement::For, env: &Env) -> Object {
+        let iterable = self.eval_expression(&stmt.iterable, env);
+        if iterable.is_error() {
+            return iterable;
+        }
+
+        let mut iter = match Iter::new(iterable) {
+            Ok(iter) => iter,
+            Err(msg) => return Object::Error(msg),
+        };
+
+        while let Some(values) = iter.next_values(stmt.variables.len()) {
+            if let Err(limit) = self.meter.step().and_then(|()| self.meter.allocate(1)) {
+                return self.abort(limit);
+            }
+
+            let mut bindings = Vec::new();
+            for (pattern, value) in stmt.variables.iter().zip(values) {
+                if let Err(msg) = bind_pattern(pattern, value, &mut bindings) {
+                    return Object::Error(msg);
+                }
+            }
+
+            if self.heap.should_collect() {
+                self.heap.collect();
+            }
+
+            // Each iteration gets its own scope, so closures made in the body
+            // keep the values of the iteration that made them.
+            let scope = self.heap.new_enclosed_environment(env);
+            for (name, value) in bindings {
+                scope.borrow_mut().set(name, value);
+            }
+
+            match self.eval_block_statement(&stmt.body, &scope) {
+                Object::Break(label) if targets_loop(&label, &stmt.label) => return Object::Null,
+                Object::Continue(label) if targets_loop(&label, &stmt.label) => (),
+                result @ (Object::ReturnValue(_)
+                | Object::Error(_)
+                | Object::Break(_)
+                | Object::Continue(_)) => return result,
+                _ => (),
+            }
+        }
+
+        Object::Null
+    }
+
+    fn eval_let_statement(&mut self, stmt: &statement::Let, env: &Env) -> Object {
+        let value = match &stmt.value {
+            Some(exp) => self.eval_expression(exp, env),
+            None => Object::Null,
+        };
+
+        if value.is_error() {
+            return value;
+        }
+
+        // Nothing is bound unless the whole pattern matches.
+        let mut bindings = Vec::new();
+        if let Err(msg) = bind_pattern(&stmt.name, value, &mut bindings) {
+            return Object::Error(msg);
+        }
+
+        let mut env = env.borrow_mut();
+        for (name, value) in bindings {
+            match stmt.mutable {
+                true => env.set_mutable(name, value),
+                false => env.set(name, value),
+            }
+        }
+
+        Object::Null
+    }
+
+    fn eval_expression(&mut self, exp: &Expression, env: &Env) -> Object {
+        if let Err(limit) = self.meter.step() {
+            return self.abort(limit);
+        }
+
+        match exp {
+            Expression::IntegerLiteral(int) => Object::Integer(int.value),
+            Expression::BigIntLiteral(int) => self.charge(Object::BigInt(int.value.clone())),
+            Expression::FloatLiteral(float) => Object::Float(float.value),
+            Expression::StringLiteral(string) => self.charge(Object::String(string.value.clone())),
+            Expression::Boolean(boolean) => Object::Boolean(boolean.value),
+            Expression::Identifier(ident) => self.eval_identifier(ident, env),
+            Expression::Prefix(prefix) => {
+                let right = self.eval_expression(&prefix.right, env);
+                if right.is_error() {
+                    return right;
+                }
+
+                self.charge(eval_prefix_expression(&prefix.operator, right))
+            }
+            Expression::Infix(infix) => {
+                let left = self.eval_expression(&infix.left, env);
+                if left.is_error() {
+                    return left;
+                }
+
+                // These short-circuit, yielding the left operand when it
+                // decides the result and the right one otherwise.
+                match infix.operator.as_str() {
+                    "??" if !matches!(left, Object::Null) => return left,
+                    "&&" if !left.is_truthy() => return left,
+                    "||" if left.is_truthy() => return left,
+                    "??" | "&&" | "||" => return self.eval_expression(&infix.right, env),
+                    _ => (),
+                }
+
+                let right = self.eval_expression(&infix.right, env);
+                if right.is_error() {
+                    return right;
+                }
+
+                self.charge(eval_infix_expression(&infix.operator, left, right))
+            }
+            Expression::Null(_) => Object::Null,
+            Expression::Range(range) => {
+                let mut bounds = [None, None];
+                for (bound, exp) in bounds.iter_mut().zip([&range.start, &range.end]) {
+                    if let Some(exp) = exp {
+                        let value = self.eval_expression(exp, env);
+                        if value.is_error() {
+                            return value;
+                        }
+                        *bound = Some(value);
+                    }
+                }
+
+                let [start, end] = bounds;
+                build_range(start, end, range.inclusive)
+            }
+            Expression::OptionalIndex(index) => {
+                let left = self.eval_expression(&index.left, env);
+                if left.is_error() || matches!(left, Object::Null) {
+                    return left;
+                }
+
+                let index = self.eval_expression(&index.index, env);
+                if index.is_error() {
+                    return index;
+                }
+
+                self.eval_index(left, index)
+            }
+            Expression::Member(member) => {
+                let left = self.eval_expression(&member.left, env);
+                if left.is_error() {
+                    return left;
+                }
+
+                eval_member_expression(left, &member.member.value)
+            }
+            Expression::OptionalMember(member) => {
+                let left = self.eval_expression(&member.left, env);
+                if left.is_error() || matches!(left, Object::Null) {
+                    return left;
+                }
+
+                eval_member_expression(left, &member.member.value)
+            }
+            Expression::If(if_exp) => self.eval_if_expression(if_exp, env),
+            Expression::Match(match_exp) => self.eval_match_expression(match_exp, env),
+            Expression::FunctionLiteral(function) => {
+                if let Err(limit) = self.meter.allocate(1) {
+                    return self.abort(limit);
+                }
+
+                Object::Function(self.heap.new_function(object::Function {
+                    parameters: function.parameters.clone(),
+                    body: function.body.clone(),
+                    env: Rc::clone(env),
+                }))
+            }
+            Expression::Call(call) => self.eval_call_expression(call, env, false),
+            Expression::ArrayLiteral(array) => {
+                let mut elements = Vec::with_capacity(array.elements.len());
+                for element in array.elements.iter() {
+                    let element = self.eval_expression(element, env);
+                    if element.is_error() {
+                        return element;
+                    }
+                    elements.push(element);
+                }
+
+                self.charge(Object::Array(Rc::new(elements)))
+            }
+            Expression::TupleLiteral(tuple) => {
+                let mut elements = Vec::with_capacity(tuple.elements.len());
+                for element in tuple.elements.iter() {
+                    let element = self.eval_expression(element, env);
+                    if element.is_error() {
+                        return element;
+                    }
+                    elements.push(element);
+                }
+
+                self.charge(Object::Tuple(Rc::new(elements)))
+            }
+            Expression::StructLiteral(literal) => {
+                let ty = self.eval_identifier(&literal.name, env);
+                if ty.is_error() {
+                    return ty;
+                }
+
+                let mut fields = Vec::with_capacity(literal.fields.len());
+                for (name, value) in literal.fields.iter() {
+                    let value = self.eval_expression(value, env);
+                    if value.is_error() {
+                        return value;
+                    }
+                    fields.push((name.value.clone(), value));
+                }
+
+                self.charge(build_struct(ty, fields))
+            }
+            Expression::HashLiteral(hash) => {
+                let mut pairs = Vec::with_capacity(hash.pairs.len());
+                for (key, value) in hash.pairs.iter() {
+                    let key = self.eval_expression(key, env);
+                    if key.is_error() {
+                        return key;
+                    }
+
+                    let value = self.eval_expression(value, env);
+                    if value.is_error() {
+                        return value;
+                    }
+
+                    pairs.push((key, value));
+                }
+
+                self.charge(build_hash(pairs))
+            }
+            Expression::Index(index) => {
+                let left = self.eval_expression(&index.left, env);
+                if left.is_error() {
+                    return left;
+                }
+
+                let index = self.eval_expression(&index.index, env);
+                if index.is_error() {
+                    return index;
+                }
+
+                self.eval_index(left, index)
+            }
+            Expression::Assign(assign) => self.eval_assign_expression(assign, env),
+        }
+    }
+
+    /// A compound assignment reads the target before evaluating the value.
+    /// Either way the assignment's own value is the value stored.
+    /// The target's containers and keys are evaluated once, before the
+    /// value, and reused both to read the current value and to store.
+    fn eval_assign_expression(&mut self, assign: &expression::Assign, env: &Env) -> Object {
+        let mut path = Vec::new();
+        let ident = match self.eval_target(&assign.target, env, &mut path) {
+            Ok(ident) => ident,
+            Err(err) => return err,
+        };
+
+        let value = match assign.compound_operator() {
+            Some(operator) => {
+                let current = self.target_value(ident, &path, env);
+                if current.is_error() {
+                    return current;
+                }
+
+                let value = self.eval_expression(&assign.value, env);
+                if value.is_error() {
+                    return value;
+                }
+
+                self.charge(eval_infix_expression(operator, current, value))
+            }
+            None => self.eval_expression(&assign.value, env),
+        };
+
+        if value.is_error() {
+            return value;
+        }
+
+        match self.store(ident, path, value.clone(), env) {
+            Object::Error(msg) => Object::Error(msg),
+            _ => value,
+        }
+    }
+
+    /// Evaluates the container and key of each element an assignment target
+    /// goes through into `path`, outermost first, and returns the variable
+    /// holding the outermost container.
+    fn eval_target<'a>(
+        &mut self,
+        target: &'a Expression,
+        env: &Env,
+        path: &mut Vec<(Object, Accessor<'a>)>,
+    ) -> Result<&'a expression::Identifier, Object> {
+        match target {
+            Expression::Identifier(ident) => Ok(ident),
+            Expression::Index(index) => {
+                let ident = self.eval_target(&index.left, env, path)?;
+                let left = self.target_value(ident, path, env);
+                if left.is_error() {
+                    return Err(left);
+                }
+
+                let key = self.eval_expression(&index.index, env);
+                if key.is_error() {
+                    return Err(key);
+                }
+
+                path.push((left, Accessor::Index(key)));
+                Ok(ident)
+            }
+            Expression::Member(member) => {
+                let ident = self.eval_target(&member.left, env, path)?;
+                let left = self.target_value(ident, path, env);
+                if left.is_error() {
+                    return Err(left);
+                }
+
+                path.push((left, Accessor::Member(&member.member.value)));
+                Ok(ident)
+            }
+            target => Err(Object::Error(format!(
+                "invalid assignment target: {}",
+                target
+            ))),
+        }
+    }
+
+    /// The current value of the target `eval_target` evaluated.
+    fn target_value(
+        &mut self,
+        ident: &expression::Identifier,
+        path: &[(Object, Accessor)],
+        env: &Env,
+    ) -> Object {
+        match path.last() {
+            None => self.eval_identifier(ident, env),
+            Some((left, Accessor::Index(key))) => self.eval_index(left.clone(), key.clone()),
+            Some((left, Accessor::Member(name))) => eval_member_expression(left.clone(), name),
+        }
+    }
+
+    /// Stores `value` in an assignment target. Containers are values, so an
+    /// element is set by storing an updated copy of its container in the
+    /// container's own target, and so on out to the variable holding it.
+    fn store(
+        &mut self,
+        ident: &expression::Identifier,
+        mut path: Vec<(Object, Accessor)>,
+        mut value: Object,
+        env: &Env,
+    ) -> Object {
+        while let Some((left, accessor)) = path.pop() {
+            value = self.charge(match accessor {
+                Accessor::Index(key) => set_index(left, key, value),
+                Accessor::Member(name) => set_member(left, name, value),
+            });
+            if value.is_error() {
+                return value;
+            }
+        }
+
+        let result = env.borrow_mut().assign(&ident.value, value);
+        match result {
+            Ok(()) => Object::Null,
+            Err(AssignError::Undefined) if self.natives.get(&ident.value).is_some() => {
+                Object::Error(assign_error(AssignError::Immutable, ident))
+            }
+            Err(err) => Object::Error(assign_error(err, ident)),
+        }
+    }
+
+    fn eval_call_expression(&mut self, call: &expression::Call, env: &Env, tail: bool) -> Object {
+        let function = self.eval_expression(&call.function, env);
+        if function.is_error() {
+            return function;
+        }
+
+        let mut args = Vec::with_capacity(call.arguments.len());
+        for arg in call.arguments.iter() {
+            let arg = self.eval_expression(arg, env);
+            if arg.is_error() {
+                return arg;
+            }
+            args.push(arg);
+        }
+
+        if tail {
+            Object::TailCall(Box::new(function), args)
+        } else {
+            self.apply_function(function, args)
+        }
+    }
+
+    fn eval_identifier(&self, ident: &expression::Identifier, env: &Env) -> Object {
+        if let Some(value) = env.borrow().get(&ident.value) {
+            return value;
+        }
+
+        match self.natives.get(&ident.value) {
+            Some(native) => Object::Native(Rc::clone(native)),
+            None => Object::Error(format!("identifier not found: {}", ident.value)),
+        }
+    }
+
+    fn eval_match_expression(&mut self, match_exp: &expression::Match, env: &Env) -> Object {
+        let subject = self.eval_expression(&match_exp.subject, env);
+        if subject.is_error() {
+            return subject;
+        }
+
+        for arm in match_exp.arms.iter() {
+            let mut values = Vec::new();
+            if !match_pattern(&Matcher::from(&arm.pattern), &subject, &mut values) {
+                continue;
+            }
+
+            // What the pattern binds is only in scope in the guard and body.
+            let scope = match values.is_empty() {
+                true => Rc::clone(env),
+                false => {
+                    if let Err(limit) = self.meter.allocate(1) {
+                        return self.abort(limit);
+                    }
+                    self.heap.new_enclosed_environment(env)
+                }
+            };
+            for (ident, value) in arm.pattern.bindings().into_iter().zip(values) {
+                scope.borrow_mut().set(ident.value.clone(), value);
+            }
+
+            if let Some(guard) = &arm.guard {
+                let guard = self.eval_expression(guard, &scope);
+                if guard.is_error() {
+                    return guard;
+                }
+                if !guard.is_truthy() {
+                    continue;
+                }
+            }
+
+            return self.eval_expression(&arm.body, &scope);
+        }
+
+        Object::Error(no_match_arm(&subject))
+    }
+
+    fn eval_if_expression(&mut self, if_exp: &expression::If, env: &Env) -> Object {
+        let condition = self.eval_expression(&if_exp.condition, env);
+        if condition.is_error() {
+            return condition;
+        }
+
+        if condition.is_truthy() {
+            self.eval_block_statement(&if_exp.consequence, env)
+        } else if let Some(alternative) = &if_exp.alternative {
+            self.eval_block_statement(alternative, env)
+        } else {
+            Object::Null
+        }
+    }
+
+    fn apply_function(&mut self, function: Object, args: Vec<Object>) -> Object {
+        if let Err(limit) = self.meter.enter_call() {
+            return self.abort(limit);
+        }
+
+        let result = self.run_function(function, args);
+        self.meter.leave_call();
+
+        result
+    }
+
+    fn run_function(&mut self, mut function: Object, mut args: Vec<Object>) -> Object {
+        loop {
+            let current = match function {
+                Object::Function(function) => function,
+                Object::Native(native) => {
+                    return match native.call(&args) {
+                        Ok(value) => value,
+                        Err(msg) => Object::Error(msg),
+                    }
+                }
+                other => return Object::Error(format!("not a function: {}", other.type_name())),
+            };
+
+            if current.parameters.len() != args.len() {
+                return Object::Error(format!(
+                    "wrong number of arguments: want={}, got={}",
+                    current.parameters.len(),
+                    args.len()
+                ));
+            }
+
+            if let Err(limit) = self.meter.allocate(1) {
+                return self.abort(limit);
+            }
+
+            if self.heap.should_collect() {
+                self.heap.collect();
+            }
+
+            let env = self.heap.new_enclosed_environment(&current.env);
+            for (param, arg) in current.parameters.iter().zip(args) {
+                env.borrow_mut().set(param.value.clone(), arg);
+            }
+
+            let result = match self.eval_tail_block_statement(&current.body, &env) {
+                Object::ReturnValue(value) => *value,
+                result => result,
+            };
+
+            match result {
+                Object::TailCall(next, next_args) => {
+                    function = *next;
+                    args = next_args;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Indexing with a range produces a new slice, which is charged; reading
+    /// a single element allocates nothing.
+    fn eval_index(&mut self, left: Object, index: Object) -> Object {
+        match index {
+            Object::Range(_) => self.charge(eval_index_expression(left, index)),
+            index => eval_index_expression(left, index),
+        }
+    }
+
+    /// Charges a value just produced to the allocation budget by its size.
+    fn charge(&mut self, value: Object) -> Object {
+        match self.meter.allocate(allocation_size(&value)) {
+            Ok(()) => value,
+            Err(limit) => self.abort(limit),
+        }
+    }
+
+    fn abort(&mut self, limit: LimitExceeded) -> Object {
+        self.exceeded = Some(limit);
+        Object::Error(limit.to_string())
+    }
+}
+
+/// How an assignment target reaches into its container.
+enum Accessor<'a> {
+    Index(Object),
+    Member(&'a str),
+}
+
+/// The units `Limits::max_allocations` counts for a newly produced value.
+fn allocation_size(value: &Object) -> usize {
+    match value {
+        Object::String(string) => string.len(),
+        Object::BigInt(value) => value.bits().div_ceil(32) as usize,
+        Object::Array(elements) | Object::Tuple(elements) => elements.len() + 1,
+        Object::Hash(pairs) => pairs.len() + 1,
+        Object::Struct(instance) => instance.values.len() + 1,
+        _ => 0,
+    }
+}
+
+pub(crate) fn eval_prefix_expression(operator: &str, right: Object) -> Object {
+    match (operator, right) {
+        ("!", right) => Object::Boolean(!right.is_truthy()),
+        ("-", Object::Integer(value)) => match value.checked_neg() {
+            Some(value) => Object::Integer(value),
+            None => Object::from(-&BigInt::from(value)),
+        },
+        ("-", Object::BigInt(value)) => Object::from(-&value),
+        ("-", Object::Float(value)) => Object::Float(-value),
+        ("~", Object::Integer(value)) => Object::Integer(!value),
+        ("~", Object::BigInt(value)) => Object::from(&-&value - &BigInt::from(1)),
+        (_, right) => Object::Error(format!(
+            "unknown operator: {}{}",
+            operator,
+            right.type_name()
+        )),
+    }
+}
+
+/// Arithmetic on two integers stays integral, so `/` truncates towards zero,
+/// and promotes to a bignum rather than overflowing. If either operand is a
+/// float the other is converted and the operation, including comparison, is
+/// carried out on floats.
+pub(crate) fn eval_infix_expression(operator: &str, left: Object, right: Object) -> Object {
+    match (left, right) {
+        (Object::Integer(left), Object::Integer(right)) => {
+            eval_integer_infix_expression(operator, left, right)
+        }
+        (Object::BigInt(left), Object::BigInt(right)) => {
+            eval_big_int_infix_expression(operator, left, right)
+        }
+        (Object::BigInt(left), Object::Integer(right)) => {
+            eval_big_int_infix_expression(operator, left, BigInt::from(right))
+        }
+        (Object::Integer(left), Object::BigInt(right)) => {
+            eval_big_int_infix_expression(operator, BigInt::from(left), right)
+        }
+        (Object::Float(left), Object::Float(right)) => {
+            eval_float_infix_expression(operator, left, right)
+        }
+        (Object::Integer(left), Object::Float(right)) => {
+            eval_float_infix_expression(operator, left as f64, right)
+        }
+        (Object::Float(left), Object::Integer(right)) => {
+            eval_float_infix_expression(operator, left, right as f64)
+        }
+        (Object::BigInt(left), Object::Float(right)) => {
+            eval_float_infix_expression(operator, left.to_f64(), right)
+        }
+        (Object::Float(left), Object::BigInt(right)) => {
+            eval_float_infix_expression(operator, left, right.to_f64())
+        }
+        (Object::Boolean(left), Object::Boolean(right)) => match operator {
+            "==" => Object::Boolean(left == right),
+            "!=" => Object::Boolean(left != right),
+            _ => Object::Error(format!("unknown operator: BOOLEAN {} BOOLEAN", operator)),
+        },
+        (Object::String(left), Object::String(right)) => {
+            eval_string_infix_expression(operator, left, right)
+        }
+        // Anything can be compared with null, which equals only itself.
+        (left @ Object::Null, right) | (left, right @ Object::Null)
+            if operator == "==" || operator == "!=" =>
+        {
+            let equal = matches!((left, right), (Object::Null, Object::Null));
+            Object::Boolean(equal == (operator == "=="))
+        }
+        (left, right) if left.type_name() != right.type_name() => Object::Error(format!(
+            "type mismatch: {} {} {}",
+            left.type_name(),
+            operator,
+            right.type_name()
+        )),
+        (left, right) => Object::Error(format!(
+            "unknown operator: {} {} {}",
+            left.type_name(),
+            operator,
+            right.type_name()
+        )),
+    }
+}
+
+pub(crate) fn build_range(start: Option<Object>, end: Option<Object>, inclusive: bool) -> Object {
+    let bound = |bound: Option<Object>| match bound {
+        None => Ok(None),
+        Some(Object::Integer(value)) => Ok(Some(value)),
+        Some(Object::BigInt(_)) => Err(Object::Error("range bound too large".to_string())),
+        Some(other) => Err(Object::Error(format!(
+            "range bounds must be INTEGER, got {}",
+            other.type_name()
+        ))),
+    };
+
+    match (bound(start), bound(end)) {
+        (Ok(start), Ok(end)) => Object::Range(Range {
+            start,
+            end,
+            inclusive,
+        }),
+        (Err(err), _) | (_, Err(err)) => err,
+    }
+}
+
+/// Later pairs overwrite earlier ones with the same key.
+pub(crate) fn build_hash(pairs: Vec<(Object, Object)>) -> Object {
+    let mut hash = BTreeMap::new();
+
+    for (key, value) in pairs {
+        match key.hash_key() {
+            Some(key) => hash.insert(key, value),
+            None => return unusable_hash_key(&key),
+        };
+    }
+
+    Object::Hash(Rc::new(hash))
+}
+
+fn unusable_hash_key(key: &Object) -> Object {
+    Object::Error(format!("unusable as hash key: {}", key.type_name()))
+}
+
+/// Indexing past either end of an array, or with a key a hash doesn't hold,
+/// yields null rather than an error. Indexing an array or string with a range
+/// slices it, see `Range::slice_bounds`; strings are sliced by character.
+pub(crate) fn eval_index_expression(left: Object, index: Object) -> Object {
+    match (left, index) {
+        (Object::Array(elements), Object::Range(range)) => {
+            let (start, end) = range.slice_bounds(elements.len());
+            Object::Array(Rc::new(elements[start..end].to_vec()))
+        }
+        (Object::String(string), Object::Range(range)) => {
+            let (start, end) = range.slice_bounds(string.chars().count());
+            Object::String(string.chars().skip(start).take(end - start).collect())
+        }
+        (Object::Hash(pairs), key) => match key.hash_key() {
+            Some(hash_key) => pairs.get(&hash_key).cloned().unwrap_or(Object::Null),
+            None => unusable_hash_key(&key),
+        },
+        (Object::Array(elements), Object::Integer(index)) => usize::try_from(index)
+            .ok()
+            .and_then(|index| elements.get(index).cloned())
+            .unwrap_or(Object::Null),
+        (Object::Array(_), Object::BigInt(_)) => Object::Null,
+        (Object::Array(_), index) => Object::Error(format!(
+            "array index must be INTEGER, got {}",
+            index.type_name()
+        )),
+        (Object::Tuple(elements), Object::Integer(index)) => usize::try_from(index)
+            .ok()
+            .and_then(|index| elements.get(index).cloned())
+            .unwrap_or(Object::Null),
+        (Object::Tuple(_), Object::BigInt(_)) => Object::Null,
+        (Object::Tuple(_), index) => Object::Error(format!(
+            "tuple index must be INTEGER, got {}",
+            index.type_name()
+        )),
+        (left, _) => Object::Error(format!(
+            "index operator not supported: {}",
+            left.type_name()
+        )),
+    }
+}
+
+/// Whether a `break` or `continue` with `label` applies to a loop labelled
+/// `loop_label`; one without a label applies to the innermost loop.
+fn targets_loop(label: &Option<String>, loop_label: &Option<String>) -> bool {
+    label.is_none() || label == loop_label
+}
+
+pub(crate) fn assign_error(err: AssignError, ident: &expression::Identifier) -> String {
+    format!("{} {} at {}", err, ident.value, ident.token.position)
+}
+
+/// The container `left` with the element at `index` replaced by `value`.
+/// Unlike reading, writing past the end of an array is an error, while a
+/// hash gains the key if it did not hold it.
+pub(crate) fn set_index(left: Object, index: Object, value: Object) -> Object {
+    match (left, index) {
+        (Object::Array(mut elements), Object::Integer(index)) => {
+            let len = elements.len();
+            match usize::try_from(index).ok().filter(|index| *index < len) {
+                Some(index) => {
+                    Rc::make_mut(&mut elements)[index] = value;
+                    Object::Array(elements)
+                }
+                None => Object::Error(format!(
+                    "array index out of bounds: {} for length {}",
+                    index, len
+                )),
+            }
+        }
+        (Object::Array(_), index) => Object::Error(format!(
+            "array index must be INTEGER, got {}",
+            index.type_name()
+        )),
+        (Object::Hash(mut pairs), key) => match key.hash_key() {
+            Some(hash_key) => {
+                Rc::make_mut(&mut pairs).insert(hash_key, value);
+                Object::Hash(pairs)
+            }
+            None => unusable_hash_key(&key),
+        },
+        (left, _) => Object::Error(format!(
+            "index assignment not supported: {}",
+            left.type_name()
+        )),
+    }
+}
+
+/// The hash or struct `left` with its member `name` set to `value`.
+pub(crate) fn set_member(left: Object, name: &str, value: Object) -> Object {
+    match left {
+        Object::Hash(mut pairs) => {
+            Rc::make_mut(&mut pairs).insert(HashKey::String(name.to_string()), value);
+            Object::Hash(pairs)
+        }
+        Object::Struct(mut instance) => match instance.ty.field_index(name) {
+            Some(index) => {
+                Rc::make_mut(&mut instance).values[index] = value;
+                Object::Struct(instance)
+            }
+            None => unknown_field(&instance.ty, name),
+        },
+        left => Object::Error(format!(
+            "member assignment not supported: {}",
+            left.type_name()
+        )),
+    }
+}
+
+/// What a destructuring pattern expects of a value: a tuple of exactly this
+/// many elements, or an array of exactly this many or, with a rest pattern,
+/// at least this many.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Shape {
+    Tuple(usize),
+    Array { len: usize, rest: bool },
+}
+
+/// Splits `value` into the elements `shape` describes. With a rest pattern
+/// the remaining elements are gathered into an array after the others.
+pub(crate) fn destructure(value: Object, shape: Shape) -> Result<Vec<Object>, String> {
+    match (value, shape) {
+        (Object::Tuple(elements), Shape::Tuple(len)) => match elements.len() == len {
+            true => Ok(elements.to_vec()),
+            false => Err(format!(
+                "tuple pattern expects {} elements, got {}",
+                len,
+                elements.len()
+            )),
+        },
+        (Object::Array(elements), Shape::Array { len, rest: false }) => {
+            match elements.len() == len {
+                true => Ok(elements.to_vec()),
+                false => Err(format!(
+                    "array pattern expects {} elements, got {}",
+                    len,
+                    elements.len()
+                )),
+            }
+        }
+        (Object::Array(elements), Shape::Array { len, rest: true }) => {
+            if elements.len() < len {
+                return Err(format!(
+                    "array pattern expects at least {} elements, got {}",
+                    len,
+                    elements.len()
+                ));
+            }
+
+            let mut values = elements[..len].to_vec();
+            values.push(Object::Array(Rc::new(elements[len..].to_vec())));
+            Ok(values)
+        }
+        (value, Shape::Tuple(_)) => {
+            Err(format!("cannot destructure {} as tuple", value.type_name()))
+        }
+        (value, Shape::Array { .. }) => {
+            Err(format!("cannot destructure {} as array", value.type_name()))
+        }
+    }
+}
+
+/// Tests `value` against a `match` arm's pattern, collecting the values it
+/// binds, in order, when it matches. Literals only match values of the same
+/// type, so `1` does not match `1.0`.
+pub(crate) fn match_pattern(matcher: &Matcher, value: &Object, bindings: &mut Vec<Object>) -> bool {
+    let all_match = |matchers: &[Matcher], values: &[Object], bindings: &mut Vec<Object>| {
+        matchers
+            .iter()
+            .zip(values)
+            .all(|(matcher, value)| match_pattern(matcher, value, bindings))
+    };
+
+    match (matcher, value) {
+        (Matcher::Bind(_), value) => {
+            bindings.push(value.clone());
+            true
+        }
+        (Matcher::Wildcard, _) => true,
+        (Matcher::Literal(Object::Float(literal)), Object::Float(value)) => literal == value,
+        (Matcher::Literal(Object::Null), Object::Null) => true,
+        (Matcher::Literal(literal), value) => {
+            literal.hash_key().is_some() && literal.hash_key() == value.hash_key()
+        }
+        (Matcher::Tuple(elements), Object::Tuple(values)) => {
+            elements.len() == values.len() && all_match(elements, values, bindings)
+        }
+        (Matcher::Array { elements, rest }, Object::Array(values)) => {
+            let len = elements.len();
+            match rest {
+                Some(rest) if values.len() >= len => {
+                    let rest_values = Object::Array(Rc::new(values[len..].to_vec()));
+                    all_match(elements, values, bindings)
+                        && match_pattern(rest, &rest_values, bindings)
+                }
+                None if values.len() == len => all_match(elements, values, bindings),
+                _ => false,
+            }
+        }
+        (Matcher::Hash(pairs), Object::Hash(hash)) => pairs.iter().all(|(key, matcher)| {
+            hash.get(key)
+                .is_some_and(|value| match_pattern(matcher, value, bindings))
+        }),
+        _ => false,
+    }
+}
+
+pub(crate) fn no_match_arm(value: &Object) -> String {
+    format!("no match arm for {}", value.inspect())
+}
+
+fn bind_pattern(
+    pattern: &Pattern,
+    value: Object,
+    bindings: &mut Vec<(String, Object)>,
+) -> Result<(), String> {
+    match pattern {
+        Pattern::Identifier(ident) => bindings.push((ident.value.clone(), value)),
+        Pattern::Wildcard(_) => (),
+        // The parser only allows these in `match` arms.
+        Pattern::Literal(_) | Pattern::Hash(_) => {
+            return Err(format!("cannot bind to pattern {}", pattern));
+        }
+        Pattern::Tuple(tuple) => {
+            let values = destructure(value, Shape::Tuple(tuple.elements.len()))?;
+            for (element, value) in tuple.elements.iter().zip(values) {
+                bind_pattern(element, value, bindings)?;
+            }
+        }
+        Pattern::Array(array) => {
+            let shape = Shape::Array {
+                len: array.elements.len(),
+                rest: array.rest.is_some(),
+            };
+            let mut values = destructure(value, shape)?.into_iter();
+            for (element, value) in array.elements.iter().zip(values.by_ref()) {
+                bind_pattern(element, value, bindings)?;
+            }
+            if let (Some(rest), Some(value)) = (&array.rest, values.next()) {
+                bind_pattern(rest, value, bindings)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds a value of the struct type `ty`, which must be given every one of
+/// its fields exactly once.
+pub(crate) fn build_struct(ty: Object, fields: Vec<(String, Object)>) -> Object {
+    let ty = match ty {
+        Object::StructType(ty) => ty,
+        other => return Object::Error(format!("not a struct: {}", other.type_name())),
+    };
+
+    let mut values = vec![None; ty.fields.len()];
+    for (name, value) in fields {
+        match ty.field_index(&name) {
+            Some(index) if values[index].is_none() => values[index] = Some(value),
+            Some(_) => {
+                return Object::Error(format!(
+                    "field {} given more than once for struct {}",
+                    name, ty.name
+                ))
+            }
+            None => return unknown_field(&ty, &name),
+        }
+    }
+
+    let mut checked = Vec::with_capacity(values.len());
+    for (value, name) in values.into_iter().zip(ty.fields.iter()) {
+        match value {
+            Some(value) => checked.push(value),
+            None => return Object::Error(format!("missing field {} for struct {}", name, ty.name)),
+        }
+    }
+
+    Object::Struct(Rc::new(object::Struct {
+        ty,
+        values: checked,
+    }))
+}
+
+fn unknown_field(ty: &object::StructType, name: &str) -> Object {
+    Object::Error(format!("unknown field {} for struct {}", name, ty.name))
+}
+
+/// `hash.name` looks up the string key `"name"`, and `value.name` the field
+/// of a struct.
+pub(crate) fn eval_member_expression(left: Object, name: &str) -> Object {
+    match left {
+        Object::Hash(pairs) => pairs
+            .get(&HashKey::String(name.to_string()))
+            .cloned()
+            .unwrap_or(Object::Null),
+        Object::Struct(value) => match value.get(name) {
+            Some(field) => field.clone(),
+            None => unknown_field(&value.ty, name),
+        },
+        left => Object::Error(format!("member access not supported: {}", left.type_name())),
+    }
+}
+
+fn eval_string_infix_expression(operator: &str, left: String, right: String) -> Object {
+    match operator {
+        "+" => Object::String(left + &right),
+        "==" => Object::Boolean(left == right),
+        "!=" => Object::Boolean(left != right),
+        _ => Object::Error(format!("unknown operator: STRING {} STRING", operator)),
+    }
+}
+
+/// Integers produced by `*`, `**` and `<<` may not grow past this many bits,
+/// so a script can't stall the interpreter computing one.
+const MAX_INTEGER_BITS: u64 = 1 << 18;
+
+fn eval_integer_infix_expression(operator: &str, left: i64, right: i64) -> Object {
+    let result = match operator {
+        "+" => left.checked_add(right),
+        "-" => left.checked_sub(right),
+        "*" => left.checked_mul(right),
+        "/" => match right {
+            0 => return Object::Error("division by zero".to_string()),
+            _ => left.checked_div(right),
+        },
+        "%" => match right {
+            0 => return Object::Error("division by zero".to_string()),
+            _ => left.checked_rem(right),
+        },
+        "**" if right < 0 => {
+            return eval_float_infix_expression(operator, left as f64, right as f64)
+        }
+        "**" => u32::try_from(right)
+            .ok()
+            .and_then(|exponent| left.checked_pow(exponent)),
+        "&" => Some(left & right),
+        "|" => Some(left | right),
+        "^" => Some(left ^ right),
+        "<<" | ">>" if right < 0 => return negative_shift(),
+        "<<" => (right < 64)
+            .then(|| left << right)
+            .filter(|shifted| shifted >> right == left),
+        ">>" => Some(left >> right.min(63)),
+        "<" => return Object::Boolean(left < right),
+        ">" => return Object::Boolean(left > right),
+        "<=" => return Object::Boolean(left <= right),
+        ">=" => return Object::Boolean(left >= right),
+        "==" => return Object::Boolean(left == right),
+        "!=" => return Object::Boolean(left != right),
+        _ => return Object::Error(format!("unknown operator: INTEGER {} INTEGER", operator)),
+    };
+
+    match result {
+        Some(value) => Object::Integer(value),
+        None => eval_big_int_infix_expression(operator, BigInt::from(left), BigInt::from(right)),
+    }
+}
+
+fn eval_big_int_infix_expression(operator: &str, left: BigInt, right: BigInt) -> Object {
+    match operator {
+        "+" => Object::from(&left + &right),
+        "-" => Object::from(&left - &right),
+        "*" if left.bits() + right.bits() > MAX_INTEGER_BITS => {
+            Object::Error("integer result too large".to_string())
+        }
+        "*" => Object::from(&left * &right),
+        "/" => match left.checked_div(&right) {
+            Some(value) => Object::from(value),
+            None => Object::Error("division by zero".to_string()),
+        },
+        "%" => match left.checked_rem(&right) {
+            Some(value) => Object::from(value),
+            None => Object::Error("division by zero".to_string()),
+        },
+        "**" if right.is_negative() => {
+            eval_float_infix_expression(operator, left.to_f64(), right.to_f64())
+        }
+        // 0, 1 and -1 stay small whatever the exponent.
+        "**" if left.bits() <= 1 => {
+            let odd = right
+                .checked_rem(&BigInt::from(2))
+                .is_some_and(|rem| !rem.is_zero());
+            Object::Integer(match left.to_i64().unwrap_or_default() {
+                0 if right.is_zero() => 1,
+                -1 if !odd => 1,
+                base => base,
+            })
+        }
+        "**" => match right
+            .to_i64()
+            .and_then(|exponent| u32::try_from(exponent).ok())
+        {
+            Some(exponent)
+                if left.bits().saturating_mul(u64::from(exponent)) <= MAX_INTEGER_BITS =>
+            {
+                Object::from(left.pow(exponent))
+            }
+            _ => Object::Error("integer result too large".to_string()),
+        },
+        "<<" | ">>" if right.is_negative() => negative_shift(),
+        "<<" => match right.to_i64().and_then(|shift| u32::try_from(shift).ok()) {
+            Some(shift) if left.bits() + u64::from(shift) <= MAX_INTEGER_BITS => {
+                Object::from(&left * &BigInt::from(2).pow(shift))
+            }
+            _ => Object::Error("integer result too large".to_string()),
+        },
+        // Shifting right floors, like the `i64` shift does.
+        ">>" => match right.to_i64().and_then(|shift| u32::try_from(shift).ok()) {
+            Some(shift) if u64::from(shift) < left.bits() => {
+                let divisor = BigInt::from(2).pow(shift);
+                let quotient = left.checked_div(&divisor).unwrap_or_default();
+                match left.is_negative() && &quotient * &divisor != left {
+                    true => Object::from(&quotient - &BigInt::from(1)),
+                    false => Object::from(quotient),
+                }
+            }
+            _ => Object::Integer(if left.is_negative() { -1 } else { 0 }),
+        },
+        "&" | "|" | "^" => Object::Error(format!("integer too large for {}", operator)),
+        "<" => Object::Boolean(left < right),
+        ">" => Object::Boolean(left > right),
+        "<=" => Object::Boolean(left <= right),
+        ">=" => Object::Boolean(left >= right),
+        "==" => Object::Boolean(left == right),
+        "!=" => Object::Boolean(left != right),
+        _ => Object::Error(format!("unknown operator: INTEGER {} INTEGER", operator)),
+    }
+}
+
+fn negative_shift() -> Object {
+    Object::Error("negative shift amount".to_string())
+}
+
+/// Dividing by zero is an error here too, so scripts never see NaN.
+fn eval_float_infix_expression(operator: &str, left: f64, right: f64) -> Object {
+    match operator {
+        "+" => Object::Float(left + right),
+        "-" => Object::Float(left - right),
+        "*" => Object::Float(left * right),
+        "/" => match right {
+            0.0 => Object::Error("division by zero".to_string()),
+            _ => Object::Float(left / right),
+        },
+        "%" => match right {
+            0.0 => Object::Error("division by zero".to_string()),
+            _ => Object::Float(left % right),
+        },
+        "**" if left == 0.0 && right < 0.0 => Object::Error("division by zero".to_string()),
+        "**" if left < 0.0 && right.fract() != 0.0 => {
+            Object::Error("negative base with fractional exponent".to_string())
+        }
+        "**" => Object::Float(left.powf(right)),
+        "<" => Object::Boolean(left < right),
+        ">" => Object::Boolean(left > right),
+        "<=" => Object::Boolean(left <= right),
+        ">=" => Object::Boolean(left >= right),
+        "==" => Object::Boolean(left == right),
+        "!=" => Object::Boolean(left != right),
+        _ => Object::Error(format!("unknown operator: FLOAT {} FLOAT", operator)),
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt::Display;
+use std::time::Instant;
+
+/// How often, in steps, the wall clock is read while a deadline is set.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// Resource limits for untrusted scripts. `None` leaves that resource
+/// unbounded, which is the default for all of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Expressions that may be evaluated.
+    pub fuel: Option<u64>,
+    /// Function calls that may be active at once. Tail calls reuse their
+    /// caller's slot.
+    pub max_call_depth: Option<usize>,
+    /// Units of memory that may be allocated: one for each environment and
+    /// function, and the size of each string, bignum and collection produced,
+    /// in bytes, 32-bit digits and elements plus one respectively.
+    pub max_allocations: Option<usize>,
+    pub deadline: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+    Fuel,
+    CallDepth,
+    Allocations,
+    Deadline,
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fuel => write!(f, "fuel exhausted"),
+            Self::CallDepth => write!(f, "maximum call depth exceeded"),
+            Self::Allocations => write!(f, "allocation limit exceeded"),
+            Self::Deadline => write!(f, "deadline exceeded"),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// Tracks what a script has used against its limits. Fuel and allocations
+/// accumulate across programs until `refill` is called.
+#[derive(Debug, Default)]
+pub(super) struct Meter {
+    limits: Limits,
+    fuel_used: u64,
+    allocations: usize,
+    depth: usize,
+}
+
+impl Meter {
+    pub(super) fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub(super) fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+        self.refill();
+    }
+
+    pub(super) fn step(&mut self) -> Result<(), LimitExceeded> {
+        self.fuel_used += 1;
+
+        if self.limits.fuel.is_some_and(|fuel| self.fuel_used > fuel) {
+            return Err(LimitExceeded::Fuel);
+        }
+
+        match self.limits.deadline {
+            Some(deadline)
+                if (self.fuel_used - 1).is_multiple_of(DEADLINE_CHECK_INTERVAL)
+                    && Instant::now() >= deadline =>
+            {
+                Err(LimitExceeded::Deadline)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn allocate(&mut self, units: usize) -> Result<(), LimitExceeded> {
+        self.allocations = self.allocations.saturating_add(units);
+
+        match self.limits.max_allocations {
+            Some(max) if self.allocations > max => Err(LimitExceeded::Allocations),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn enter_call(&mut self) -> Result<(), LimitExceeded> {
+        match self.limits.max_call_depth {
+            Some(max) if self.depth >= max => Err(LimitExceeded::CallDepth),
+            _ => {
+                self.depth += 1;
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) fn leave_call(&mut self) {
+        self.depth -= 1;
+    }
+
+    pub(super) fn remaining_fuel(&self) -> Option<u64> {
+        self.limits
+            .fuel
+            .map(|fuel| fuel.saturating_sub(self.fuel_used))
+    }
+
+    pub(super) fn remaining_allocations(&self) -> Option<usize> {
+        self.limits
+            .max_allocations
+            .map(|max| max.saturating_sub(self.allocations))
+    }
+
+    pub(super) fn refill(&mut self) {
+        self.fuel_used = 0;
+        self.allocations = 0;
+    }
+}
@@ -0,0 +1,170 @@
+use crate::eval::limits::{LimitExceeded, Limits};
+use crate::eval::Evaluator;
+use crate::lexer::Lexer;
+use crate::object::heap::HeapStats;
+use crate::object::Object;
+use crate::parser::Parser;
+use crate::token::TokenType;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum InterpreterError {
+    Io(io::Error),
+    Lex(String),
+    Parse(Vec<String>),
+    Runtime(String),
+    Limit(LimitExceeded),
+}
+
+impl Display for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "i/o error: {}", err),
+            Self::Lex(msg) => write!(f, "lex error: {}", msg),
+            Self::Parse(errors) => write!(f, "parse error: {}", errors.join("; ")),
+            Self::Runtime(msg) => write!(f, "runtime error: {}", msg),
+            Self::Limit(limit) => write!(f, "limit exceeded: {}", limit),
+        }
+    }
+}
+
+impl std::error::Error for InterpreterError {}
+
+impl From<io::Error> for InterpreterError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Everything an embedder needs: source goes in, values or a typed error come
+/// out, and globals persist from one call to the next.
+#[derive(Debug, Default)]
+pub struct Interpreter {
+    evaluator: Evaluator,
+    warnings: Vec<String>,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn eval_str(&mut self, source: &str) -> Result<Object, InterpreterError> {
+        check_tokens(source)?;
+
+        let mut parser = Parser::new(Box::new(Lexer::new(source.to_string())));
+        let parsed = parser.parse_program();
+        self.warnings = parser.warnings().to_vec();
+        let program = match parsed {
+            Ok(program) => program,
+            Err(err) => {
+                let mut errors = parser.errors();
+                if !errors.contains(&err) {
+                    errors.push(err);
+                }
+                return Err(InterpreterError::Parse(errors));
+            }
+        };
+
+        let result = self.evaluator.eval_program(&program);
+        self.check_result(result)
+    }
+
+    pub fn eval_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Object, InterpreterError> {
+        let source = fs::read_to_string(path)?;
+        self.eval_str(&source)
+    }
+
+    /// Warnings from parsing the source given to the last `eval_str`, such
+    /// as match arms that can never be reached.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn set_global(&mut self, name: &str, value: Object) {
+        self.evaluator
+            .environment()
+            .borrow_mut()
+            .set(name.to_string(), value);
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Object> {
+        self.evaluator.environment().borrow().get(name)
+    }
+
+    /// Calls the function bound to `name` in the global environment.
+    pub fn call_function(
+        &mut self,
+        name: &str,
+        args: Vec<Object>,
+    ) -> Result<Object, InterpreterError> {
+        let function = self
+            .get_global(name)
+            .ok_or(InterpreterError::Runtime(format!(
+                "identifier not found: {}",
+                name
+            )))?;
+
+        let result = self.evaluator.call_function(function, args);
+        self.check_result(result)
+    }
+
+    pub fn register_native<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Object]) -> Result<Object, String> + 'static,
+    {
+        self.evaluator.register_native(name, arity, function);
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.evaluator.set_limits(limits);
+    }
+
+    pub fn limits(&self) -> &Limits {
+        self.evaluator.limits()
+    }
+
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.evaluator.remaining_fuel()
+    }
+
+    pub fn remaining_allocations(&self) -> Option<usize> {
+        self.evaluator.remaining_allocations()
+    }
+
+    pub fn refill(&mut self) {
+        self.evaluator.refill();
+    }
+
+    pub fn heap_stats(&self) -> HeapStats {
+        self.evaluator.heap_stats()
+    }
+
+    pub fn collect_garbage(&mut self) -> usize {
+        self.evaluator.collect_garbage()
+    }
+
+    fn check_result(&self, obj: Object) -> Result<Object, InterpreterError> {
+        match (obj, self.evaluator.limit_exceeded()) {
+            (Object::Error(_), Some(limit)) => Err(InterpreterError::Limit(limit)),
+            (Object::Error(msg), None) => Err(InterpreterError::Runtime(msg)),
+            (obj, _) => Ok(obj),
+        }
+    }
+}
+
+/// The parser reports an illegal token only as a missing parse function, so
+/// the source is scanned up front to report lexer errors as what they are.
+fn check_tokens(source: &str) -> Result<(), InterpreterError> {
+    let mut lexer = Lexer::new(source.to_string());
+
+    while lexer.next_token().token_type != TokenType::EOF {}
+
+    match lexer.errors() {
+        [] => Ok(()),
+        errors => Err(InterpreterError::Lex(errors.join("; "))),
+    }
+}
@@ -1,6 +1,6 @@
 use std::vec;
 
-use crate::token::{self, Token, TokenType};
+use crate::token::{self, Position, Token, TokenType};
 
 #[derive(Debug)]
 pub struct Lexer {
@@ -8,20 +8,35 @@ pub struct Lexer {
     position: usize,      // current position in input (points to current char)
     read_position: usize, // current reading position in input (after current char)
     ch: u8,               // current char under examimination
+    line: usize,          // line of the current char
+    line_start: usize,    // position of the first char on the current line
+    errors: Vec<String>,
 }
 
 impl Lexer {
     pub fn new(input: String) -> Self {
         let input = input.as_bytes().to_vec();
         Self {
-            ch: input[0],
+            ch: input.first().copied().unwrap_or(b'\0'),
             input,
             position: 0,
             read_position: 1,
+            line: 1,
+            line_start: 0,
+            errors: vec![],
         }
     }
 
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
     pub fn read_char(&mut self) {
+        if self.ch == b'\n' {
+            self.line += 1;
+            self.line_start = self.read_position;
+        }
+
         if self.read_position >= self.input.len() {
             self.ch = b'\0'
         } else {
@@ -34,6 +49,18 @@ impl Lexer {
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace();
 
+        let position = Position {
+            line: self.line,
+            column: self.position - self.line_start + 1,
+        };
+
+        Token {
+            position,
+            ..self.read_token()
+        }
+    }
+
+    fn read_token(&mut self) -> Token {
         let tok = match self.ch {
             b'=' => {
                 if self.peek_char() == b'=' {
@@ -43,6 +70,9 @@ impl Lexer {
                         TokenType::Eq,
                         String::from_utf8(vec![ch, self.ch]).unwrap_or_default(),
                     )
+                } else if self.peek_char() == b'>' {
+                    self.read_char();
+                    Token::new(TokenType::FatArrow, "=>".to_string())
                 } else {
                     Token::new(
                         TokenType::Assign,
@@ -50,6 +80,19 @@ impl Lexer {
                     )
                 }
             }
+            b'+' | b'-' | b'/' if self.peek_char() == b'=' => {
+                let ch = self.ch;
+                self.read_char();
+                let token_type = match ch {
+                    b'+' => TokenType::PlusAssign,
+                    b'-' => TokenType::MinusAssign,
+                    _ => TokenType::SlashAssign,
+                };
+                Token::new(
+                    token_type,
+                    String::from_utf8(vec![ch, self.ch]).unwrap_or_default(),
+                )
+            }
             b'+' => Token::new(
                 TokenType::Plus,
                 String::from_utf8(vec![self.ch]).unwrap_or_default(),
@@ -73,26 +116,147 @@ impl Lexer {
                     )
                 }
             }
-            b'*' => Token::new(
-                TokenType::Asterisk,
+            b'*' => {
+                if self.peek_char() == b'*' || self.peek_char() == b'=' {
+                    let ch = self.ch;
+                    self.read_char();
+                    let token_type = match self.ch {
+                        b'*' => TokenType::Power,
+                        _ => TokenType::AsteriskAssign,
+                    };
+                    Token::new(
+                        token_type,
+                        String::from_utf8(vec![ch, self.ch]).unwrap_or_default(),
+                    )
+                } else {
+                    Token::new(
+                        TokenType::Asterisk,
+                        String::from_utf8(vec![self.ch]).unwrap_or_default(),
+                    )
+                }
+            }
+            b'%' => Token::new(
+                TokenType::Percent,
                 String::from_utf8(vec![self.ch]).unwrap_or_default(),
             ),
-            b'/' => Token::new(
-                TokenType::Slash,
+            b'^' => Token::new(
+                TokenType::Caret,
                 String::from_utf8(vec![self.ch]).unwrap_or_default(),
             ),
-            b'<' => Token::new(
-                TokenType::Lt,
+            b'~' => Token::new(
+                TokenType::Tilde,
                 String::from_utf8(vec![self.ch]).unwrap_or_default(),
             ),
-            b'>' => Token::new(
-                TokenType::Gt,
+            b'/' => Token::new(
+                TokenType::Slash,
+                String::from_utf8(vec![self.ch]).unwrap_or_default(),
+            ),
+            b'<' => {
+                if self.peek_char() == b'=' || self.peek_char() == b'<' {
+                    let ch = self.ch;
+                    self.read_char();
+                    let token_type = match self.ch {
+                        b'=' => TokenType::LtEq,
+                        _ => TokenType::ShiftLeft,
+                    };
+                    Token::new(
+                        token_type,
+                        String::from_utf8(vec![ch, self.ch]).unwrap_or_default(),
+                    )
+                } else {
+                    Token::new(
+                        TokenType::Lt,
+                        String::from_utf8(vec![self.ch]).unwrap_or_default(),
+                    )
+                }
+            }
+            b'>' => {
+                if self.peek_char() == b'=' || self.peek_char() == b'>' {
+                    let ch = self.ch;
+                    self.read_char();
+                    let token_type = match self.ch {
+                        b'=' => TokenType::GtEq,
+                        _ => TokenType::ShiftRight,
+                    };
+                    Token::new(
+                        token_type,
+                        String::from_utf8(vec![ch, self.ch]).unwrap_or_default(),
+                    )
+                } else {
+                    Token::new(
+                        TokenType::Gt,
+                        String::from_utf8(vec![self.ch]).unwrap_or_default(),
+                    )
+                }
+            }
+            b'&' | b'|' => {
+                if self.peek_char() == self.ch {
+                    let ch = self.ch;
+                    self.read_char();
+                    let token_type = match ch {
+                        b'&' => TokenType::And,
+                        _ => TokenType::Or,
+                    };
+                    Token::new(
+                        token_type,
+                        String::from_utf8(vec![ch, self.ch]).unwrap_or_default(),
+                    )
+                } else {
+                    let token_type = match self.ch {
+                        b'&' => TokenType::Ampersand,
+                        _ => TokenType::Pipe,
+                    };
+                    Token::new(
+                        token_type,
+                        String::from_utf8(vec![self.ch]).unwrap_or_default(),
+                    )
+                }
+            }
+            b'?' => match self.peek_char() {
+                b'?' | b'.' | b'[' => {
+                    let ch = self.ch;
+                    self.read_char();
+                    let token_type = match self.ch {
+                        b'?' => TokenType::NullCoalesce,
+                        b'.' => TokenType::OptionalDot,
+                        _ => TokenType::OptionalBracket,
+                    };
+                    Token::new(
+                        token_type,
+                        String::from_utf8(vec![ch, self.ch]).unwrap_or_default(),
+                    )
+                }
+                _ => {
+                    self.errors.push("illegal character \"?\"".to_string());
+                    Token::new(TokenType::Illegal, "?".to_string())
+                }
+            },
+            b'.' if self.peek_char() == b'.' => {
+                self.read_char();
+                match self.peek_char() {
+                    b'=' => {
+                        self.read_char();
+                        Token::new(TokenType::DotDotEq, "..=".to_string())
+                    }
+                    b'.' => {
+                        self.read_char();
+                        Token::new(TokenType::Ellipsis, "...".to_string())
+                    }
+                    _ => Token::new(TokenType::DotDot, "..".to_string()),
+                }
+            }
+            b'.' => Token::new(
+                TokenType::Dot,
                 String::from_utf8(vec![self.ch]).unwrap_or_default(),
             ),
             b';' => Token::new(
                 TokenType::Semicolon,
                 String::from_utf8(vec![self.ch]).unwrap_or_default(),
             ),
+            b':' => Token::new(
+                TokenType::Colon,
+                String::from_utf8(vec![self.ch]).unwrap_or_default(),
+            ),
             b'(' => Token::new(
                 TokenType::LParen,
                 String::from_utf8(vec![self.ch]).unwrap_or_default(),
@@ -113,6 +277,37 @@ impl Lexer {
                 TokenType::RBrace,
                 String::from_utf8(vec![self.ch]).unwrap_or_default(),
             ),
+            b'[' => Token::new(
+                TokenType::LBracket,
+                String::from_utf8(vec![self.ch]).unwrap_or_default(),
+            ),
+            b']' => Token::new(
+                TokenType::RBracket,
+                String::from_utf8(vec![self.ch]).unwrap_or_default(),
+            ),
+            // `'name` labels a loop for `break` and `continue` to refer to.
+            b'\'' if is_letter(self.peek_char()) => {
+                self.read_char();
+                let label = self.read_identifier();
+                return Token::new(
+                    TokenType::Label,
+                    String::from_utf8(label.into()).unwrap_or_default(),
+                );
+            }
+            b'"' => {
+                let start = self.position;
+                match self.read_string() {
+                    Ok(value) => Token::new(TokenType::String, value),
+                    Err(err) => {
+                        self.errors.push(err);
+                        let end = self.position.min(self.input.len());
+                        Token::new(
+                            TokenType::Illegal,
+                            String::from_utf8_lossy(&self.input[start..end]).into_owned(),
+                        )
+                    }
+                }
+            }
 
             b'\0' => Token::new(
                 TokenType::EOF,
@@ -133,15 +328,18 @@ impl Lexer {
                         );
                     }
                 } else if is_digit(self.ch) {
+                    let (token_type, literal) = self.read_number();
                     return Token::new(
-                        TokenType::Int,
-                        String::from_utf8(self.read_number().into()).unwrap_or_default(),
+                        token_type,
+                        String::from_utf8(literal.into()).unwrap_or_default(),
                     );
                 } else {
-                    return Token::new(
-                        TokenType::Illegal,
-                        String::from_utf8(vec![self.ch]).unwrap_or_default(),
-                    );
+                    let (literal, width) = self.current_char();
+                    for _ in 0..width {
+                        self.read_char();
+                    }
+                    self.errors.push(format!("illegal character {:?}", literal));
+                    return Token::new(TokenType::Illegal, literal);
                 }
             }
         };
@@ -157,12 +355,136 @@ impl Lexer {
         &self.input[position..self.position]
     }
 
-    fn read_number(&mut self) -> &[u8] {
+    /// Reads an integer or float literal. A `.` only continues the number
+    /// when a digit follows it, and an `e` only when an optionally signed
+    /// exponent does, so `1..2` and `2else` still lex as before.
+    fn read_number(&mut self) -> (TokenType, &[u8]) {
         let position = self.position;
+        let mut token_type = TokenType::Int;
+
+        self.read_digits();
+
+        if self.ch == b'.' && is_digit(self.peek_char()) {
+            token_type = TokenType::Float;
+            self.read_char();
+            self.read_digits();
+        }
+
+        if self.ch == b'e' || self.ch == b'E' {
+            let sign = usize::from(matches!(self.peek_char(), b'+' | b'-'));
+            if is_digit(self.peek_nth(sign + 1)) {
+                token_type = TokenType::Float;
+                for _ in 0..=sign {
+                    self.read_char();
+                }
+                self.read_digits();
+            }
+        }
+
+        (token_type, &self.input[position..(self.position)])
+    }
+
+    fn read_digits(&mut self) {
         while is_digit(self.ch) {
             self.read_char();
         }
-        &self.input[position..(self.position)]
+    }
+
+    /// Reads a string literal up to its closing quote, leaving the lexer on
+    /// that quote. On a bad escape the rest of the literal is still consumed so
+    /// lexing can resume after it.
+    fn read_string(&mut self) -> Result<String, String> {
+        let mut value = Vec::new();
+        let mut error = None;
+
+        loop {
+            self.read_char();
+
+            match self.ch {
+                b'"' => break,
+                b'\0' if self.position >= self.input.len() => {
+                    return Err("unterminated string".to_string())
+                }
+                b'\\' => {
+                    if self.read_position >= self.input.len() {
+                        return Err("unterminated string".to_string());
+                    }
+                    self.read_char();
+
+                    match self.read_escape() {
+                        Ok(ch) => value.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes()),
+                        Err(err) => {
+                            error.get_or_insert(err);
+                        }
+                    }
+                }
+                ch => value.push(ch),
+            }
+        }
+
+        match error {
+            Some(err) => Err(err),
+            None => Ok(String::from_utf8(value).unwrap_or_default()),
+        }
+    }
+
+    /// Decodes the escape whose first character (after the backslash) is
+    /// under the cursor, leaving the cursor on its last character.
+    fn read_escape(&mut self) -> Result<char, String> {
+        match self.ch {
+            b'n' => Ok('\n'),
+            b't' => Ok('\t'),
+            b'r' => Ok('\r'),
+            b'"' => Ok('"'),
+            b'\\' => Ok('\\'),
+            b'u' => {
+                if self.peek_char() != b'{' {
+                    return Err("invalid unicode escape: expected '{' after \\u".to_string());
+                }
+                self.read_char();
+
+                let mut digits = String::new();
+                while self.peek_char().is_ascii_hexdigit() {
+                    self.read_char();
+                    digits.push(self.ch as char);
+                }
+
+                if self.peek_char() != b'}' {
+                    return Err(format!("invalid unicode escape \\u{{{}", digits));
+                }
+                self.read_char();
+
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or(format!("invalid unicode escape \\u{{{}}}", digits))
+            }
+            _ => {
+                let (escape, width) = self.current_char();
+                for _ in 1..width {
+                    self.read_char();
+                }
+                Err(format!("unknown escape sequence \\{}", escape))
+            }
+        }
+    }
+
+    /// Decodes the possibly multi-byte character starting under the cursor,
+    /// returning it along with its width in bytes.
+    fn current_char(&self) -> (String, usize) {
+        let width = match self.ch {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        let end = (self.position + width).min(self.input.len());
+
+        (
+            String::from_utf8_lossy(&self.input[self.position..end]).into_owned(),
+            width,
+        )
     }
 
     fn skip_whitespace(&mut self) {
@@ -171,19 +493,20 @@ impl Lexer {
         }
     }
 
-    fn peek_char(&mut self) -> u8 {
-        if self.read_position >= self.input.len() {
-            b'\0'
-        } else {
-            self.input[self.read_position]
-        }
+    fn peek_char(&self) -> u8 {
+        self.peek_nth(1)
+    }
+
+    /// The byte `n` places past the current one.
+    fn peek_nth(&self, n: usize) -> u8 {
+        self.input.get(self.position + n).copied().unwrap_or(b'\0')
     }
 }
 
 fn is_letter(ch: u8) -> bool {
-    b'a' <= ch && ch <= b'z' || b'A' <= ch && ch <= b'Z' || ch == b'_'
+    ch.is_ascii_lowercase() || ch.is_ascii_uppercase() || ch == b'_'
 }
 
 fn is_digit(ch: u8) -> bool {
-    b'0' <= ch && ch <= b'9'
+    ch.is_ascii_digit()
 }
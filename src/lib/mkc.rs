@@ -0,0 +1,534 @@
+//! Reading and writing compiled programs as `.mkc` files.
+//!
+//! Layout, all integers big-endian:
+//!
+//! ```text
+//! magic        b"MKC\0"
+//! version      u16
+//! constants    u32 count, then per constant a u8 tag and its payload
+//! instructions u32 length, then the main program's instructions
+//! checksum     u32 FNV-1a of every preceding byte
+//! ```
+//!
+//! Strings are stored as a `u32` byte length and UTF-8 bytes, and floats
+//! as their IEEE 754 bits. Integers too large for an `i64` are stored as
+//! their decimal digits, in the same layout as strings. Function
+//! constants are stored as prototypes: `u16` locals, `u8`
+//! parameters and a length-prefixed instruction stream. Struct types are
+//! stored as their name, then a `u16` count of field names. Match patterns
+//! are stored as a tree of nodes, each a kind byte and its payload, with
+//! literals stored as constants and lists prefixed by a `u16` count. Source
+//! maps are not stored, so loaded programs disassemble without tokens.
+
+use crate::code::{self, Instructions, Opcode, SourceMap};
+use crate::compiler::Bytecode;
+use crate::object::bigint::BigInt;
+use crate::object::{CompiledFunction, Matcher, Object, StructType};
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+pub const MAGIC: &[u8; 4] = b"MKC\0";
+pub const FORMAT_VERSION: u16 = 1;
+
+const TAG_INTEGER: u8 = 0x01;
+const TAG_FUNCTION: u8 = 0x02;
+const TAG_STRING: u8 = 0x03;
+const TAG_FLOAT: u8 = 0x04;
+const TAG_BIGINT: u8 = 0x05;
+const TAG_STRUCT_TYPE: u8 = 0x06;
+const TAG_MATCHER: u8 = 0x07;
+
+// Kinds of node in a match pattern.
+const MATCH_BIND: u8 = 0x00;
+const MATCH_WILDCARD: u8 = 0x01;
+const MATCH_LITERAL: u8 = 0x02;
+const MATCH_TRUE: u8 = 0x03;
+const MATCH_FALSE: u8 = 0x04;
+const MATCH_NULL: u8 = 0x05;
+const MATCH_TUPLE: u8 = 0x06;
+const MATCH_ARRAY: u8 = 0x07;
+const MATCH_HASH: u8 = 0x08;
+
+/// How deeply match patterns may nest in a loaded file. Patterns are decoded
+/// recursively, and a crafted file must not be able to overflow the stack.
+const MAX_PATTERN_DEPTH: usize = 256;
+
+#[derive(Debug)]
+pub enum MkcError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u16),
+    Truncated,
+    ChecksumMismatch,
+    TrailingBytes,
+    UnknownConstantTag(u8),
+    InvalidString,
+    InvalidBigInt,
+    InvalidPattern,
+    PatternTooDeep,
+    UnsupportedConstant(&'static str),
+    TooLarge(&'static str),
+    InvalidOpcode(u8),
+    InvalidOperand { offset: usize },
+}
+
+impl Display for MkcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "i/o error: {}", err),
+            Self::BadMagic => write!(f, "not a monkey bytecode file"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported bytecode version {}, expected {}",
+                version, FORMAT_VERSION
+            ),
+            Self::Truncated => write!(f, "bytecode file is truncated"),
+            Self::ChecksumMismatch => write!(f, "bytecode file is corrupted: checksum mismatch"),
+            Self::TrailingBytes => write!(f, "bytecode file has trailing bytes"),
+            Self::UnknownConstantTag(tag) => write!(f, "unknown constant tag {:#04x}", tag),
+            Self::InvalidString => write!(f, "string constant is not valid UTF-8"),
+            Self::InvalidBigInt => write!(f, "integer constant is not a valid number"),
+            Self::InvalidPattern => write!(f, "match pattern constant is malformed"),
+            Self::PatternTooDeep => write!(
+                f,
+                "match pattern constant nests deeper than {}",
+                MAX_PATTERN_DEPTH
+            ),
+            Self::UnsupportedConstant(type_name) => {
+                write!(f, "constant of type {} cannot be serialized", type_name)
+            }
+            Self::TooLarge(what) => write!(f, "{} too large to serialize", what),
+            Self::InvalidOpcode(byte) => write!(f, "opcode {} undefined", byte),
+            Self::InvalidOperand { offset } => {
+                write!(f, "invalid operand for instruction at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MkcError {}
+
+impl From<io::Error> for MkcError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+pub fn encode(bytecode: &Bytecode) -> Result<Vec<u8>, MkcError> {
+    let mut out = Vec::new();
+
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+
+    encode_u32(bytecode.constants.len(), "constant count", &mut out)?;
+    for constant in bytecode.constants.iter() {
+        encode_constant(constant, &mut out)?;
+    }
+
+    encode_instructions(&bytecode.instructions, &mut out)?;
+
+    let checksum = fnv1a(&out);
+    out.extend_from_slice(&checksum.to_be_bytes());
+
+    Ok(out)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Bytecode, MkcError> {
+    let mut reader = Reader {
+        bytes,
+        position: 0,
+        depth: 0,
+    };
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(MkcError::BadMagic);
+    }
+
+    let version = reader.u16()?;
+    if version != FORMAT_VERSION {
+        return Err(MkcError::UnsupportedVersion(version));
+    }
+
+    if bytes.len() < reader.position + 4 {
+        return Err(MkcError::Truncated);
+    }
+    let (body, checksum) = bytes.split_at(bytes.len() - 4);
+    if fnv1a(body).to_be_bytes() != checksum {
+        return Err(MkcError::ChecksumMismatch);
+    }
+    reader.bytes = body;
+
+    let count = reader.u32()? as usize;
+    let mut constants = Vec::new();
+    for _ in 0..count {
+        constants.push(reader.constant()?);
+    }
+
+    let instructions = reader.instructions()?;
+
+    if reader.position != reader.bytes.len() {
+        return Err(MkcError::TrailingBytes);
+    }
+
+    verify_instructions(&instructions, constants.len())?;
+    for constant in constants.iter() {
+        if let Object::CompiledFunction(function) = constant {
+            verify_instructions(&function.instructions, constants.len())?;
+        }
+    }
+
+    Ok(Bytecode {
+        instructions,
+        constants,
+        source_map: SourceMap::new(),
+    })
+}
+
+pub fn write_file<P: AsRef<Path>>(path: P, bytecode: &Bytecode) -> Result<(), MkcError> {
+    fs::write(path, encode(bytecode)?)?;
+    Ok(())
+}
+
+pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Bytecode, MkcError> {
+    decode(&fs::read(path)?)
+}
+
+fn encode_constant(constant: &Object, out: &mut Vec<u8>) -> Result<(), MkcError> {
+    match constant {
+        Object::Integer(value) => {
+            out.push(TAG_INTEGER);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+        Object::Float(value) => {
+            out.push(TAG_FLOAT);
+            out.extend_from_slice(&value.to_bits().to_be_bytes());
+        }
+        Object::String(value) => {
+            out.push(TAG_STRING);
+            encode_string(value, out)?;
+        }
+        Object::BigInt(value) => {
+            out.push(TAG_BIGINT);
+            encode_string(&value.to_string(), out)?;
+        }
+        Object::StructType(ty) => {
+            out.push(TAG_STRUCT_TYPE);
+            encode_string(&ty.name, out)?;
+            encode_u16(ty.fields.len(), "struct field count", out)?;
+            for field in ty.fields.iter() {
+                encode_string(field, out)?;
+            }
+        }
+        Object::CompiledFunction(function) => {
+            out.push(TAG_FUNCTION);
+            encode_u16(function.num_locals, "function locals count", out)?;
+            let num_parameters = u8::try_from(function.num_parameters)
+                .map_err(|_| MkcError::TooLarge("function parameter count"))?;
+            out.push(num_parameters);
+            encode_instructions(&function.instructions, out)?;
+        }
+        Object::Matcher(matcher) => {
+            out.push(TAG_MATCHER);
+            encode_matcher(matcher, out)?;
+        }
+        other => return Err(MkcError::UnsupportedConstant(other.type_name())),
+    }
+
+    Ok(())
+}
+
+fn encode_matcher(matcher: &Matcher, out: &mut Vec<u8>) -> Result<(), MkcError> {
+    match matcher {
+        Matcher::Bind(name) => {
+            out.push(MATCH_BIND);
+            encode_string(name, out)?;
+        }
+        Matcher::Wildcard => out.push(MATCH_WILDCARD),
+        Matcher::Literal(Object::Boolean(true)) => out.push(MATCH_TRUE),
+        Matcher::Literal(Object::Boolean(false)) => out.push(MATCH_FALSE),
+        Matcher::Literal(Object::Null) => out.push(MATCH_NULL),
+        Matcher::Literal(value) => {
+            out.push(MATCH_LITERAL);
+            encode_constant(value, out)?;
+        }
+        Matcher::Tuple(elements) => {
+            out.push(MATCH_TUPLE);
+            encode_matchers(elements, out)?;
+        }
+        Matcher::Array { elements, rest } => {
+            out.push(MATCH_ARRAY);
+            encode_matchers(elements, out)?;
+            match rest {
+                Some(rest) => {
+                    out.push(1);
+                    encode_matcher(rest, out)?;
+                }
+                None => out.push(0),
+            }
+        }
+        Matcher::Hash(pairs) => {
+            out.push(MATCH_HASH);
+            encode_u16(pairs.len(), "match pattern length", out)?;
+            for (key, value) in pairs.iter() {
+                encode_matcher(&Matcher::Literal(key.into()), out)?;
+                encode_matcher(value, out)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn encode_matchers(matchers: &[Matcher], out: &mut Vec<u8>) -> Result<(), MkcError> {
+    encode_u16(matchers.len(), "match pattern length", out)?;
+    for matcher in matchers.iter() {
+        encode_matcher(matcher, out)?;
+    }
+
+    Ok(())
+}
+
+fn encode_string(value: &str, out: &mut Vec<u8>) -> Result<(), MkcError> {
+    encode_u32(value.len(), "string", out)?;
+    out.extend_from_slice(value.as_bytes());
+
+    Ok(())
+}
+
+fn encode_instructions(instructions: &Instructions, out: &mut Vec<u8>) -> Result<(), MkcError> {
+    encode_u32(instructions.len(), "instruction stream", out)?;
+    out.extend_from_slice(instructions);
+
+    Ok(())
+}
+
+fn encode_u16(value: usize, what: &'static str, out: &mut Vec<u8>) -> Result<(), MkcError> {
+    let value = u16::try_from(value).map_err(|_| MkcError::TooLarge(what))?;
+    out.extend_from_slice(&value.to_be_bytes());
+
+    Ok(())
+}
+
+fn encode_u32(value: usize, what: &'static str, out: &mut Vec<u8>) -> Result<(), MkcError> {
+    let value = u32::try_from(value).map_err(|_| MkcError::TooLarge(what))?;
+    out.extend_from_slice(&value.to_be_bytes());
+
+    Ok(())
+}
+
+/// Walks an instruction stream checking that every opcode is known, every
+/// operand is complete, constants exist and jumps land at the start of an
+/// instruction or the end. This does not make a loaded program well behaved:
+/// local, free variable and stack accesses are checked by the VM as it runs,
+/// and fail with an error.
+fn verify_instructions(instructions: &[u8], num_constants: usize) -> Result<(), MkcError> {
+    let mut offset = 0;
+    // Every offset an instruction starts at, and the end.
+    let mut starts = vec![false; instructions.len() + 1];
+    let mut jumps = Vec::new();
+
+    while offset < instructions.len() {
+        let op = Opcode::from_byte(instructions[offset])
+            .ok_or(MkcError::InvalidOpcode(instructions[offset]))?;
+        let def = op.definition();
+
+        let width: usize = def.operand_widths.iter().sum();
+        if offset + 1 + width > instructions.len() {
+            return Err(MkcError::InvalidOperand { offset });
+        }
+
+        let (operands, read) = code::read_operands(&def, &instructions[offset + 1..]);
+
+        let valid = match op {
+            Opcode::Constant | Opcode::Closure => operands[0] < num_constants,
+            Opcode::Match => operands[1] < num_constants,
+            _ => true,
+        };
+        if !valid {
+            return Err(MkcError::InvalidOperand { offset });
+        }
+
+        if matches!(
+            op,
+            Opcode::Jump
+                | Opcode::JumpNotTruthy
+                | Opcode::JumpNull
+                | Opcode::JumpNotNull
+                | Opcode::JumpTruthy
+                | Opcode::JumpFalsy
+                | Opcode::IterNext
+                | Opcode::Match
+        ) {
+            jumps.push((offset, operands[0]));
+        }
+
+        starts[offset] = true;
+        offset += 1 + read;
+    }
+    starts[instructions.len()] = true;
+
+    match jumps
+        .into_iter()
+        .find(|(_, target)| !starts.get(*target).copied().unwrap_or(false))
+    {
+        Some((offset, _)) => Err(MkcError::InvalidOperand { offset }),
+        None => Ok(()),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    // How many patterns enclose the one being read.
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], MkcError> {
+        let end = self.position.checked_add(n).ok_or(MkcError::Truncated)?;
+        let slice = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(MkcError::Truncated)?;
+        self.position = end;
+
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, MkcError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, MkcError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, MkcError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes(bytes.try_into().expect("took 4 bytes")))
+    }
+
+    fn i64(&mut self) -> Result<i64, MkcError> {
+        let bytes = self.take(8)?;
+        Ok(i64::from_be_bytes(bytes.try_into().expect("took 8 bytes")))
+    }
+
+    fn string(&mut self) -> Result<String, MkcError> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?.to_vec();
+
+        String::from_utf8(bytes).map_err(|_| MkcError::InvalidString)
+    }
+
+    fn instructions(&mut self) -> Result<Instructions, MkcError> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn constant(&mut self) -> Result<Object, MkcError> {
+        match self.u8()? {
+            TAG_INTEGER => Ok(Object::Integer(self.i64()?)),
+            TAG_FLOAT => Ok(Object::Float(f64::from_bits(self.i64()? as u64))),
+            TAG_STRING => self.string().map(Object::String),
+            TAG_BIGINT => BigInt::from_decimal(&self.string()?)
+                .map(Object::BigInt)
+                .ok_or(MkcError::InvalidBigInt),
+            TAG_STRUCT_TYPE => {
+                let name = self.string()?;
+                let fields = (0..self.u16()?)
+                    .map(|_| self.string())
+                    .collect::<Result<Vec<String>, MkcError>>()?;
+
+                Ok(Object::StructType(Rc::new(StructType { name, fields })))
+            }
+            TAG_FUNCTION => {
+                let num_locals = self.u16()? as usize;
+                let num_parameters = self.u8()? as usize;
+                let instructions = self.instructions()?;
+
+                Ok(Object::CompiledFunction(Rc::new(CompiledFunction {
+                    instructions,
+                    num_locals,
+                    num_parameters,
+                    source_map: SourceMap::new(),
+                })))
+            }
+            TAG_MATCHER => self
+                .matcher()
+                .map(|matcher| Object::Matcher(Rc::new(matcher))),
+            tag => Err(MkcError::UnknownConstantTag(tag)),
+        }
+    }
+
+    fn matcher(&mut self) -> Result<Matcher, MkcError> {
+        if self.depth == MAX_PATTERN_DEPTH {
+            return Err(MkcError::PatternTooDeep);
+        }
+
+        self.depth += 1;
+        let matcher = self.matcher_body();
+        self.depth -= 1;
+
+        matcher
+    }
+
+    fn matcher_body(&mut self) -> Result<Matcher, MkcError> {
+        match self.u8()? {
+            MATCH_BIND => self.string().map(Matcher::Bind),
+            MATCH_WILDCARD => Ok(Matcher::Wildcard),
+            MATCH_LITERAL => match self.constant()? {
+                value @ (Object::Integer(_)
+                | Object::BigInt(_)
+                | Object::Float(_)
+                | Object::String(_)) => Ok(Matcher::Literal(value)),
+                _ => Err(MkcError::InvalidPattern),
+            },
+            MATCH_TRUE => Ok(Matcher::Literal(Object::Boolean(true))),
+            MATCH_FALSE => Ok(Matcher::Literal(Object::Boolean(false))),
+            MATCH_NULL => Ok(Matcher::Literal(Object::Null)),
+            MATCH_TUPLE => self.matchers().map(Matcher::Tuple),
+            MATCH_ARRAY => {
+                let elements = self.matchers()?;
+                let rest = match self.u8()? {
+                    0 => None,
+                    _ => Some(Box::new(self.matcher()?)),
+                };
+
+                Ok(Matcher::Array { elements, rest })
+            }
+            MATCH_HASH => {
+                let pairs = (0..self.u16()?)
+                    .map(|_| {
+                        let key = match self.matcher()? {
+                            Matcher::Literal(key) => key.hash_key(),
+                            _ => None,
+                        };
+                        Ok((key.ok_or(MkcError::InvalidPattern)?, self.matcher()?))
+                    })
+                    .collect::<Result<Vec<_>, MkcError>>()?;
+
+                Ok(Matcher::Hash(pairs))
+            }
+            _ => Err(MkcError::InvalidPattern),
+        }
+    }
+
+    fn matchers(&mut self) -> Result<Vec<Matcher>, MkcError> {
+        (0..self.u16()?).map(|_| self.matcher()).collect()
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+
+    for byte in bytes.iter() {
+        hash ^= *byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+
+    hash
+}
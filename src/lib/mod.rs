@@ -1,5 +1,13 @@
 pub mod ast;
+pub mod code;
+pub mod compiler;
+pub mod disassembler;
+pub mod eval;
+pub mod interpreter;
 pub mod lexer;
+pub mod mkc;
+pub mod object;
 pub mod parser;
 pub mod repl;
 pub mod token;
+pub mod vm;
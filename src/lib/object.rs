@@ -0,0 +1,447 @@
+pub mod bigint;
+pub mod environment;
+pub mod heap;
+pub mod iter;
+pub mod native;
+
+use crate::ast::expression::{self, Expression};
+use crate::ast::pattern::Pattern;
+use crate::ast::statement;
+use crate::code::{Instructions, SourceMap};
+use bigint::BigInt;
+use environment::Env;
+use iter::Iter;
+use native::NativeFunction;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
+pub enum Object {
+    Integer(i64),
+    /// An integer outside the range of `i64`. Arithmetic produces this only
+    /// on overflow, so values that fit are always `Integer`.
+    BigInt(BigInt),
+    Float(f64),
+    Boolean(bool),
+    String(String),
+    Array(Rc<Vec<Object>>),
+    /// A fixed-size group of values, written `(1, "x")`.
+    Tuple(Rc<Vec<Object>>),
+    Hash(Rc<BTreeMap<HashKey, Object>>),
+    Range(Range),
+    StructType(Rc<StructType>),
+    Struct(Rc<Struct>),
+    Null,
+    ReturnValue(Box<Object>),
+    TailCall(Box<Object>, Vec<Object>),
+    /// Unwinds to the loop a `break` or `continue` with this label refers to,
+    /// or to the innermost loop without one.
+    Break(Option<String>),
+    Continue(Option<String>),
+    /// A `for` loop's progress, which the VM keeps on the stack while the
+    /// loop runs.
+    Iterator(Box<Iter>),
+    /// A `match` arm's pattern, which the VM keeps as a constant.
+    Matcher(Rc<Matcher>),
+    /// A mutable variable in the VM that closures may capture, shared with
+    /// them so every one sees its assignments.
+    Cell(Rc<RefCell<Object>>),
+    Error(String),
+    Function(Rc<Function>),
+    CompiledFunction(Rc<CompiledFunction>),
+    Closure(Rc<Closure>),
+    Native(Rc<NativeFunction>),
+}
+
+impl Object {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Integer(_) => "INTEGER",
+            Self::BigInt(_) => "INTEGER",
+            Self::Float(_) => "FLOAT",
+            Self::Boolean(_) => "BOOLEAN",
+            Self::String(_) => "STRING",
+            Self::Array(_) => "ARRAY",
+            Self::Tuple(_) => "TUPLE",
+            Self::Hash(_) => "HASH",
+            Self::Range(_) => "RANGE",
+            Self::StructType(_) => "STRUCT_TYPE",
+            Self::Struct(_) => "STRUCT",
+            Self::Null => "NULL",
+            Self::ReturnValue(_) => "RETURN_VALUE",
+            Self::TailCall(_, _) => "TAIL_CALL",
+            Self::Break(_) => "BREAK",
+            Self::Continue(_) => "CONTINUE",
+            Self::Iterator(_) => "ITERATOR",
+            Self::Matcher(_) => "MATCHER",
+            Self::Cell(_) => "CELL",
+            Self::Error(_) => "ERROR",
+            Self::Function(_) => "FUNCTION",
+            Self::CompiledFunction(_) => "COMPILED_FUNCTION",
+            Self::Closure(_) => "CLOSURE",
+            Self::Native(_) => "NATIVE_FUNCTION",
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Error(_))
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Self::Null | Self::Boolean(false))
+    }
+
+    /// The key this value is stored under in a hash, or `None` if it cannot be
+    /// used as one.
+    pub fn hash_key(&self) -> Option<HashKey> {
+        match self {
+            Self::Integer(int) => Some(HashKey::Integer(*int)),
+            Self::BigInt(int) => Some(HashKey::BigInt(int.clone())),
+            Self::Boolean(boolean) => Some(HashKey::Boolean(*boolean)),
+            Self::String(string) => Some(HashKey::String(string.clone())),
+            _ => None,
+        }
+    }
+
+    /// Formats the value the way it would be written in source, so strings
+    /// nested in collections are quoted.
+    pub fn inspect(&self) -> String {
+        match self {
+            Self::String(string) => format!("{:?}", string),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer(int) => write!(f, "{}", int),
+            Self::BigInt(int) => write!(f, "{}", int),
+            Self::Float(float) => write!(f, "{:?}", float),
+            Self::Boolean(boolean) => write!(f, "{}", boolean),
+            Self::String(string) => write!(f, "{}", string),
+            Self::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.inspect()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Self::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.inspect()).collect();
+                match elements.as_slice() {
+                    [element] => write!(f, "({},)", element),
+                    _ => write!(f, "({})", elements.join(", ")),
+                }
+            }
+            Self::Hash(pairs) => {
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value.inspect()))
+                    .collect();
+                write!(f, "{{{}}}", pairs.join(", "))
+            }
+            Self::Range(range) => write!(f, "{}", range),
+            Self::StructType(ty) => write!(f, "{}", ty),
+            Self::Struct(value) => write!(f, "{}", value),
+            Self::Null => write!(f, "null"),
+            Self::ReturnValue(value) => write!(f, "{}", value),
+            Self::TailCall(function, _) => write!(f, "tail call to {}", function),
+            Self::Break(Some(label)) => write!(f, "break '{}", label),
+            Self::Break(None) => write!(f, "break"),
+            Self::Continue(Some(label)) => write!(f, "continue '{}", label),
+            Self::Continue(None) => write!(f, "continue"),
+            Self::Iterator(_) => write!(f, "<iterator>"),
+            Self::Matcher(matcher) => write!(f, "{}", matcher),
+            Self::Cell(_) => write!(f, "<cell>"),
+            Self::Error(msg) => write!(f, "ERROR: {}", msg),
+            Self::Function(function) => write!(f, "{}", function),
+            Self::CompiledFunction(function) => write!(f, "{}", function),
+            Self::Closure(closure) => write!(f, "{}", closure.function),
+            Self::Native(native) => write!(f, "{}", native),
+        }
+    }
+}
+
+impl From<BigInt> for Object {
+    /// Results that fit in an `i64` are demoted back to `Integer`.
+    fn from(value: BigInt) -> Self {
+        match value.to_i64() {
+            Some(int) => Self::Integer(int),
+            None => Self::BigInt(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub inclusive: bool,
+}
+
+impl Range {
+    /// Resolves the range against a sequence of `len` items, returning the
+    /// half-open span it selects. Missing bounds extend to that end, negative
+    /// bounds count back from the end, and the result is clamped to the
+    /// sequence, so the span may be empty but never out of bounds.
+    pub fn slice_bounds(&self, len: usize) -> (usize, usize) {
+        let len = len as i64;
+        let resolve = |bound: i64| if bound < 0 { bound + len } else { bound };
+
+        let start = self.start.map_or(0, resolve).clamp(0, len);
+        let end = match self.end {
+            Some(end) if self.inclusive => resolve(end).saturating_add(1),
+            Some(end) => resolve(end),
+            None => len,
+        }
+        .clamp(start, len);
+
+        (start as usize, end as usize)
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(start) = self.start {
+            write!(f, "{}", start)?;
+        }
+        write!(f, "{}", if self.inclusive { "..=" } else { ".." })?;
+        if let Some(end) = self.end {
+            write!(f, "{}", end)?;
+        }
+        Ok(())
+    }
+}
+
+/// A declared record type, which struct literals are built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+impl StructType {
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field == name)
+    }
+}
+
+impl From<&statement::Struct> for StructType {
+    fn from(declaration: &statement::Struct) -> Self {
+        Self {
+            name: declaration.name.value.clone(),
+            fields: declaration
+                .fields
+                .iter()
+                .map(|field| field.value.clone())
+                .collect(),
+        }
+    }
+}
+
+impl Display for StructType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "struct {} {{ {} }}", self.name, self.fields.join(", "))
+    }
+}
+
+/// A value of a `StructType`, holding its fields in declaration order.
+#[derive(Debug, Clone)]
+pub struct Struct {
+    pub ty: Rc<StructType>,
+    pub values: Vec<Object>,
+}
+
+impl Struct {
+    pub fn get(&self, field: &str) -> Option<&Object> {
+        self.ty
+            .field_index(field)
+            .and_then(|index| self.values.get(index))
+    }
+}
+
+impl Display for Struct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: Vec<String> = self
+            .ty
+            .fields
+            .iter()
+            .zip(self.values.iter())
+            .map(|(name, value)| format!("{}: {}", name, value.inspect()))
+            .collect();
+
+        write!(f, "{} {{ {} }}", self.ty.name, fields.join(", "))
+    }
+}
+
+/// Hash keys are kept ordered so that hashes print deterministically.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HashKey {
+    Integer(i64),
+    BigInt(BigInt),
+    Boolean(bool),
+    String(String),
+}
+
+impl Display for HashKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer(int) => write!(f, "{}", int),
+            Self::BigInt(int) => write!(f, "{}", int),
+            Self::Boolean(boolean) => write!(f, "{}", boolean),
+            Self::String(string) => write!(f, "{:?}", string),
+        }
+    }
+}
+
+impl From<&HashKey> for Object {
+    fn from(key: &HashKey) -> Self {
+        match key {
+            HashKey::Integer(int) => Self::Integer(*int),
+            HashKey::BigInt(int) => Self::BigInt(int.clone()),
+            HashKey::Boolean(boolean) => Self::Boolean(*boolean),
+            HashKey::String(string) => Self::String(string.clone()),
+        }
+    }
+}
+
+/// A pattern as `match` tests it at run time, with its literals evaluated.
+/// Bindings are produced in the order they appear.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Bind(String),
+    Wildcard,
+    Literal(Object),
+    Tuple(Vec<Matcher>),
+    Array {
+        elements: Vec<Matcher>,
+        rest: Option<Box<Matcher>>,
+    },
+    Hash(Vec<(HashKey, Matcher)>),
+}
+
+impl From<&Pattern> for Matcher {
+    fn from(pattern: &Pattern) -> Self {
+        match pattern {
+            Pattern::Identifier(ident) => Self::Bind(ident.value.clone()),
+            Pattern::Wildcard(_) => Self::Wildcard,
+            Pattern::Literal(literal) => Self::Literal(literal_value(literal)),
+            Pattern::Tuple(tuple) => Self::Tuple(tuple.elements.iter().map(Self::from).collect()),
+            Pattern::Array(array) => Self::Array {
+                elements: array.elements.iter().map(Self::from).collect(),
+                rest: array.rest.as_deref().map(|rest| Box::new(Self::from(rest))),
+            },
+            Pattern::Hash(hash) => Self::Hash(
+                hash.pairs
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = literal_value(key)
+                            .hash_key()
+                            .expect("the parser only allows hashable keys");
+                        (key, Self::from(value))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Display for Matcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |matchers: &[Matcher]| {
+            let matchers: Vec<String> = matchers.iter().map(|m| m.to_string()).collect();
+            matchers.join(", ")
+        };
+
+        match self {
+            Self::Bind(name) => write!(f, "{}", name),
+            Self::Wildcard => write!(f, "_"),
+            Self::Literal(value) => write!(f, "{}", value.inspect()),
+            Self::Tuple(elements) if elements.len() == 1 => write!(f, "({},)", join(elements)),
+            Self::Tuple(elements) => write!(f, "({})", join(elements)),
+            Self::Array { elements, rest } => {
+                write!(f, "[{}", join(elements))?;
+                match rest {
+                    Some(rest) if elements.is_empty() => write!(f, "...{}]", rest),
+                    Some(rest) => write!(f, ", ...{}]", rest),
+                    None => write!(f, "]"),
+                }
+            }
+            Self::Hash(pairs) => {
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                write!(f, "{{{}}}", pairs.join(", "))
+            }
+        }
+    }
+}
+
+/// The value of a literal pattern, which is a literal or a negated number.
+fn literal_value(literal: &Expression) -> Object {
+    match literal {
+        Expression::IntegerLiteral(int) => Object::Integer(int.value),
+        Expression::BigIntLiteral(int) => Object::BigInt(int.value.clone()),
+        Expression::FloatLiteral(float) => Object::Float(float.value),
+        Expression::StringLiteral(string) => Object::String(string.value.clone()),
+        Expression::Boolean(boolean) => Object::Boolean(boolean.value),
+        Expression::Prefix(prefix) => match literal_value(&prefix.right) {
+            Object::Integer(int) => match int.checked_neg() {
+                Some(int) => Object::Integer(int),
+                None => Object::from(-&BigInt::from(int)),
+            },
+            Object::BigInt(int) => Object::from(-&int),
+            Object::Float(float) => Object::Float(-float),
+            other => other,
+        },
+        _ => Object::Null,
+    }
+}
+
+#[derive(Clone)]
+pub struct Function {
+    pub parameters: Vec<expression::Identifier>,
+    pub body: statement::Block,
+    pub env: Env,
+}
+
+// The captured environment usually holds the function itself, so it is left
+// out here to keep recursive closures from formatting forever.
+impl Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Function")
+            .field("parameters", &self.parameters)
+            .field("body", &self.body)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params: Vec<String> = self.parameters.iter().map(|p| p.to_string()).collect();
+
+        write!(f, "fn({}) {{\n{}\n}}", params.join(", "), self.body)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledFunction {
+    pub instructions: Instructions,
+    pub num_locals: usize,
+    pub num_parameters: usize,
+    pub source_map: SourceMap,
+}
+
+impl Display for CompiledFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<compiled fn/{}>", self.num_parameters)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Closure {
+    pub function: Rc<CompiledFunction>,
+    pub free: Vec<Object>,
+}
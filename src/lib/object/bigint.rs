@@ -0,0 +1,346 @@
+//! Arbitrary-precision integers for values that do not fit in an `i64`.
+
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Largest power of ten that fits in a digit, used for decimal conversion.
+const DECIMAL_BASE: u32 = 1_000_000_000;
+const DECIMAL_DIGITS: usize = 9;
+
+/// A sign and magnitude integer. The magnitude holds little-endian base 2^32
+/// digits without trailing zeros, and zero is never negative, so every value
+/// has exactly one representation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut magnitude: Vec<u32>) -> Self {
+        trim(&mut magnitude);
+        Self {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    /// Parses ASCII decimal digits with an optional leading `-`.
+    pub fn from_decimal(s: &str) -> Option<Self> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut magnitude = Vec::new();
+        for chunk in digits.as_bytes().chunks(DECIMAL_DIGITS) {
+            let value = chunk
+                .iter()
+                .fold(0, |acc, digit| acc * 10 + u32::from(digit - b'0'));
+            mul_small_add(&mut magnitude, 10u32.pow(chunk.len() as u32), value);
+        }
+
+        Some(Self::new(negative, magnitude))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u64, |acc, digit| (acc << 32) | u64::from(*digit));
+
+        match self.negative {
+            true if magnitude <= i64::MIN.unsigned_abs() => Some((magnitude as i64).wrapping_neg()),
+            true => None,
+            false => i64::try_from(magnitude).ok(),
+        }
+    }
+
+    /// The number of bits needed for the magnitude, zero for zero.
+    pub fn bits(&self) -> u64 {
+        match self.magnitude.last() {
+            Some(top) => self.magnitude.len() as u64 * 32 - u64::from(top.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    /// Raises to a power by repeated squaring.
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut base = self.clone();
+        let mut result = Self::from(1);
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+
+        result
+    }
+
+    /// The nearest float, or an infinity if the value is out of its range.
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0.0, |acc, digit| acc * 4_294_967_296.0 + f64::from(*digit));
+
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Division truncating towards zero, or `None` when dividing by zero.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        let (quotient, _) = div_rem(&self.magnitude, &other.magnitude)?;
+        Some(Self::new(self.negative != other.negative, quotient))
+    }
+
+    /// The remainder of `checked_div`, which takes the sign of `self`.
+    pub fn checked_rem(&self, other: &Self) -> Option<Self> {
+        let (_, remainder) = div_rem(&self.magnitude, &other.magnitude)?;
+        Some(Self::new(self.negative, remainder))
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = value.unsigned_abs();
+        Self::new(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => cmp_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => cmp_magnitude(&other.magnitude, &self.magnitude),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(
+                self.negative,
+                add_magnitude(&self.magnitude, &other.magnitude),
+            );
+        }
+
+        match cmp_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::new(
+                other.negative,
+                sub_magnitude(&other.magnitude, &self.magnitude),
+            ),
+            _ => BigInt::new(
+                self.negative,
+                sub_magnitude(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        let mut product = vec![0u32; self.magnitude.len() + other.magnitude.len()];
+
+        for (i, a) in self.magnitude.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b) in other.magnitude.iter().enumerate() {
+                let value = u64::from(*a) * u64::from(*b) + u64::from(product[i + j]) + carry;
+                product[i + j] = value as u32;
+                carry = value >> 32;
+            }
+            product[i + other.magnitude.len()] = carry as u32;
+        }
+
+        BigInt::new(self.negative != other.negative, product)
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            chunks.push(div_rem_small(&mut magnitude, DECIMAL_BASE));
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in chunks {
+            write!(f, "{:0width$}", chunk, width = DECIMAL_DIGITS)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn trim(magnitude: &mut Vec<u32>) {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+}
+
+fn cmp_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut sum = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+
+    for (i, digit) in long.iter().enumerate() {
+        let value = u64::from(*digit) + u64::from(short.get(i).copied().unwrap_or(0)) + carry;
+        sum.push(value as u32);
+        carry = value >> 32;
+    }
+    sum.push(carry as u32);
+
+    sum
+}
+
+/// Subtracts `b` from `a`, which must not be smaller.
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut difference = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+
+    for (i, digit) in a.iter().enumerate() {
+        let mut value = i64::from(*digit) - i64::from(b.get(i).copied().unwrap_or(0)) - borrow;
+        borrow = i64::from(value < 0);
+        if value < 0 {
+            value += 1 << 32;
+        }
+        difference.push(value as u32);
+    }
+
+    trim(&mut difference);
+    difference
+}
+
+fn mul_small_add(magnitude: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = u64::from(addend);
+
+    for digit in magnitude.iter_mut() {
+        let value = u64::from(*digit) * u64::from(factor) + carry;
+        *digit = value as u32;
+        carry = value >> 32;
+    }
+
+    if carry > 0 {
+        magnitude.push(carry as u32);
+    }
+}
+
+/// Divides `magnitude` in place, returning the remainder.
+fn div_rem_small(magnitude: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+
+    for digit in magnitude.iter_mut().rev() {
+        let value = (remainder << 32) | u64::from(*digit);
+        *digit = (value / u64::from(divisor)) as u32;
+        remainder = value % u64::from(divisor);
+    }
+
+    trim(magnitude);
+    remainder as u32
+}
+
+/// Long division of magnitudes, or `None` when `b` is zero. Multi-digit
+/// divisors are handled a bit at a time, which is slow but simple.
+fn div_rem(a: &[u32], b: &[u32]) -> Option<(Vec<u32>, Vec<u32>)> {
+    match b {
+        [] => None,
+        [divisor] => {
+            let mut quotient = a.to_vec();
+            let remainder = div_rem_small(&mut quotient, *divisor);
+            let mut remainder = vec![remainder];
+            trim(&mut remainder);
+            Some((quotient, remainder))
+        }
+        _ if cmp_magnitude(a, b) == Ordering::Less => Some((Vec::new(), a.to_vec())),
+        _ => {
+            let mut quotient = vec![0u32; a.len()];
+            let mut remainder: Vec<u32> = Vec::with_capacity(b.len() + 1);
+
+            for bit in (0..a.len() * 32).rev() {
+                mul_small_add(&mut remainder, 2, (a[bit / 32] >> (bit % 32)) & 1);
+
+                if cmp_magnitude(&remainder, b) != Ordering::Less {
+                    remainder = sub_magnitude(&remainder, b);
+                    quotient[bit / 32] |= 1 << (bit % 32);
+                }
+            }
+
+            trim(&mut quotient);
+            Some((quotient, remainder))
+        }
+    }
+}
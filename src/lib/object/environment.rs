@@ -0,0 +1,82 @@
+use super::Object;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::rc::Rc;
+
+pub type Env = Rc<RefCell<Environment>>;
+
+#[derive(Debug, Default)]
+pub struct Environment {
+    pub(super) store: HashMap<String, Object>,
+    pub(super) outer: Option<Env>,
+    mutable: HashSet<String>,
+}
+
+/// Why `Environment::assign` could not rebind a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignError {
+    Undefined,
+    Immutable,
+}
+
+impl Display for AssignError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Undefined => write!(f, "cannot assign to undefined variable"),
+            Self::Immutable => write!(f, "cannot assign to immutable variable"),
+        }
+    }
+}
+
+impl Environment {
+    pub fn new() -> Env {
+        Rc::new(RefCell::new(Self::default()))
+    }
+
+    pub fn new_enclosed(outer: &Env) -> Env {
+        Rc::new(RefCell::new(Self {
+            store: HashMap::new(),
+            outer: Some(Rc::clone(outer)),
+            mutable: HashSet::new(),
+        }))
+    }
+
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.store.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .outer
+                .as_ref()
+                .and_then(|outer| outer.borrow().get(name)),
+        }
+    }
+
+    pub fn set(&mut self, name: String, value: Object) {
+        self.mutable.remove(&name);
+        self.store.insert(name, value);
+    }
+
+    /// Binds `name` like `set`, but so that `assign` may change it later.
+    pub fn set_mutable(&mut self, name: String, value: Object) {
+        self.mutable.insert(name.clone());
+        self.store.insert(name, value);
+    }
+
+    /// Rebinds `name` in the innermost environment that binds it.
+    pub fn assign(&mut self, name: &str, value: Object) -> Result<(), AssignError> {
+        if let Some(slot) = self.store.get_mut(name) {
+            if !self.mutable.contains(name) {
+                return Err(AssignError::Immutable);
+            }
+
+            *slot = value;
+            return Ok(());
+        }
+
+        match &self.outer {
+            Some(outer) => outer.borrow_mut().assign(name, value),
+            None => Err(AssignError::Undefined),
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Cycle collection for the tree-walking evaluator.
+//!
+//! Environments and functions are reference counted, but a function captures
+//! the environment that binds it, so recursive closures form cycles that
+//! reference counting alone never frees. The heap keeps a weak handle to every
+//! environment and function it allocates and periodically runs a synchronous
+//! cycle collector over them:
+//!
+//! 1. References between tracked environments and the functions, arrays,
+//!    hashes and structs they hold are counted. Any of them with more strong
+//!    references than that is held from outside the heap (the evaluator, the
+//!    Rust stack, the host) and is a root.
+//! 2. Everything reachable from a root is marked.
+//! 3. Unmarked environments are garbage; clearing their bindings breaks the
+//!    cycles and lets reference counting free the rest.
+//!
+//! Because roots are discovered rather than registered, a collection is safe
+//! at any point where no environment is borrowed.
+//!
+//! The VM's closures refer to themselves through `OpCurrentClosure` and copy
+//! their free variables, so compiled code only builds a cycle through a cell:
+//! a closure stored in a mutable variable it captures, or in a container that
+//! variable holds. [`CellHeap`] collects those the same way, with cells in
+//! place of environments, and empties the unreachable ones.
+
+use super::environment::{Env, Environment};
+use super::{Closure, Function, HashKey, Object, Struct};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::{Rc, Weak};
+
+const INITIAL_THRESHOLD: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// Environments and functions, or cells in the VM, currently alive.
+    pub live_objects: usize,
+    /// Collections run so far, automatic and explicit.
+    pub collections: usize,
+    /// Environments, or cells in the VM, freed by the collector over the
+    /// heap's lifetime.
+    pub reclaimed: usize,
+}
+
+#[derive(Debug)]
+pub struct Heap {
+    environments: Vec<Weak<RefCell<Environment>>>,
+    functions: Vec<Weak<Function>>,
+    allocations: usize,
+    threshold: usize,
+    collections: usize,
+    reclaimed: usize,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Self {
+            environments: Vec::new(),
+            functions: Vec::new(),
+            allocations: 0,
+            threshold: INITIAL_THRESHOLD,
+            collections: 0,
+            reclaimed: 0,
+        }
+    }
+
+    pub fn new_environment(&mut self) -> Env {
+        self.track(Environment::new())
+    }
+
+    pub fn new_enclosed_environment(&mut self, outer: &Env) -> Env {
+        self.track(Environment::new_enclosed(outer))
+    }
+
+    pub fn new_function(&mut self, function: Function) -> Rc<Function> {
+        let function = Rc::new(function);
+        self.functions.push(Rc::downgrade(&function));
+        self.allocations += 1;
+        function
+    }
+
+    /// Whether enough has been allocated since the last collection to make
+    /// another one worthwhile.
+    pub fn should_collect(&self) -> bool {
+        self.allocations >= self.threshold
+    }
+
+    pub fn stats(&self) -> HeapStats {
+        HeapStats {
+            live_objects: self
+                .environments
+                .iter()
+                .filter(|w| w.strong_count() > 0)
+                .count()
+                + self
+                    .functions
+                    .iter()
+                    .filter(|w| w.strong_count() > 0)
+                    .count(),
+            collections: self.collections,
+            reclaimed: self.reclaimed,
+        }
+    }
+
+    /// Frees every environment that is only reachable through cycles and
+    /// returns how many were freed.
+    pub fn collect(&mut self) -> usize {
+        self.environments.retain(|w| w.strong_count() > 0);
+
+        let environments: Vec<Env> = self.environments.iter().filter_map(Weak::upgrade).collect();
+        let garbage = find_garbage(&environments);
+        let count = garbage.len();
+
+        // Bindings are moved out first and dropped only once every borrow has
+        // been released, since dropping them can free further environments.
+        let mut freed = Vec::with_capacity(garbage.len());
+        for env in garbage.iter() {
+            let mut env = env.borrow_mut();
+            freed.push((std::mem::take(&mut env.store), env.outer.take()));
+        }
+        drop((environments, garbage, freed));
+
+        self.environments.retain(|w| w.strong_count() > 0);
+        self.functions.retain(|w| w.strong_count() > 0);
+
+        self.allocations = 0;
+        self.threshold = INITIAL_THRESHOLD.max(self.environments.len() * 2);
+        self.collections += 1;
+        self.reclaimed += count;
+
+        count
+    }
+
+    fn track(&mut self, env: Env) -> Env {
+        self.environments.push(Rc::downgrade(&env));
+        self.allocations += 1;
+        env
+    }
+}
+
+impl Drop for Heap {
+    // Owners drop their root environment before the heap, so this frees the
+    // cycles nothing outside still points into.
+    fn drop(&mut self) {
+        self.collect();
+    }
+}
+
+/// The cells a VM allocates for mutable variables that closures capture.
+#[derive(Debug)]
+pub struct CellHeap {
+    cells: Vec<Weak<RefCell<Object>>>,
+    allocations: usize,
+    threshold: usize,
+    collections: usize,
+    reclaimed: usize,
+}
+
+impl Default for CellHeap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CellHeap {
+    pub fn new() -> Self {
+        Self {
+            cells: Vec::new(),
+            allocations: 0,
+            threshold: INITIAL_THRESHOLD,
+            collections: 0,
+            reclaimed: 0,
+        }
+    }
+
+    pub fn new_cell(&mut self, value: Object) -> Rc<RefCell<Object>> {
+        let cell = Rc::new(RefCell::new(value));
+        self.cells.push(Rc::downgrade(&cell));
+        self.allocations += 1;
+        cell
+    }
+
+    pub fn should_collect(&self) -> bool {
+        self.allocations >= self.threshold
+    }
+
+    pub fn stats(&self) -> HeapStats {
+        HeapStats {
+            live_objects: self.cells.iter().filter(|w| w.strong_count() > 0).count(),
+            collections: self.collections,
+            reclaimed: self.reclaimed,
+        }
+    }
+
+    /// Empties every cell that is only reachable through cycles and returns
+    /// how many were emptied.
+    pub fn collect(&mut self) -> usize {
+        self.cells.retain(|w| w.strong_count() > 0);
+
+        let garbage = find_garbage_cells(&self.cells);
+        let count = garbage.len();
+
+        let freed: Vec<Object> = garbage
+            .iter()
+            .map(|cell| cell.replace(Object::Null))
+            .collect();
+        drop((garbage, freed));
+
+        self.cells.retain(|w| w.strong_count() > 0);
+
+        self.allocations = 0;
+        self.threshold = INITIAL_THRESHOLD.max(self.cells.len() * 2);
+        self.collections += 1;
+        self.reclaimed += count;
+
+        count
+    }
+}
+
+impl Drop for CellHeap {
+    // The VM drops its stack and globals before the heap, so this frees the
+    // cycles nothing outside still points into.
+    fn drop(&mut self) {
+        self.collect();
+    }
+}
+
+fn find_garbage_cells(cells: &[Weak<RefCell<Object>>]) -> Vec<Rc<RefCell<Object>>> {
+    // Count the references each value receives from the cells and from what
+    // they hold. Every cell starts out counted, with no references yet.
+    let mut value_refs: HashMap<*const (), (Value, usize)> = HashMap::new();
+    let mut pending: Vec<Value> = Vec::new();
+
+    for cell in cells.iter().filter_map(Weak::upgrade) {
+        let value = Value::Cell(cell);
+        pending.push(value.clone());
+        value_refs.insert(value.as_ptr(), (value, 0));
+    }
+
+    while let Some(value) = pending.pop() {
+        children(&value, &mut |value| {
+            count(&mut value_refs, &mut pending, value)
+        });
+    }
+
+    // As with environments, one reference is the clone held by `value_refs`.
+    let mut values: Vec<Value> = value_refs
+        .values()
+        .filter(|(value, internal)| value.strong_count() - 1 > *internal)
+        .map(|(value, _)| value.clone())
+        .collect();
+
+    let mut marked = HashSet::new();
+    while let Some(value) = values.pop() {
+        if marked.insert(value.as_ptr()) {
+            children(&value, &mut |value| values.push(value));
+        }
+    }
+
+    value_refs
+        .into_iter()
+        .filter(|(ptr, _)| !marked.contains(ptr))
+        .filter_map(|(_, (value, _))| match value {
+            Value::Cell(cell) => Some(cell),
+            _ => None,
+        })
+        .collect()
+}
+
+fn find_garbage(environments: &[Env]) -> Vec<Env> {
+    let tracked: HashSet<*const RefCell<Environment>> =
+        environments.iter().map(Rc::as_ptr).collect();
+
+    // Count the references each object receives from inside the heap. Shared
+    // values are counted once per reference but their contents only once.
+    let mut env_refs: HashMap<*const RefCell<Environment>, usize> = HashMap::new();
+    let mut value_refs: HashMap<*const (), (Value, usize)> = HashMap::new();
+    let mut pending: Vec<Value> = Vec::new();
+
+    for env in environments.iter() {
+        let env = env.borrow();
+
+        if let Some(outer) = &env.outer {
+            *env_refs.entry(Rc::as_ptr(outer)).or_default() += 1;
+        }
+
+        for value in env.store.values() {
+            trace(value, &mut |value| {
+                count(&mut value_refs, &mut pending, value)
+            });
+        }
+    }
+
+    while let Some(value) = pending.pop() {
+        if let Value::Function(function) = &value {
+            *env_refs.entry(Rc::as_ptr(&function.env)).or_default() += 1;
+        }
+        children(&value, &mut |value| {
+            count(&mut value_refs, &mut pending, value)
+        });
+    }
+
+    // Anything with more strong references than the heap accounts for is held
+    // from outside it. One reference is the clone held by `value_refs`, or by
+    // `environments`, respectively.
+    let mut values: Vec<Value> = value_refs
+        .values()
+        .filter(|(value, internal)| value.strong_count() - 1 > *internal)
+        .map(|(value, _)| value.clone())
+        .collect();
+    drop(value_refs);
+
+    let mut envs: Vec<Env> = environments
+        .iter()
+        .filter(|env| {
+            let internal = env_refs.get(&Rc::as_ptr(env)).copied().unwrap_or(0);
+            Rc::strong_count(env) - 1 > internal
+        })
+        .cloned()
+        .collect();
+
+    let mut marked = HashSet::new();
+    let mut visited = HashSet::new();
+
+    loop {
+        if let Some(value) = values.pop() {
+            if !visited.insert(value.as_ptr()) {
+                continue;
+            }
+
+            if let Value::Function(function) = &value {
+                envs.push(Rc::clone(&function.env));
+            }
+            children(&value, &mut |value| values.push(value));
+        } else if let Some(env) = envs.pop() {
+            let ptr = Rc::as_ptr(&env);
+            if !tracked.contains(&ptr) || !marked.insert(ptr) {
+                continue;
+            }
+
+            let env = env.borrow();
+            if let Some(outer) = &env.outer {
+                envs.push(Rc::clone(outer));
+            }
+            for value in env.store.values() {
+                trace(value, &mut |value| values.push(value));
+            }
+        } else {
+            break;
+        }
+    }
+
+    environments
+        .iter()
+        .filter(|env| !marked.contains(&Rc::as_ptr(env)))
+        .cloned()
+        .collect()
+}
+
+/// Records one reference to `value`, queueing it to have its own references
+/// counted the first time it is seen.
+fn count(refs: &mut HashMap<*const (), (Value, usize)>, pending: &mut Vec<Value>, value: Value) {
+    refs.entry(value.as_ptr())
+        .or_insert_with(|| {
+            pending.push(value.clone());
+            (value, 0)
+        })
+        .1 += 1;
+}
+
+/// A reference counted value that can lead back to an environment or a cell.
+#[derive(Clone)]
+enum Value {
+    Function(Rc<Function>),
+    Array(Rc<Vec<Object>>),
+    Hash(Rc<BTreeMap<HashKey, Object>>),
+    Struct(Rc<Struct>),
+    Closure(Rc<Closure>),
+    Cell(Rc<RefCell<Object>>),
+}
+
+impl Value {
+    fn as_ptr(&self) -> *const () {
+        match self {
+            Self::Function(function) => Rc::as_ptr(function) as *const (),
+            Self::Array(elements) => Rc::as_ptr(elements) as *const (),
+            Self::Hash(pairs) => Rc::as_ptr(pairs) as *const (),
+            Self::Struct(value) => Rc::as_ptr(value) as *const (),
+            Self::Closure(closure) => Rc::as_ptr(closure) as *const (),
+            Self::Cell(cell) => Rc::as_ptr(cell) as *const (),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Self::Function(function) => Rc::strong_count(function),
+            Self::Array(elements) => Rc::strong_count(elements),
+            Self::Hash(pairs) => Rc::strong_count(pairs),
+            Self::Struct(value) => Rc::strong_count(value),
+            Self::Closure(closure) => Rc::strong_count(closure),
+            Self::Cell(cell) => Rc::strong_count(cell),
+        }
+    }
+}
+
+/// Calls `visit` for every reference counted value held by `value`. A
+/// function's environment is left to the caller.
+fn children(value: &Value, visit: &mut impl FnMut(Value)) {
+    match value {
+        Value::Function(_) => (),
+        Value::Array(elements) => {
+            for element in elements.iter() {
+                trace(element, visit);
+            }
+        }
+        Value::Hash(pairs) => {
+            for value in pairs.values() {
+                trace(value, visit);
+            }
+        }
+        Value::Struct(value) => {
+            for value in value.values.iter() {
+                trace(value, visit);
+            }
+        }
+        Value::Closure(closure) => {
+            for value in closure.free.iter() {
+                trace(value, visit);
+            }
+        }
+        Value::Cell(cell) => trace(&cell.borrow(), visit),
+    }
+}
+
+/// Calls `visit` for every reference counted value directly held by `value`.
+fn trace(value: &Object, visit: &mut impl FnMut(Value)) {
+    match value {
+        Object::Function(function) => visit(Value::Function(Rc::clone(function))),
+        Object::Array(elements) | Object::Tuple(elements) => {
+            visit(Value::Array(Rc::clone(elements)))
+        }
+        Object::Hash(pairs) => visit(Value::Hash(Rc::clone(pairs))),
+        Object::Struct(value) => visit(Value::Struct(Rc::clone(value))),
+        Object::Closure(closure) => visit(Value::Closure(Rc::clone(closure))),
+        Object::Cell(cell) => visit(Value::Cell(Rc::clone(cell))),
+        Object::ReturnValue(value) => trace(value, visit),
+        Object::TailCall(function, args) => {
+            trace(function, visit);
+            for arg in args.iter() {
+                trace(arg, visit);
+            }
+        }
+        _ => (),
+    }
+}
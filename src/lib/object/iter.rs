@@ -0,0 +1,88 @@
+//! Iteration over the values a `for` loop accepts, shared by both backends.
+
+use super::{HashKey, Object, Range};
+use std::rc::Rc;
+
+/// A `for` loop part way through a value. Arrays, tuples and hashes are
+/// iterated as they were when the loop started, and ranges are produced
+/// lazily, so one without an end goes on until the loop breaks.
+#[derive(Debug, Clone)]
+pub struct Iter {
+    source: Source,
+    index: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Source {
+    Elements(Rc<Vec<Object>>),
+    Chars(Vec<char>),
+    Range(i64, Range),
+    Hash(Vec<(HashKey, Object)>),
+}
+
+impl Iter {
+    pub fn new(value: Object) -> Result<Self, String> {
+        let source = match value {
+            Object::Array(elements) | Object::Tuple(elements) => Source::Elements(elements),
+            Object::String(string) => Source::Chars(string.chars().collect()),
+            Object::Range(range) => match range.start {
+                Some(start) => Source::Range(start, range),
+                None => {
+                    return Err(format!(
+                        "cannot iterate over range without a start: {}",
+                        range
+                    ))
+                }
+            },
+            Object::Hash(pairs) => Source::Hash(
+                pairs
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            ),
+            other => return Err(format!("not iterable: {}", other.type_name())),
+        };
+
+        Ok(Self { source, index: 0 })
+    }
+
+    /// The values to bind for the next iteration of a loop with `count`
+    /// variables, or `None` once there are no more. Two variables get the
+    /// index or key and the value, while one gets the value, except that a
+    /// hash gives its keys.
+    pub fn next_values(&mut self, count: usize) -> Option<Vec<Object>> {
+        let (key, value) = self.next_entry()?;
+        self.index += 1;
+
+        Some(match (count, &self.source) {
+            (2, _) => vec![key, value],
+            (_, Source::Hash(_)) => vec![key],
+            _ => vec![value],
+        })
+    }
+
+    fn next_entry(&self) -> Option<(Object, Object)> {
+        let index = Object::Integer(self.index as i64);
+
+        match &self.source {
+            Source::Elements(elements) => Some((index, elements.get(self.index)?.clone())),
+            Source::Chars(chars) => {
+                Some((index, Object::String(chars.get(self.index)?.to_string())))
+            }
+            Source::Range(start, range) => {
+                let value = start.checked_add(self.index as i64)?;
+                let in_range = match range.end {
+                    Some(end) if range.inclusive => value <= end,
+                    Some(end) => value < end,
+                    None => true,
+                };
+
+                in_range.then_some((index, Object::Integer(value)))
+            }
+            Source::Hash(pairs) => {
+                let (key, value) = pairs.get(self.index)?;
+                Some((key.into(), value.clone()))
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use super::Object;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::rc::Rc;
+
+pub type NativeFn = dyn Fn(&[Object]) -> Result<Object, String>;
+
+/// A function implemented by the host. Arity is checked before `function` is
+/// called, and an `Err` it returns becomes a Monkey runtime error.
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: usize,
+    pub function: Box<NativeFn>,
+}
+
+impl NativeFunction {
+    pub fn call(&self, args: &[Object]) -> Result<Object, String> {
+        if args.len() != self.arity {
+            return Err(format!(
+                "wrong number of arguments: want={}, got={}",
+                self.arity,
+                args.len()
+            ));
+        }
+
+        (self.function)(args)
+    }
+}
+
+impl Debug for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn {}/{}>", self.name, self.arity)
+    }
+}
+
+/// Host functions visible to scripts by name. Each keeps the index it was
+/// first registered at, which is how compiled code refers to it.
+#[derive(Debug, Clone, Default)]
+pub struct NativeRegistry {
+    functions: Vec<Rc<NativeFunction>>,
+    indices: HashMap<String, usize>,
+}
+
+impl NativeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `function` under `name`, replacing any native already
+    /// registered with that name.
+    pub fn register<F>(&mut self, name: &str, arity: usize, function: F)
+    where
+        F: Fn(&[Object]) -> Result<Object, String> + 'static,
+    {
+        let native = Rc::new(NativeFunction {
+            name: name.to_string(),
+            arity,
+            function: Box::new(function),
+        });
+
+        match self.indices.get(name) {
+            Some(index) => self.functions[*index] = native,
+            None => {
+                self.indices.insert(name.to_string(), self.functions.len());
+                self.functions.push(native);
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Rc<NativeFunction>> {
+        self.indices.get(name).map(|index| &self.functions[*index])
+    }
+
+    pub fn get_by_index(&self, index: usize) -> Option<&Rc<NativeFunction>> {
+        self.functions.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rc<NativeFunction>> {
+        self.functions.iter()
+    }
+}
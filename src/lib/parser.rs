@@ -14,6 +14,13 @@ pub struct Parser {
     cur_token: Token,
     peek_token: Token,
     errors: Vec<String>,
+    warnings: Vec<String>,
+    // Labels of the loops enclosing the current statement, innermost last.
+    loops: Vec<Option<String>>,
+    // The `break` and `continue` statements parsed so far that leave the
+    // innermost loop being parsed or one enclosing it: the index in `loops`
+    // of the loop each targets, and its keyword.
+    exits: Vec<(usize, String)>,
 }
 
 impl Parser {
@@ -26,6 +33,9 @@ impl Parser {
             peek_token,
             l: lex,
             errors: vec![],
+            warnings: vec![],
+            loops: vec![],
+            exits: vec![],
         }
     }
 
@@ -38,52 +48,318 @@ impl Parser {
         let mut program = Program { statements: vec![] };
 
         while self.cur_token.token_type != TokenType::EOF {
-            program.statements.push(self.parse_statement()?);
+            match self.parse_statement() {
+                Ok(stmt) => program.statements.push(stmt),
+                Err(err) => {
+                    if !self.errors.contains(&err) {
+                        self.errors.push(err);
+                    }
+                    break;
+                }
+            }
             self.next_token();
         }
 
-        Ok(program)
+        // A statement that fails to parse inside a block is skipped, so its
+        // error may be the only sign that the program is invalid.
+        match self.errors.first() {
+            Some(err) => Err(err.clone()),
+            None => Ok(program),
+        }
     }
 
     fn parse_statement(&mut self) -> ParserError<statement::Statement> {
         match self.cur_token.token_type {
-            TokenType::Let => self
-                .parse_let_statement()
-                .map(|stmt| statement::Statement::Let(stmt)),
+            TokenType::Let => self.parse_let_statement().map(statement::Statement::Let),
             TokenType::Return => self
                 .parse_return_statement()
-                .map(|stmt| statement::Statement::Return(stmt)),
+                .map(statement::Statement::Return),
+            TokenType::Struct => self
+                .parse_struct_statement()
+                .map(statement::Statement::Struct),
+            TokenType::While => self
+                .parse_while_statement(None)
+                .map(statement::Statement::While),
+            TokenType::For => self
+                .parse_for_statement(None)
+                .map(statement::Statement::For),
+            TokenType::Label => self.parse_labeled_statement(),
+            TokenType::Break => {
+                let token = self.cur_token.clone();
+                let label = self.parse_loop_label()?;
+                Ok(statement::Statement::Break(statement::Break {
+                    token,
+                    label,
+                }))
+            }
+            TokenType::Continue => {
+                let token = self.cur_token.clone();
+                let label = self.parse_loop_label()?;
+                Ok(statement::Statement::Continue(statement::Continue {
+                    token,
+                    label,
+                }))
+            }
             _ => self
                 .parse_expression_statement()
-                .map(|stmt| statement::Statement::Expression(stmt)),
+                .map(statement::Statement::Expression),
         }
     }
 
     fn parse_let_statement(&mut self) -> ParserError<statement::Let> {
         let token = self.cur_token.clone();
 
+        let mutable = self.peek_token_is(&TokenType::Mut);
+        if mutable {
+            self.next_token();
+        }
+
+        if !matches!(
+            self.peek_token.token_type,
+            TokenType::Ident | TokenType::LParen | TokenType::LBracket
+        ) {
+            self.peek_error(TokenType::Ident);
+            return Err("Expected Ident".to_string());
+        }
+
+        self.next_token();
+        let name = parse_destructuring_pattern(self)?;
+
+        if !self.expect_peek(TokenType::Assign) {
+            return Err("Expected Assign".to_string());
+        }
+
+        self.next_token();
+
+        let value = Some(self.parse_expression(Precedence::Lowest)?);
+
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(statement::Let {
+            token,
+            mutable,
+            name,
+            value,
+        })
+    }
+
+    fn parse_struct_statement(&mut self) -> ParserError<statement::Struct> {
+        let token = self.cur_token.clone();
+
         if !self.expect_peek(TokenType::Ident) {
             return Err("Expected Ident".to_string());
         }
 
         let name = expression::Identifier {
             token: self.cur_token.clone(),
-            value: self.cur_token.to_string(),
+            value: self.cur_token.literal.clone(),
         };
 
-        if !self.expect_peek(TokenType::Assign) {
-            return Err("Expected Assign".to_string());
+        if !self.expect_peek(TokenType::LBrace) {
+            return Err("Expected LBrace".to_string());
+        }
+
+        let mut fields: Vec<expression::Identifier> = Vec::new();
+        while !self.peek_token_is(&TokenType::RBrace) {
+            if !self.expect_peek(TokenType::Ident) {
+                return Err("Expected Ident".to_string());
+            }
+
+            if fields
+                .iter()
+                .any(|field| field.value == self.cur_token.literal)
+            {
+                let error = format!(
+                    "duplicate field {} in struct {}",
+                    self.cur_token.literal, name.value
+                );
+                self.errors.push(error.clone());
+                return Err(error);
+            }
+
+            fields.push(expression::Identifier {
+                token: self.cur_token.clone(),
+                value: self.cur_token.literal.clone(),
+            });
+
+            if !self.peek_token_is(&TokenType::RBrace) && !self.expect_peek(TokenType::Comma) {
+                return Err("Was expecting Comma or RBrace".to_string());
+            }
         }
 
         self.next_token();
 
-        let value = self.parse_expression(Precedence::Lowest).ok();
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(statement::Struct {
+            token,
+            name,
+            fields,
+        })
+    }
+
+    fn parse_labeled_statement(&mut self) -> ParserError<statement::Statement> {
+        let label = self.cur_token.literal.clone();
+
+        if !self.expect_peek(TokenType::Colon) {
+            return Err("Expected Colon".to_string());
+        }
+        if self.peek_token_is(&TokenType::For) {
+            self.next_token();
+            return self
+                .parse_for_statement(Some(label))
+                .map(statement::Statement::For);
+        }
+        if !self.expect_peek(TokenType::While) {
+            return Err("Expected While".to_string());
+        }
+
+        self.parse_while_statement(Some(label))
+            .map(statement::Statement::While)
+    }
+
+    fn parse_while_statement(&mut self, label: Option<String>) -> ParserError<statement::While> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenType::LParen) {
+            return Err("Expected LParen".to_string());
+        }
+
+        self.next_token();
+        let condition = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expect_peek(TokenType::RParen) {
+            return Err("Expected RParen".to_string());
+        }
+        if !self.expect_peek(TokenType::LBrace) {
+            return Err("Expected LBrace".to_string());
+        }
+
+        let body = self.parse_loop_body(label.clone())?;
 
         if self.peek_token_is(&TokenType::Semicolon) {
             self.next_token();
         }
 
-        Ok(statement::Let { token, name, value })
+        Ok(statement::While {
+            token,
+            label,
+            condition,
+            body,
+        })
+    }
+
+    fn parse_for_statement(&mut self, label: Option<String>) -> ParserError<statement::For> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenType::LParen) {
+            return Err("Expected LParen".to_string());
+        }
+
+        let mut variables = Vec::new();
+        loop {
+            self.next_token();
+            variables.push(parse_destructuring_pattern(self)?);
+
+            if variables.len() == 2 || !self.peek_token_is(&TokenType::Comma) {
+                break;
+            }
+            self.next_token();
+        }
+
+        if !self.expect_peek(TokenType::In) {
+            return Err("Expected In".to_string());
+        }
+
+        self.next_token();
+        let iterable = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expect_peek(TokenType::RParen) {
+            return Err("Expected RParen".to_string());
+        }
+        if !self.expect_peek(TokenType::LBrace) {
+            return Err("Expected LBrace".to_string());
+        }
+
+        let body = self.parse_loop_body(label.clone())?;
+
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(statement::For {
+            token,
+            label,
+            variables,
+            iterable,
+            body,
+        })
+    }
+
+    fn parse_loop_body(&mut self, label: Option<String>) -> ParserError<statement::Block> {
+        self.loops.push(label);
+        let body = parse_block_statement(self);
+        self.loops.pop();
+
+        let loops = self.loops.len();
+        self.exits.retain(|(target, _)| *target < loops);
+
+        body
+    }
+
+    /// Reads the optional label after `break` or `continue`, which must name
+    /// an enclosing loop; without one there must be a loop at all.
+    fn parse_loop_label(&mut self) -> ParserError<Option<String>> {
+        let keyword = self.cur_token.literal.clone();
+
+        let label = match self.peek_token_is(&TokenType::Label) {
+            true => {
+                self.next_token();
+                Some(self.cur_token.literal.clone())
+            }
+            false => None,
+        };
+
+        let error = match &label {
+            None if self.loops.is_empty() => Some(format!("{} outside of a loop", keyword)),
+            Some(name) if !self.loops.contains(&label) => {
+                Some(format!("undeclared loop label '{}", name))
+            }
+            _ => None,
+        };
+        if let Some(error) = error {
+            self.errors.push(error.clone());
+            return Err(error);
+        }
+
+        let target = match &label {
+            Some(_) => self.loops.iter().rposition(|name| *name == label),
+            None => self.loops.len().checked_sub(1),
+        };
+        self.exits.push((target.unwrap_or_default(), keyword));
+
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(label)
+    }
+
+    /// Fails if a `break` or `continue` parsed since `start` leaves the
+    /// expression just parsed, whose value is used: there would be no value.
+    fn check_exits(&mut self, start: usize) -> ParserError<()> {
+        match self.exits.get(start) {
+            Some((_, keyword)) => {
+                let error = format!("{} out of an expression whose value is used", keyword);
+                self.errors.push(error.clone());
+                Err(error)
+            }
+            None => Ok(()),
+        }
     }
 
     fn cur_token_is(&self, t: TokenType) -> bool {
@@ -104,8 +380,20 @@ impl Parser {
         }
     }
 
+    /// Errors from both lexing and parsing, lexer errors first.
     pub fn errors(&self) -> Vec<String> {
-        self.errors.clone()
+        self.l
+            .errors()
+            .iter()
+            .chain(self.errors.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Problems that do not stop the program from running, such as match
+    /// arms that can never be reached.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
     }
 
     fn peek_error(&mut self, t: TokenType) {
@@ -118,9 +406,17 @@ impl Parser {
     fn parse_return_statement(&mut self) -> ParserError<statement::Return> {
         let token = self.cur_token.clone();
 
+        if self.peek_token_is(&TokenType::Semicolon) {
+            self.next_token();
+            return Ok(statement::Return {
+                token,
+                return_value: None,
+            });
+        }
+
         self.next_token();
 
-        let return_value = self.parse_expression(Precedence::Lowest).ok();
+        let return_value = Some(self.parse_expression(Precedence::Lowest)?);
 
         if self.peek_token_is(&TokenType::Semicolon) {
             self.next_token();
@@ -135,7 +431,7 @@ impl Parser {
     fn parse_expression_statement(&mut self) -> ParserError<statement::Expression> {
         let stmt = statement::Expression {
             token: self.cur_token.clone(),
-            expression: self.parse_expression(Precedence::Lowest)?,
+            expression: self.parse_statement_expression(Precedence::Lowest)?,
         };
         if self.peek_token_is(&TokenType::Semicolon) {
             self.next_token();
@@ -148,13 +444,22 @@ impl Parser {
         match token {
             TokenType::Ident => Some(parse_identifier),
             TokenType::Int => Some(parse_integer_literal),
+            TokenType::Float => Some(parse_float_literal),
+            TokenType::String => Some(parse_string_literal),
             TokenType::Bang => Some(parse_prefix_expression),
             TokenType::Minus => Some(parse_prefix_expression),
+            TokenType::Tilde => Some(parse_prefix_expression),
             TokenType::True => Some(parse_boolean),
             TokenType::False => Some(parse_boolean),
+            TokenType::Null => Some(parse_null),
+            TokenType::DotDot => Some(parse_prefix_range_expression),
+            TokenType::DotDotEq => Some(parse_prefix_range_expression),
             TokenType::LParen => Some(parse_grouped_expression),
             TokenType::If => Some(parse_if_expression),
+            TokenType::Match => Some(parse_match_expression),
             TokenType::Function => Some(parse_function_literal),
+            TokenType::LBracket => Some(parse_array_literal),
+            TokenType::LBrace => Some(parse_hash_literal),
 
             _ => None,
         }
@@ -166,28 +471,66 @@ impl Parser {
             TokenType::Minus => Some(parse_infix_expression),
             TokenType::Slash => Some(parse_infix_expression),
             TokenType::Asterisk => Some(parse_infix_expression),
+            TokenType::Percent => Some(parse_infix_expression),
+            TokenType::Power => Some(parse_infix_expression),
+            TokenType::Ampersand => Some(parse_infix_expression),
+            TokenType::Pipe => Some(parse_infix_expression),
+            TokenType::Caret => Some(parse_infix_expression),
+            TokenType::ShiftLeft => Some(parse_infix_expression),
+            TokenType::ShiftRight => Some(parse_infix_expression),
             TokenType::Eq => Some(parse_infix_expression),
             TokenType::NotEq => Some(parse_infix_expression),
             TokenType::Lt => Some(parse_infix_expression),
             TokenType::Gt => Some(parse_infix_expression),
+            TokenType::LtEq => Some(parse_infix_expression),
+            TokenType::GtEq => Some(parse_infix_expression),
+            TokenType::And => Some(parse_infix_expression),
+            TokenType::Or => Some(parse_infix_expression),
             TokenType::LParen => Some(parse_call_expression),
+            TokenType::LBracket => Some(parse_index_expression),
+            TokenType::OptionalBracket => Some(parse_optional_index_expression),
+            TokenType::OptionalDot => Some(parse_optional_member_expression),
+            TokenType::Dot => Some(parse_member_expression),
+            TokenType::NullCoalesce => Some(parse_infix_expression),
+            TokenType::DotDot => Some(parse_range_expression),
+            TokenType::DotDotEq => Some(parse_range_expression),
+            TokenType::Assign => Some(parse_assign_expression),
+            TokenType::PlusAssign => Some(parse_assign_expression),
+            TokenType::MinusAssign => Some(parse_assign_expression),
+            TokenType::AsteriskAssign => Some(parse_assign_expression),
+            TokenType::SlashAssign => Some(parse_assign_expression),
             _ => None,
         }
     }
 
     fn parse_expression(&mut self, precedence: Precedence) -> ParserError<expression::Expression> {
+        let exits = self.exits.len();
+        let expression = self.parse_statement_expression(precedence)?;
+        self.check_exits(exits)?;
+
+        Ok(expression)
+    }
+
+    /// Parses an expression whose value is discarded, so an `if` or `match`
+    /// making up all of it may `break` or `continue` out of it.
+    fn parse_statement_expression(
+        &mut self,
+        precedence: Precedence,
+    ) -> ParserError<expression::Expression> {
+        let exits = self.exits.len();
         let prefix = self
             .prefix_parse_fns(self.cur_token.token_type.clone())
-            .ok_or(format!("couldn't find function for {:#?}", self.cur_token))?;
+            .ok_or(format!("unexpected token '{}'", self.cur_token.literal))?;
 
         let mut left_exp = Box::new(prefix(self)?);
 
-        while !self.peek_token_is(&TokenType::Semicolon) && precedence < self.peek_precedence() {
+        while !self.peek_token_is(&TokenType::Semicolon) && self.peek_binds_tighter(&precedence) {
             let infix = match self.infix_parse_fns(self.peek_token.token_type.clone()) {
                 Some(fun) => fun,
                 None => return Ok(*left_exp),
             };
 
+            self.check_exits(exits)?;
             self.next_token();
 
             left_exp = Box::new(infix(self, left_exp)?);
@@ -200,6 +543,17 @@ impl Parser {
         Precedence::precedences(&self.peek_token.token_type)
     }
 
+    /// Whether the peek token takes the expression parsed so far as its left
+    /// operand. A right-associative operator also does so at its own level,
+    /// which makes `a ** b ** c` parse as `a ** (b ** c)`.
+    fn peek_binds_tighter(&self, precedence: &Precedence) -> bool {
+        let peek = self.peek_precedence();
+
+        *precedence < peek
+            || (*precedence == peek
+                && Precedence::is_right_associative(&self.peek_token.token_type))
+    }
+
     fn cur_precedence(&self) -> Precedence {
         Precedence::precedences(&self.cur_token.token_type)
     }
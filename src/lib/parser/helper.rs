@@ -1,5 +1,7 @@
 use crate::ast::expression::{self, Expression};
+use crate::ast::pattern::{self, Pattern};
 use crate::ast::statement::{self, Statement};
+use crate::object::bigint::BigInt;
 use crate::parser::Parser;
 use crate::token::TokenType;
 
@@ -8,19 +10,96 @@ pub type ParserError<T> = Result<T, String>;
 pub type PrefixParseFn = fn(&mut Parser) -> ParserError<Expression>;
 pub type InfixParseFn = fn(&mut Parser, Box<Expression>) -> ParserError<Expression>;
 
+/// An identifier directly followed by `{` names the struct of a literal.
 pub fn parse_identifier(parser: &mut Parser) -> ParserError<Expression> {
-    Ok(Expression::Identifier(expression::Identifier {
+    let ident = expression::Identifier {
         token: parser.cur_token.clone(),
         value: parser.cur_token.literal.clone(),
+    };
+
+    match parser.peek_token_is(&TokenType::LBrace) {
+        true => parse_struct_literal(parser, ident),
+        false => Ok(Expression::Identifier(ident)),
+    }
+}
+
+fn parse_struct_literal(
+    parser: &mut Parser,
+    name: expression::Identifier,
+) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+    let mut fields = Vec::new();
+
+    parser.next_token();
+
+    while !parser.peek_token_is(&TokenType::RBrace) {
+        if !parser.expect_peek(TokenType::Ident) {
+            return Err("Was expecting Ident".to_string());
+        }
+
+        let field = expression::Identifier {
+            token: parser.cur_token.clone(),
+            value: parser.cur_token.literal.clone(),
+        };
+
+        if !parser.expect_peek(TokenType::Colon) {
+            return Err("Was expecting Colon".to_string());
+        }
+
+        parser.next_token();
+        let value = parser.parse_expression(Precedence::Lowest)?;
+
+        fields.push((field, value));
+
+        if !parser.peek_token_is(&TokenType::RBrace) && !parser.expect_peek(TokenType::Comma) {
+            return Err("Was expecting Comma or RBrace".to_string());
+        }
+    }
+
+    parser.next_token();
+
+    Ok(Expression::StructLiteral(expression::StructLiteral {
+        token,
+        name,
+        fields,
     }))
 }
 
+/// Literals too large for an `i64` become `BigIntLiteral`s.
 pub fn parse_integer_literal(parser: &mut Parser) -> ParserError<Expression> {
-    let value: i64 = match parser.cur_token.literal.parse() {
-        Ok(val) => val,
-        Err(err) => {
+    let token = parser.cur_token.clone();
+
+    match token.literal.parse::<i64>() {
+        Ok(value) => Ok(Expression::IntegerLiteral(expression::IntegerLiteral {
+            token,
+            value,
+        })),
+        Err(err) => match BigInt::from_decimal(&token.literal) {
+            Some(value) => Ok(Expression::BigIntLiteral(expression::BigIntLiteral {
+                token,
+                value,
+            })),
+            None => {
+                let error = format!("could not parse {} as integer. Err: {err}", token.literal);
+                parser.errors.push(error.clone());
+                Err(error)
+            }
+        },
+    }
+}
+
+/// Literals too large to represent, such as `1e400`, are rejected rather
+/// than becoming infinity.
+pub fn parse_float_literal(parser: &mut Parser) -> ParserError<Expression> {
+    let value = match parser.cur_token.literal.parse::<f64>() {
+        Ok(val) if val.is_finite() => val,
+        result => {
+            let reason = match result {
+                Err(err) => err.to_string(),
+                Ok(_) => "out of range".to_string(),
+            };
             let error = format!(
-                "could not parse {} as integer. Err: {err}",
+                "could not parse {} as float. Err: {reason}",
                 parser.cur_token.literal.clone(),
             );
             parser.errors.push(error.clone());
@@ -28,12 +107,19 @@ pub fn parse_integer_literal(parser: &mut Parser) -> ParserError<Expression> {
         }
     };
 
-    Ok(Expression::IntegerLiteral(expression::IntegerLiteral {
+    Ok(Expression::FloatLiteral(expression::FloatLiteral {
         token: parser.cur_token.clone(),
         value,
     }))
 }
 
+pub fn parse_string_literal(parser: &mut Parser) -> ParserError<Expression> {
+    Ok(Expression::StringLiteral(expression::StringLiteral {
+        token: parser.cur_token.clone(),
+        value: parser.cur_token.literal.clone(),
+    }))
+}
+
 pub fn parse_prefix_expression(parser: &mut Parser) -> ParserError<Expression> {
     let token = parser.cur_token.clone();
     let operator = parser.cur_token.literal.clone();
@@ -76,16 +162,324 @@ pub fn parse_boolean(parser: &mut Parser) -> ParserError<Expression> {
     }))
 }
 
+/// Parentheses group an expression unless they hold a comma, which makes
+/// them a tuple: `(a)` is just `a`, while `()`, `(a,)` and `(a, b)` are tuples.
 pub fn parse_grouped_expression(parser: &mut Parser) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+
+    if parser.peek_token_is(&TokenType::RParen) {
+        parser.next_token();
+        return Ok(Expression::TupleLiteral(expression::TupleLiteral {
+            token,
+            elements: vec![],
+        }));
+    }
+
     parser.next_token();
 
     let exp = parser.parse_expression(Precedence::Lowest)?;
 
+    if !parser.peek_token_is(&TokenType::Comma) {
+        if !parser.expect_peek(TokenType::RParen) {
+            return Err("Was expecting RParen".to_string());
+        }
+
+        return Ok(exp);
+    }
+
+    parser.next_token();
+    let mut elements = vec![exp];
+    if !parser.peek_token_is(&TokenType::RParen) {
+        elements.extend(parse_expression_list(parser, TokenType::RParen)?);
+    } else {
+        parser.next_token();
+    }
+
+    Ok(Expression::TupleLiteral(expression::TupleLiteral {
+        token,
+        elements,
+    }))
+}
+
+/// Parses the pattern starting at the current token.
+pub fn parse_pattern(parser: &mut Parser) -> ParserError<Pattern> {
+    let token = parser.cur_token.clone();
+
+    match token.token_type {
+        TokenType::Ident if token.literal == "_" => Ok(Pattern::Wildcard(token)),
+        TokenType::Ident => Ok(Pattern::Identifier(expression::Identifier {
+            value: token.literal.clone(),
+            token,
+        })),
+        TokenType::LParen => {
+            let (mut elements, rest, trailing_comma) =
+                parse_pattern_list(parser, TokenType::RParen)?;
+
+            if rest.is_some() {
+                let error = "rest patterns are only allowed in array patterns".to_string();
+                parser.errors.push(error.clone());
+                return Err(error);
+            }
+
+            match elements.len() {
+                1 if !trailing_comma => Ok(elements.remove(0)),
+                _ => Ok(Pattern::Tuple(pattern::TuplePattern { token, elements })),
+            }
+        }
+        TokenType::LBracket => {
+            let (elements, rest, _) = parse_pattern_list(parser, TokenType::RBracket)?;
+
+            Ok(Pattern::Array(pattern::ArrayPattern {
+                token,
+                elements,
+                rest,
+            }))
+        }
+        TokenType::LBrace => parse_hash_pattern(parser),
+        TokenType::Int => parse_integer_literal(parser)
+            .map(Box::new)
+            .map(Pattern::Literal),
+        TokenType::Float => parse_float_literal(parser)
+            .map(Box::new)
+            .map(Pattern::Literal),
+        TokenType::String => parse_string_literal(parser)
+            .map(Box::new)
+            .map(Pattern::Literal),
+        TokenType::True | TokenType::False => {
+            parse_boolean(parser).map(Box::new).map(Pattern::Literal)
+        }
+        TokenType::Null => parse_null(parser).map(Box::new).map(Pattern::Literal),
+        TokenType::Minus
+            if matches!(
+                parser.peek_token.token_type,
+                TokenType::Int | TokenType::Float
+            ) =>
+        {
+            parser.next_token();
+            let right = match parser.cur_token.token_type {
+                TokenType::Int => parse_integer_literal(parser)?,
+                _ => parse_float_literal(parser)?,
+            };
+
+            Ok(Pattern::Literal(Box::new(Expression::Prefix(
+                expression::Prefix {
+                    operator: token.literal.clone(),
+                    token,
+                    right: Box::new(right),
+                },
+            ))))
+        }
+        _ => {
+            let error = format!("expected a pattern, got {:?}", token.literal);
+            parser.errors.push(error.clone());
+            Err(error)
+        }
+    }
+}
+
+/// Parses the pattern of a `let` or `for`, which may only take values apart.
+pub fn parse_destructuring_pattern(parser: &mut Parser) -> ParserError<Pattern> {
+    let pattern = parse_pattern(parser)?;
+
+    if !pattern.is_destructuring() {
+        let error = format!(
+            "literal and hash patterns are only allowed in match arms, got {}",
+            pattern
+        );
+        parser.errors.push(error.clone());
+        return Err(error);
+    }
+
+    Ok(pattern)
+}
+
+/// `{"key": pattern, ...}`, where each key is a literal usable as a hash key.
+fn parse_hash_pattern(parser: &mut Parser) -> ParserError<Pattern> {
+    let token = parser.cur_token.clone();
+    let mut pairs = Vec::new();
+
+    while !parser.peek_token_is(&TokenType::RBrace) {
+        parser.next_token();
+        let key = match parse_pattern(parser)? {
+            Pattern::Literal(key) if is_hash_key_literal(&key) => *key,
+            other => {
+                let error = format!("invalid hash pattern key: {}", other);
+                parser.errors.push(error.clone());
+                return Err(error);
+            }
+        };
+
+        if !parser.expect_peek(TokenType::Colon) {
+            return Err("Was expecting Colon".to_string());
+        }
+
+        parser.next_token();
+        pairs.push((key, parse_pattern(parser)?));
+
+        if !parser.peek_token_is(&TokenType::RBrace) && !parser.expect_peek(TokenType::Comma) {
+            return Err("Was expecting Comma or RBrace".to_string());
+        }
+    }
+
+    parser.next_token();
+
+    Ok(Pattern::Hash(pattern::HashPattern { token, pairs }))
+}
+
+fn is_hash_key_literal(literal: &Expression) -> bool {
+    match literal {
+        Expression::IntegerLiteral(_)
+        | Expression::BigIntLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::Boolean(_) => true,
+        Expression::Prefix(prefix) => is_hash_key_literal(&prefix.right),
+        _ => false,
+    }
+}
+
+/// Parses comma separated patterns up to and including `end`, along with a
+/// trailing `...rest` pattern and whether the list ended with a comma.
+fn parse_pattern_list(
+    parser: &mut Parser,
+    end: TokenType,
+) -> ParserError<(Vec<Pattern>, Option<Box<Pattern>>, bool)> {
+    let mut elements = Vec::new();
+    let mut rest = None;
+    let mut trailing_comma = false;
+
+    while !parser.peek_token_is(&end) {
+        if rest.is_some() {
+            let error = "rest pattern must come last".to_string();
+            parser.errors.push(error.clone());
+            return Err(error);
+        }
+
+        parser.next_token();
+        if parser.cur_token_is(TokenType::Ellipsis) {
+            parser.next_token();
+            rest = Some(Box::new(parse_pattern(parser)?));
+        } else {
+            elements.push(parse_pattern(parser)?);
+        }
+
+        trailing_comma = parser.peek_token_is(&TokenType::Comma);
+        if trailing_comma {
+            parser.next_token();
+        } else if !parser.peek_token_is(&end) {
+            parser.peek_error(end);
+            return Err("Was expecting Comma".to_string());
+        }
+    }
+
+    parser.next_token();
+
+    Ok((elements, rest, trailing_comma))
+}
+
+/// `match (subject) { pattern if guard => body, ... }`. An arm that an
+/// earlier one always matches first is reported as a warning.
+pub fn parse_match_expression(parser: &mut Parser) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+
+    if !parser.expect_peek(TokenType::LParen) {
+        return Err("Was expecting LParen".to_string());
+    }
+
+    parser.next_token();
+    let subject = Box::new(parser.parse_expression(Precedence::Lowest)?);
+
     if !parser.expect_peek(TokenType::RParen) {
         return Err("Was expecting RParen".to_string());
     }
+    if !parser.expect_peek(TokenType::LBrace) {
+        return Err("Was expecting LBrace".to_string());
+    }
+
+    let mut arms: Vec<expression::MatchArm> = Vec::new();
+    while !parser.peek_token_is(&TokenType::RBrace) {
+        parser.next_token();
+        let pattern = parse_pattern(parser)?;
+
+        let guard = match parser.peek_token_is(&TokenType::If) {
+            true => {
+                parser.next_token();
+                parser.next_token();
+                Some(parser.parse_expression(Precedence::Lowest)?)
+            }
+            false => None,
+        };
+
+        if !parser.expect_peek(TokenType::FatArrow) {
+            return Err("Was expecting FatArrow".to_string());
+        }
+
+        // The body is the value of the whole `match`, so it may leave a loop
+        // unless that value is used.
+        parser.next_token();
+        let body = parser.parse_statement_expression(Precedence::Lowest)?;
+
+        if arms
+            .iter()
+            .any(|arm| arm.guard.is_none() && covers(&arm.pattern, &pattern))
+        {
+            parser.warnings.push(format!(
+                "unreachable match arm {} at {}",
+                pattern,
+                pattern.token().position
+            ));
+        }
+
+        arms.push(expression::MatchArm {
+            pattern,
+            guard,
+            body,
+        });
+
+        if !parser.peek_token_is(&TokenType::RBrace) && !parser.expect_peek(TokenType::Comma) {
+            return Err("Was expecting Comma or RBrace".to_string());
+        }
+    }
+
+    parser.next_token();
+
+    Ok(Expression::Match(expression::Match {
+        token,
+        subject,
+        arms,
+    }))
+}
+
+/// Whether every value `later` matches is also matched by `earlier`.
+fn covers(earlier: &Pattern, later: &Pattern) -> bool {
+    let all_cover = |earlier: &[Pattern], later: &[Pattern]| {
+        earlier.iter().zip(later).all(|(e, l)| covers(e, l))
+    };
 
-    Ok(exp)
+    match (earlier, later) {
+        (Pattern::Identifier(_) | Pattern::Wildcard(_), _) => true,
+        (Pattern::Literal(e), Pattern::Literal(l)) => e.to_string() == l.to_string(),
+        (Pattern::Tuple(e), Pattern::Tuple(l)) => {
+            e.elements.len() == l.elements.len() && all_cover(&e.elements, &l.elements)
+        }
+        (Pattern::Array(e), Pattern::Array(l)) => {
+            let lengths = match (&e.rest, &l.rest) {
+                (None, None) => e.elements.len() == l.elements.len(),
+                (Some(rest), _) => {
+                    matches!(**rest, Pattern::Identifier(_) | Pattern::Wildcard(_))
+                        && e.elements.len() <= l.elements.len()
+                }
+                (None, Some(_)) => false,
+            };
+
+            lengths && all_cover(&e.elements, &l.elements)
+        }
+        (Pattern::Hash(e), Pattern::Hash(l)) => e.pairs.iter().all(|(key, pattern)| {
+            l.pairs
+                .iter()
+                .any(|(k, p)| k.to_string() == key.to_string() && covers(pattern, p))
+        }),
+        _ => false,
+    }
 }
 
 pub fn parse_if_expression(parser: &mut Parser) -> ParserError<Expression> {
@@ -118,7 +512,7 @@ pub fn parse_if_expression(parser: &mut Parser) -> ParserError<Expression> {
     if parser.peek_token_is(&TokenType::Else) {
         parser.next_token();
 
-        if !parser.peek_token_is(&TokenType::LBrace) {
+        if !parser.expect_peek(TokenType::LBrace) {
             return Err("Was expecting LBrace".to_string());
         }
 
@@ -135,13 +529,14 @@ pub fn parse_block_statement(parser: &mut Parser) -> ParserError<statement::Bloc
 
     parser.next_token();
 
-    while !parser.cur_token_is(TokenType::RBrace) && !parser.cur_token_is(TokenType::EOF) {
-        let stmt = parser.parse_statement();
-
-        if let Ok(stmt) = stmt {
-            statements.push(stmt);
+    while !parser.cur_token_is(TokenType::RBrace) {
+        if parser.cur_token_is(TokenType::EOF) {
+            let error = "expected } before the end of the input".to_string();
+            parser.errors.push(error.clone());
+            return Err(error);
         }
 
+        statements.push(parser.parse_statement()?);
         parser.next_token();
     }
 
@@ -161,7 +556,13 @@ pub fn parse_function_literal(parser: &mut Parser) -> ParserError<expression::Ex
         return Err("Was expecting LBrace next got none".to_string());
     };
 
-    let body = parse_block_statement(parser)?;
+    // A loop outside the function cannot be left from inside it.
+    let loops = std::mem::take(&mut parser.loops);
+    let exits = std::mem::take(&mut parser.exits);
+    let body = parse_block_statement(parser);
+    parser.loops = loops;
+    parser.exits = exits;
+    let body = body?;
 
     Ok(Expression::FunctionLiteral(expression::FunctionLiteral {
         token,
@@ -206,7 +607,7 @@ pub fn parse_call_expression(
     parser: &mut Parser,
     function: Box<Expression>,
 ) -> ParserError<Expression> {
-    let arguments = parse_call_arguments(parser);
+    let arguments = parse_expression_list(parser, TokenType::RParen)?;
 
     Ok(Expression::Call(expression::Call {
         token: parser.cur_token.clone(),
@@ -215,45 +616,250 @@ pub fn parse_call_expression(
     }))
 }
 
-fn parse_call_arguments(parser: &mut Parser) -> Vec<Expression> {
-    let mut args: Vec<Expression> = Vec::new();
+pub fn parse_array_literal(parser: &mut Parser) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+    let elements = parse_expression_list(parser, TokenType::RBracket)?;
 
-    if parser.peek_token_is(&TokenType::RParen) {
+    Ok(Expression::ArrayLiteral(expression::ArrayLiteral {
+        token,
+        elements,
+    }))
+}
+
+/// A `{` in expression position always opens a hash literal; blocks only
+/// follow `if`, `else` and `fn`, which consume their `{` themselves.
+pub fn parse_hash_literal(parser: &mut Parser) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+    let mut pairs = Vec::new();
+
+    while !parser.peek_token_is(&TokenType::RBrace) {
         parser.next_token();
-        return args;
+        let key = parser.parse_expression(Precedence::Lowest)?;
+
+        if !parser.expect_peek(TokenType::Colon) {
+            return Err("Was expecting Colon".to_string());
+        }
+
+        parser.next_token();
+        let value = parser.parse_expression(Precedence::Lowest)?;
+
+        pairs.push((key, value));
+
+        if !parser.peek_token_is(&TokenType::RBrace) && !parser.expect_peek(TokenType::Comma) {
+            return Err("Was expecting Comma or RBrace".to_string());
+        }
+    }
+
+    parser.next_token();
+
+    Ok(Expression::HashLiteral(expression::HashLiteral {
+        token,
+        pairs,
+    }))
+}
+
+pub fn parse_index_expression(
+    parser: &mut Parser,
+    left: Box<Expression>,
+) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+
+    parser.next_token();
+    let index = Box::new(parser.parse_expression(Precedence::Lowest)?);
+
+    if !parser.expect_peek(TokenType::RBracket) {
+        return Err("Was expecting RBracket".to_string());
     }
 
+    Ok(Expression::Index(expression::Index { token, left, index }))
+}
+
+pub fn parse_optional_index_expression(
+    parser: &mut Parser,
+    left: Box<Expression>,
+) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+
     parser.next_token();
+    let index = Box::new(parser.parse_expression(Precedence::Lowest)?);
 
-    if let Ok(arg) = parser.parse_expression(Precedence::Lowest) {
-        args.push(arg);
+    if !parser.expect_peek(TokenType::RBracket) {
+        return Err("Was expecting RBracket".to_string());
     }
 
+    Ok(Expression::OptionalIndex(expression::OptionalIndex {
+        token,
+        left,
+        index,
+    }))
+}
+
+pub fn parse_member_expression(
+    parser: &mut Parser,
+    left: Box<Expression>,
+) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+
+    if !parser.expect_peek(TokenType::Ident) {
+        return Err("Was expecting Ident".to_string());
+    }
+
+    let member = expression::Identifier {
+        token: parser.cur_token.clone(),
+        value: parser.cur_token.literal.clone(),
+    };
+
+    Ok(Expression::Member(expression::Member {
+        token,
+        left,
+        member,
+    }))
+}
+
+pub fn parse_optional_member_expression(
+    parser: &mut Parser,
+    left: Box<Expression>,
+) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+
+    if !parser.expect_peek(TokenType::Ident) {
+        return Err("Was expecting Ident".to_string());
+    }
+
+    let member = expression::Identifier {
+        token: parser.cur_token.clone(),
+        value: parser.cur_token.literal.clone(),
+    };
+
+    Ok(Expression::OptionalMember(expression::OptionalMember {
+        token,
+        left,
+        member,
+    }))
+}
+
+/// Only names, index expressions and fields can be assigned to, so anything
+/// else on the left is rejected here rather than when it is evaluated.
+pub fn parse_assign_expression(
+    parser: &mut Parser,
+    target: Box<Expression>,
+) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+    let operator = parser.cur_token.literal.clone();
+
+    if !matches!(
+        *target,
+        Expression::Identifier(_) | Expression::Index(_) | Expression::Member(_)
+    ) {
+        let error = format!("invalid assignment target: {}", target);
+        parser.errors.push(error.clone());
+        return Err(error);
+    }
+
+    let precedence = parser.cur_precedence();
+    parser.next_token();
+
+    let value = Box::new(parser.parse_expression(precedence)?);
+
+    Ok(Expression::Assign(expression::Assign {
+        token,
+        target,
+        operator,
+        value,
+    }))
+}
+
+pub fn parse_range_expression(
+    parser: &mut Parser,
+    left: Box<Expression>,
+) -> ParserError<Expression> {
+    parse_range(parser, Some(left))
+}
+
+/// A range with no start, as in `..end`.
+pub fn parse_prefix_range_expression(parser: &mut Parser) -> ParserError<Expression> {
+    parse_range(parser, None)
+}
+
+/// Parses the end of a range, if the next token can begin one. The current
+/// token is the `..` or `..=`.
+fn parse_range(parser: &mut Parser, start: Option<Box<Expression>>) -> ParserError<Expression> {
+    let token = parser.cur_token.clone();
+    let inclusive = parser.cur_token_is(TokenType::DotDotEq);
+
+    let end = match parser.prefix_parse_fns(parser.peek_token.token_type.clone()) {
+        Some(_) => {
+            parser.next_token();
+            Some(Box::new(parser.parse_expression(Precedence::Range)?))
+        }
+        None if inclusive => {
+            let error = "expected an end for inclusive range".to_string();
+            parser.errors.push(error.clone());
+            return Err(error);
+        }
+        None => None,
+    };
+
+    Ok(Expression::Range(expression::Range {
+        token,
+        start,
+        end,
+        inclusive,
+    }))
+}
+
+pub fn parse_null(parser: &mut Parser) -> ParserError<Expression> {
+    Ok(Expression::Null(expression::Null {
+        token: parser.cur_token.clone(),
+    }))
+}
+
+/// Parses comma separated expressions up to and including `end`.
+fn parse_expression_list(parser: &mut Parser, end: TokenType) -> ParserError<Vec<Expression>> {
+    let mut list: Vec<Expression> = Vec::new();
+
+    if parser.peek_token_is(&end) {
+        parser.next_token();
+        return Ok(list);
+    }
+
+    parser.next_token();
+    list.push(parser.parse_expression(Precedence::Lowest)?);
+
     while parser.peek_token_is(&TokenType::Comma) {
         parser.next_token();
         parser.next_token();
 
-        if let Ok(arg) = parser.parse_expression(Precedence::Lowest) {
-            args.push(arg);
-        }
+        list.push(parser.parse_expression(Precedence::Lowest)?);
     }
 
-    if !parser.expect_peek(TokenType::RParen) {
-        panic!("Was expecting a closing bracket");
+    if !parser.expect_peek(end.clone()) {
+        return Err(format!("Was expecting {}", end));
     };
 
-    args
+    Ok(list)
 }
 
 #[derive(PartialEq, PartialOrd)]
 pub enum Precedence {
     Lowest,
+    Assign,
+    Coalesce,
+    Or,
+    And,
     Equals,
     LessGreater,
+    Range,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
     Sum,
     Product,
     Prefix,
+    Power,
     Call,
+    Index,
 }
 
 impl Precedence {
@@ -263,12 +869,48 @@ impl Precedence {
             TokenType::NotEq => Self::Equals,
             TokenType::Lt => Self::LessGreater,
             TokenType::Gt => Self::LessGreater,
+            TokenType::LtEq => Self::LessGreater,
+            TokenType::GtEq => Self::LessGreater,
+            TokenType::And => Self::And,
+            TokenType::Or => Self::Or,
             TokenType::Plus => Self::Sum,
             TokenType::Minus => Self::Sum,
             TokenType::Slash => Self::Product,
             TokenType::Asterisk => Self::Product,
+            TokenType::Percent => Self::Product,
+            TokenType::Power => Self::Power,
+            TokenType::Pipe => Self::BitOr,
+            TokenType::Caret => Self::BitXor,
+            TokenType::Ampersand => Self::BitAnd,
+            TokenType::ShiftLeft => Self::Shift,
+            TokenType::ShiftRight => Self::Shift,
             TokenType::LParen => Self::Call,
+            TokenType::Dot => Self::Call,
+            TokenType::LBracket => Self::Index,
+            TokenType::OptionalDot => Self::Index,
+            TokenType::OptionalBracket => Self::Index,
+            TokenType::NullCoalesce => Self::Coalesce,
+            TokenType::DotDot => Self::Range,
+            TokenType::DotDotEq => Self::Range,
+            TokenType::Assign => Self::Assign,
+            TokenType::PlusAssign => Self::Assign,
+            TokenType::MinusAssign => Self::Assign,
+            TokenType::AsteriskAssign => Self::Assign,
+            TokenType::SlashAssign => Self::Assign,
             _ => Precedence::Lowest,
         }
     }
+
+    /// Operators that group from the right; all others group from the left.
+    pub fn is_right_associative(token: &TokenType) -> bool {
+        matches!(
+            token,
+            TokenType::Power
+                | TokenType::Assign
+                | TokenType::PlusAssign
+                | TokenType::MinusAssign
+                | TokenType::AsteriskAssign
+                | TokenType::SlashAssign
+        )
+    }
 }
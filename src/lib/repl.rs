@@ -1,12 +1,12 @@
-use crate::lexer;
-use crate::parser::Parser;
-use crate::token;
+use crate::interpreter::{Interpreter, InterpreterError};
 use std::io::Write;
 use std::io::{self, BufRead};
 
 static PROMPT: &str = ">> ";
 
 pub fn start(io_in: io::Stdin, mut io_out: io::Stdout) -> io::Result<()> {
+    let mut interpreter = Interpreter::new();
+
     loop {
         let _ = io_out.lock();
         io_out.write_all(PROMPT.as_bytes())?;
@@ -14,23 +14,30 @@ pub fn start(io_in: io::Stdin, mut io_out: io::Stdout) -> io::Result<()> {
 
         let mut scanner = io_in.lock();
         let mut line = String::new();
-        scanner.read_line(&mut line)?;
+        if scanner.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
 
-        let l = Box::new(lexer::Lexer::new(line));
-        let mut p = Parser::new(l);
+        let result = interpreter.eval_str(&line);
+        for msg in interpreter.warnings().iter() {
+            io_out.write_all(format!("warning: {}\n", msg).as_bytes())?;
+        }
 
-        if let Ok(program) = p.parse_program() {
-            io_out.write_all(program.to_string().as_bytes())?;
-            io_out.write_all("\n".as_bytes())?;
-        } else {
-            let errors = p.errors();
-            print_parse_errors(&io_out, &errors)?;
-            continue;
+        match result {
+            Ok(evaluated) => {
+                io_out.write_all(evaluated.to_string().as_bytes())?;
+                io_out.write_all("\n".as_bytes())?;
+            }
+            Err(InterpreterError::Parse(errors)) => print_parse_errors(&io_out, &errors)?,
+            Err(err) => {
+                io_out.write_all(err.to_string().as_bytes())?;
+                io_out.write_all("\n".as_bytes())?;
+            }
         }
     }
 }
 
-fn print_parse_errors(mut io_out: &io::Stdout, errors: &Vec<String>) -> io::Result<()> {
+fn print_parse_errors(mut io_out: &io::Stdout, errors: &[String]) -> io::Result<()> {
     for msg in errors.iter() {
         io_out.write_all(msg.as_bytes())?;
         io_out.write_all("\n".as_bytes())?;
@@ -9,6 +9,9 @@ pub enum TokenType {
     // Identifier + literals
     Ident,
     Int,
+    Float,
+    String,
+    Label,
 
     //Operators
     Assign,
@@ -17,20 +20,53 @@ pub enum TokenType {
     Bang,
     Asterisk,
     Slash,
+    Percent,
+    Power,
+
+    PlusAssign,
+    MinusAssign,
+    AsteriskAssign,
+    SlashAssign,
+
+    Ampersand,
+    Pipe,
+    Caret,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
+
     Eq,
     NotEq,
 
     Lt,
     Gt,
+    LtEq,
+    GtEq,
+
+    And,
+    Or,
+
+    NullCoalesce,
+    OptionalDot,
+    OptionalBracket,
+
+    Dot,
+    DotDot,
+    DotDotEq,
+    Ellipsis,
 
     // Delimiters
     Comma,
     Semicolon,
+    Colon,
+    FatArrow,
 
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
 
     // Keywords
     Function,
@@ -40,6 +76,15 @@ pub enum TokenType {
     If,
     Else,
     Return,
+    Null,
+    Struct,
+    Mut,
+    While,
+    Break,
+    Continue,
+    For,
+    In,
+    Match,
 }
 
 impl Display for TokenType {
@@ -48,27 +93,63 @@ impl Display for TokenType {
             Self::Illegal => write!(f, ""),
             Self::EOF => write!(f, ""),
 
-            Self::Ident => write!(f, "{}", ""),
-            Self::Int => write!(f, "{}", ""),
+            Self::Ident => write!(f, ""),
+            Self::Int => write!(f, ""),
+            Self::Float => write!(f, ""),
+            Self::String => write!(f, ""),
+            Self::Label => write!(f, ""),
             Self::Assign => write!(f, "="),
             Self::Plus => write!(f, "+"),
             Self::Minus => write!(f, "-"),
             Self::Bang => write!(f, "!"),
             Self::Asterisk => write!(f, "*"),
             Self::Slash => write!(f, "/"),
+            Self::Percent => write!(f, "%"),
+            Self::Power => write!(f, "**"),
+
+            Self::PlusAssign => write!(f, "+="),
+            Self::MinusAssign => write!(f, "-="),
+            Self::AsteriskAssign => write!(f, "*="),
+            Self::SlashAssign => write!(f, "/="),
+
+            Self::Ampersand => write!(f, "&"),
+            Self::Pipe => write!(f, "|"),
+            Self::Caret => write!(f, "^"),
+            Self::Tilde => write!(f, "~"),
+            Self::ShiftLeft => write!(f, "<<"),
+            Self::ShiftRight => write!(f, ">>"),
+
             Self::Eq => write!(f, "=="),
             Self::NotEq => write!(f, "!="),
 
             Self::Lt => write!(f, "<"),
             Self::Gt => write!(f, ">"),
+            Self::LtEq => write!(f, "<="),
+            Self::GtEq => write!(f, ">="),
+
+            Self::And => write!(f, "&&"),
+            Self::Or => write!(f, "||"),
+
+            Self::NullCoalesce => write!(f, "??"),
+            Self::OptionalDot => write!(f, "?."),
+            Self::OptionalBracket => write!(f, "?["),
+
+            Self::Dot => write!(f, "."),
+            Self::DotDot => write!(f, ".."),
+            Self::DotDotEq => write!(f, "..="),
+            Self::Ellipsis => write!(f, "..."),
 
             Self::Comma => write!(f, ","),
             Self::Semicolon => write!(f, ";"),
+            Self::Colon => write!(f, ":"),
+            Self::FatArrow => write!(f, "=>"),
 
             Self::LParen => write!(f, "("),
             Self::RParen => write!(f, ")"),
-            Self::LBrace => write!(f, "{}", '{'),
-            Self::RBrace => write!(f, "{}", '}'),
+            Self::LBrace => write!(f, "{{"),
+            Self::RBrace => write!(f, "}}"),
+            Self::LBracket => write!(f, "["),
+            Self::RBracket => write!(f, "]"),
 
             Self::Function => write!(f, "fn"),
             Self::Let => write!(f, "let"),
@@ -77,14 +158,38 @@ impl Display for TokenType {
             Self::If => write!(f, "if"),
             Self::Else => write!(f, "else"),
             Self::Return => write!(f, "return"),
+            Self::Null => write!(f, "null"),
+            Self::Struct => write!(f, "struct"),
+            Self::Mut => write!(f, "mut"),
+            Self::While => write!(f, "while"),
+            Self::Break => write!(f, "break"),
+            Self::Continue => write!(f, "continue"),
+            Self::For => write!(f, "for"),
+            Self::In => write!(f, "in"),
+            Self::Match => write!(f, "match"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Where a token starts in the source, as 1-based line and byte column. Tokens
+/// that did not come from a lexer are at `0:0`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub literal: String,
+    pub position: Position,
 }
 
 impl Token {
@@ -92,6 +197,7 @@ impl Token {
         Self {
             token_type,
             literal,
+            position: Position::default(),
         }
     }
 }
@@ -104,33 +210,78 @@ impl Display for Token {
 
             TokenType::Ident => write!(f, "{}", self.literal),
             TokenType::Int => write!(f, "{}", self.literal),
-            TokenType::Assign => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Plus => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Minus => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Bang => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Asterisk => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Slash => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Eq => write!(f, "{}", self.token_type.to_string()),
-            TokenType::NotEq => write!(f, "{}", self.token_type.to_string()),
-
-            TokenType::Lt => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Gt => write!(f, "{}", self.token_type.to_string()),
-
-            TokenType::Comma => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Semicolon => write!(f, "{}", self.token_type.to_string()),
-
-            TokenType::LParen => write!(f, "{}", self.token_type.to_string()),
-            TokenType::RParen => write!(f, "{}", self.token_type.to_string()),
-            TokenType::LBrace => write!(f, "{}", self.token_type.to_string()),
-            TokenType::RBrace => write!(f, "{}", self.token_type.to_string()),
-
-            TokenType::Function => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Let => write!(f, "{}", self.token_type.to_string()),
-            TokenType::True => write!(f, "{}", self.token_type.to_string()),
-            TokenType::False => write!(f, "{}", self.token_type.to_string()),
-            TokenType::If => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Else => write!(f, "{}", self.token_type.to_string()),
-            TokenType::Return => write!(f, "{}", self.token_type.to_string()),
+            TokenType::Float => write!(f, "{}", self.literal),
+            TokenType::String => write!(f, "{}", self.literal),
+            TokenType::Label => write!(f, "'{}", self.literal),
+            TokenType::Assign => write!(f, "{}", self.token_type),
+            TokenType::Plus => write!(f, "{}", self.token_type),
+            TokenType::Minus => write!(f, "{}", self.token_type),
+            TokenType::Bang => write!(f, "{}", self.token_type),
+            TokenType::Asterisk => write!(f, "{}", self.token_type),
+            TokenType::Slash => write!(f, "{}", self.token_type),
+            TokenType::Percent => write!(f, "{}", self.token_type),
+            TokenType::Power => write!(f, "{}", self.token_type),
+
+            TokenType::PlusAssign => write!(f, "{}", self.token_type),
+            TokenType::MinusAssign => write!(f, "{}", self.token_type),
+            TokenType::AsteriskAssign => write!(f, "{}", self.token_type),
+            TokenType::SlashAssign => write!(f, "{}", self.token_type),
+
+            TokenType::Ampersand => write!(f, "{}", self.token_type),
+            TokenType::Pipe => write!(f, "{}", self.token_type),
+            TokenType::Caret => write!(f, "{}", self.token_type),
+            TokenType::Tilde => write!(f, "{}", self.token_type),
+            TokenType::ShiftLeft => write!(f, "{}", self.token_type),
+            TokenType::ShiftRight => write!(f, "{}", self.token_type),
+
+            TokenType::Eq => write!(f, "{}", self.token_type),
+            TokenType::NotEq => write!(f, "{}", self.token_type),
+
+            TokenType::Lt => write!(f, "{}", self.token_type),
+            TokenType::Gt => write!(f, "{}", self.token_type),
+            TokenType::LtEq => write!(f, "{}", self.token_type),
+            TokenType::GtEq => write!(f, "{}", self.token_type),
+
+            TokenType::And => write!(f, "{}", self.token_type),
+            TokenType::Or => write!(f, "{}", self.token_type),
+
+            TokenType::NullCoalesce => write!(f, "{}", self.token_type),
+            TokenType::OptionalDot => write!(f, "{}", self.token_type),
+            TokenType::OptionalBracket => write!(f, "{}", self.token_type),
+
+            TokenType::Dot => write!(f, "{}", self.token_type),
+            TokenType::DotDot => write!(f, "{}", self.token_type),
+            TokenType::DotDotEq => write!(f, "{}", self.token_type),
+            TokenType::Ellipsis => write!(f, "{}", self.token_type),
+
+            TokenType::Comma => write!(f, "{}", self.token_type),
+            TokenType::Semicolon => write!(f, "{}", self.token_type),
+            TokenType::Colon => write!(f, "{}", self.token_type),
+            TokenType::FatArrow => write!(f, "{}", self.token_type),
+
+            TokenType::LParen => write!(f, "{}", self.token_type),
+            TokenType::RParen => write!(f, "{}", self.token_type),
+            TokenType::LBrace => write!(f, "{}", self.token_type),
+            TokenType::RBrace => write!(f, "{}", self.token_type),
+            TokenType::LBracket => write!(f, "{}", self.token_type),
+            TokenType::RBracket => write!(f, "{}", self.token_type),
+
+            TokenType::Function => write!(f, "{}", self.token_type),
+            TokenType::Let => write!(f, "{}", self.token_type),
+            TokenType::True => write!(f, "{}", self.token_type),
+            TokenType::False => write!(f, "{}", self.token_type),
+            TokenType::If => write!(f, "{}", self.token_type),
+            TokenType::Else => write!(f, "{}", self.token_type),
+            TokenType::Return => write!(f, "{}", self.token_type),
+            TokenType::Null => write!(f, "{}", self.token_type),
+            TokenType::Struct => write!(f, "{}", self.token_type),
+            TokenType::Mut => write!(f, "{}", self.token_type),
+            TokenType::While => write!(f, "{}", self.token_type),
+            TokenType::Break => write!(f, "{}", self.token_type),
+            TokenType::Continue => write!(f, "{}", self.token_type),
+            TokenType::For => write!(f, "{}", self.token_type),
+            TokenType::In => write!(f, "{}", self.token_type),
+            TokenType::Match => write!(f, "{}", self.token_type),
         }
     }
 }
@@ -146,6 +297,15 @@ pub fn lookup_keyword(ident: &[u8]) -> TokenType {
         ("if".to_string(), TokenType::If),
         ("else".to_string(), TokenType::Else),
         ("return".to_string(), TokenType::Return),
+        ("null".to_string(), TokenType::Null),
+        ("struct".to_string(), TokenType::Struct),
+        ("mut".to_string(), TokenType::Mut),
+        ("while".to_string(), TokenType::While),
+        ("break".to_string(), TokenType::Break),
+        ("continue".to_string(), TokenType::Continue),
+        ("for".to_string(), TokenType::For),
+        ("in".to_string(), TokenType::In),
+        ("match".to_string(), TokenType::Match),
     ]);
 
     keywords.get(&ident).unwrap_or(&TokenType::Illegal).clone()
@@ -162,7 +322,16 @@ pub fn is_keyword(ident: &[u8]) -> bool {
         ("if".to_string(), TokenType::If),
         ("else".to_string(), TokenType::Else),
         ("return".to_string(), TokenType::Return),
+        ("null".to_string(), TokenType::Null),
+        ("struct".to_string(), TokenType::Struct),
+        ("mut".to_string(), TokenType::Mut),
+        ("while".to_string(), TokenType::While),
+        ("break".to_string(), TokenType::Break),
+        ("continue".to_string(), TokenType::Continue),
+        ("for".to_string(), TokenType::For),
+        ("in".to_string(), TokenType::In),
+        ("match".to_string(), TokenType::Match),
     ]);
 
-    keywords.get(&ident).is_some()
+    keywords.contains_key(&ident)
 }
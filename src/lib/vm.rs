@@ -0,0 +1,689 @@
+mod frame;
+
+use crate::code::{self, Opcode};
+use crate::compiler::Bytecode;
+use crate::eval::{
+    build_hash, build_range, build_struct, destructure, eval_index_expression,
+    eval_infix_expression, eval_member_expression, eval_prefix_expression, match_pattern,
+    no_match_arm, set_index, set_member, Shape,
+};
+use crate::object::heap::{CellHeap, HeapStats};
+use crate::object::iter::Iter;
+use crate::object::native::{NativeFunction, NativeRegistry};
+use crate::object::{Closure, CompiledFunction, Object};
+use frame::Frame;
+use std::rc::Rc;
+
+pub const STACK_SIZE: usize = 2048;
+pub const MAX_FRAMES: usize = 1024;
+
+pub type VmError<T> = Result<T, String>;
+
+#[derive(Debug)]
+pub struct VM {
+    constants: Vec<Object>,
+    stack: Vec<Object>,
+    globals: Vec<Object>,
+    frames: Vec<Frame>,
+    natives: NativeRegistry,
+    last_popped: Object,
+    // Declared last so the stack and globals are gone by the time it is
+    // dropped and collects.
+    heap: CellHeap,
+}
+
+impl VM {
+    pub fn new(bytecode: Bytecode) -> Self {
+        Self::new_with_globals(bytecode, Vec::new())
+    }
+
+    pub fn new_with_globals(bytecode: Bytecode, globals: Vec<Object>) -> Self {
+        let main_function = CompiledFunction {
+            instructions: bytecode.instructions,
+            num_locals: 0,
+            num_parameters: 0,
+            source_map: bytecode.source_map,
+        };
+        let main_closure = Rc::new(Closure {
+            function: Rc::new(main_function),
+            free: vec![],
+        });
+
+        Self {
+            constants: bytecode.constants,
+            stack: Vec::with_capacity(STACK_SIZE),
+            globals,
+            frames: vec![Frame::new(main_closure, 0)],
+            natives: NativeRegistry::new(),
+            last_popped: Object::Null,
+            heap: CellHeap::new(),
+        }
+    }
+
+    /// Supplies the natives `OpGetNative` refers to; this must be the registry
+    /// the program was compiled against.
+    pub fn set_natives(&mut self, natives: NativeRegistry) {
+        self.natives = natives;
+    }
+
+    pub fn last_popped_stack_elem(&self) -> &Object {
+        &self.last_popped
+    }
+
+    pub fn into_globals(self) -> Vec<Object> {
+        self.globals
+    }
+
+    pub fn heap_stats(&self) -> HeapStats {
+        self.heap.stats()
+    }
+
+    /// Runs a full collection now rather than waiting for the allocation
+    /// threshold, returning the number of cells emptied.
+    pub fn collect_garbage(&mut self) -> usize {
+        self.heap.collect()
+    }
+
+    pub fn run(&mut self) -> VmError<()> {
+        while let Some(op) = self.read_opcode()? {
+            match op {
+                Opcode::Constant => {
+                    let index = self.read_u16();
+                    self.push(self.constants[index].clone())?;
+                }
+                Opcode::Pop => {
+                    self.last_popped = self.pop();
+                }
+                Opcode::Dup => {
+                    let top = self.stack.last().cloned().unwrap_or(Object::Null);
+                    self.push(top)?;
+                }
+                Opcode::Dup2 => {
+                    let below = self.peek(1)?.clone();
+                    let top = self.peek(0)?.clone();
+                    self.push(below)?;
+                    self.push(top)?;
+                }
+                // Moves the top of the stack down under the given number of
+                // values.
+                Opcode::Bury => {
+                    let depth = self.read_u8();
+                    let top = self.pop();
+                    match self.stack.len().checked_sub(depth) {
+                        Some(index) => self.stack.insert(index, top),
+                        None => return Err("stack underflow".to_string()),
+                    }
+                }
+                Opcode::Cell => {
+                    let value = self.pop();
+                    if self.heap.should_collect() {
+                        self.heap.collect();
+                    }
+                    let cell = self.heap.new_cell(value);
+                    self.push(Object::Cell(cell))?;
+                }
+                Opcode::Deref => match self.pop() {
+                    Object::Cell(cell) => {
+                        let value = cell.borrow().clone();
+                        self.push(value)?;
+                    }
+                    _ => return Err("dereference of a value that is not a cell".to_string()),
+                },
+                Opcode::SetCell => {
+                    let cell = self.pop();
+                    let value = self.pop();
+                    match cell {
+                        Object::Cell(cell) => *cell.borrow_mut() = value,
+                        _ => return Err("assignment to a value that is not a cell".to_string()),
+                    }
+                }
+                Opcode::Add => self.execute_binary_operation("+")?,
+                Opcode::Sub => self.execute_binary_operation("-")?,
+                Opcode::Mul => self.execute_binary_operation("*")?,
+                Opcode::Div => self.execute_binary_operation("/")?,
+                Opcode::Equal => self.execute_binary_operation("==")?,
+                Opcode::NotEqual => self.execute_binary_operation("!=")?,
+                Opcode::GreaterThan => self.execute_binary_operation(">")?,
+                Opcode::GreaterEqual => self.execute_binary_operation(">=")?,
+                Opcode::Mod => self.execute_binary_operation("%")?,
+                Opcode::Pow => self.execute_binary_operation("**")?,
+                Opcode::BitAnd => self.execute_binary_operation("&")?,
+                Opcode::BitOr => self.execute_binary_operation("|")?,
+                Opcode::BitXor => self.execute_binary_operation("^")?,
+                Opcode::ShiftLeft => self.execute_binary_operation("<<")?,
+                Opcode::ShiftRight => self.execute_binary_operation(">>")?,
+                Opcode::True => self.push(Object::Boolean(true))?,
+                Opcode::False => self.push(Object::Boolean(false))?,
+                Opcode::Null => self.push(Object::Null)?,
+                Opcode::Minus => self.execute_prefix_operation("-")?,
+                Opcode::Bang => self.execute_prefix_operation("!")?,
+                Opcode::BitNot => self.execute_prefix_operation("~")?,
+                Opcode::Jump => {
+                    let target = self.read_u16();
+                    self.current_frame_mut().ip = target;
+                }
+                Opcode::JumpNotTruthy => {
+                    let target = self.read_u16();
+                    let condition = self.pop();
+
+                    if !condition.is_truthy() {
+                        self.current_frame_mut().ip = target;
+                    }
+                }
+                Opcode::JumpNull => {
+                    let target = self.read_u16();
+
+                    if matches!(self.stack.last(), Some(Object::Null)) {
+                        self.current_frame_mut().ip = target;
+                    }
+                }
+                // Keeps a non-null value as the result, otherwise drops it so
+                // the fallback can take its place.
+                Opcode::JumpNotNull => {
+                    let target = self.read_u16();
+
+                    if matches!(self.stack.last(), Some(Object::Null)) {
+                        self.pop();
+                    } else {
+                        self.current_frame_mut().ip = target;
+                    }
+                }
+                // Like `OpJumpNotNull`, these keep a value that decides the
+                // result of `||` or `&&` and drop one that doesn't.
+                Opcode::JumpTruthy | Opcode::JumpFalsy => {
+                    let target = self.read_u16();
+                    let truthy = self.stack.last().is_some_and(Object::is_truthy);
+
+                    if truthy == (op == Opcode::JumpTruthy) {
+                        self.current_frame_mut().ip = target;
+                    } else {
+                        self.pop();
+                    }
+                }
+                Opcode::SetGlobal => {
+                    let index = self.read_u16();
+                    let value = self.pop();
+
+                    if index >= self.globals.len() {
+                        self.globals.resize(index + 1, Object::Null);
+                    }
+                    self.globals[index] = value;
+                }
+                Opcode::GetGlobal => {
+                    let index = self.read_u16();
+                    let value = self.globals.get(index).cloned().unwrap_or(Object::Null);
+                    self.push(value)?;
+                }
+                // The value must come from above the frame's locals, or
+                // popping it could remove the slot being set.
+                Opcode::SetLocal => {
+                    let frame = self.current_frame();
+                    let locals_end = frame.base_pointer + frame.closure.function.num_locals;
+                    if self.stack.len() <= locals_end {
+                        return Err("stack underflow".to_string());
+                    }
+
+                    let value = self.pop();
+                    let slot = self.local_slot()?;
+                    self.stack[slot] = value;
+                }
+                Opcode::GetLocal => {
+                    let slot = self.local_slot()?;
+                    self.push(self.stack[slot].clone())?;
+                }
+                Opcode::GetFree => {
+                    let index = self.read_u8();
+                    let value = self
+                        .current_frame()
+                        .closure
+                        .free
+                        .get(index)
+                        .cloned()
+                        .ok_or(format!("free variable {} out of range", index))?;
+                    self.push(value)?;
+                }
+                Opcode::GetNative => {
+                    let index = self.read_u8();
+                    let native = self
+                        .natives
+                        .get_by_index(index)
+                        .ok_or(format!("native function {} is not registered", index))?;
+                    self.push(Object::Native(Rc::clone(native)))?;
+                }
+                Opcode::CurrentClosure => {
+                    let closure = Rc::clone(&self.current_frame().closure);
+                    self.push(Object::Closure(closure))?;
+                }
+                Opcode::Closure => {
+                    let index = self.read_u16();
+                    let num_free = self.read_u8();
+                    self.push_closure(index, num_free)?;
+                }
+                Opcode::Array => {
+                    let len = self.read_u16();
+                    let elements = self.pop_many(len)?;
+                    self.push(Object::Array(Rc::new(elements)))?;
+                }
+                Opcode::ArrayExtend => {
+                    let len = self.read_u16();
+                    let elements = self.pop_many(len)?;
+                    match self.pop_many(1)?.pop() {
+                        Some(Object::Array(array)) => {
+                            let mut array = Rc::unwrap_or_clone(array);
+                            array.extend(elements);
+                            self.push(Object::Array(Rc::new(array)))?;
+                        }
+                        _ => return Err("array extend without an array".to_string()),
+                    }
+                }
+                Opcode::Tuple => {
+                    let len = self.read_u16();
+                    let elements = self.pop_many(len)?;
+                    self.push(Object::Tuple(Rc::new(elements)))?;
+                }
+                Opcode::Unpack => {
+                    let len = self.read_u16();
+                    let flags = self.read_u8();
+                    let shape = match flags & code::UNPACK_ARRAY != 0 {
+                        true => Shape::Array {
+                            len,
+                            rest: flags & code::UNPACK_REST != 0,
+                        },
+                        false => Shape::Tuple(len),
+                    };
+
+                    let value = self.pop();
+                    for element in destructure(value, shape)?.into_iter().rev() {
+                        self.push(element)?;
+                    }
+                }
+                Opcode::Hash => {
+                    let len = self.read_u16();
+                    let mut items = self.pop_many(len)?.into_iter();
+                    let mut pairs = Vec::with_capacity(len / 2);
+                    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+                        pairs.push((key, value));
+                    }
+
+                    match build_hash(pairs) {
+                        Object::Error(msg) => return Err(msg),
+                        hash => self.push(hash)?,
+                    }
+                }
+                Opcode::Index => {
+                    let index = self.pop();
+                    let left = self.pop();
+
+                    match eval_index_expression(left, index) {
+                        Object::Error(msg) => return Err(msg),
+                        result => self.push(result)?,
+                    }
+                }
+                // The value to store sits beneath the container and index, and
+                // the updated container is pushed for the next store.
+                Opcode::SetIndex => {
+                    let index = self.pop();
+                    let left = self.pop();
+                    let value = self.pop();
+
+                    match set_index(left, index, value) {
+                        Object::Error(msg) => return Err(msg),
+                        result => self.push(result)?,
+                    }
+                }
+                Opcode::Range => {
+                    let flags = self.read_u8();
+                    let end = (flags & code::RANGE_END != 0).then(|| self.pop());
+                    let start = (flags & code::RANGE_START != 0).then(|| self.pop());
+
+                    match build_range(start, end, flags & code::RANGE_INCLUSIVE != 0) {
+                        Object::Error(msg) => return Err(msg),
+                        range => self.push(range)?,
+                    }
+                }
+                Opcode::Struct => {
+                    let len = self.read_u16();
+                    let mut items = self.pop_many(len)?.into_iter();
+                    let mut fields = Vec::with_capacity(len / 2);
+                    while let (Some(name), Some(value)) = (items.next(), items.next()) {
+                        match name {
+                            Object::String(name) => fields.push((name, value)),
+                            name => {
+                                return Err(format!(
+                                    "field name must be STRING, got {}",
+                                    name.type_name()
+                                ))
+                            }
+                        }
+                    }
+                    let ty = self.pop();
+
+                    match build_struct(ty, fields) {
+                        Object::Error(msg) => return Err(msg),
+                        value => self.push(value)?,
+                    }
+                }
+                Opcode::Member => {
+                    let name = self.pop();
+                    let left = self.pop();
+
+                    let result = match name {
+                        Object::String(name) => eval_member_expression(left, &name),
+                        name => Object::Error(format!(
+                            "member name must be STRING, got {}",
+                            name.type_name()
+                        )),
+                    };
+
+                    match result {
+                        Object::Error(msg) => return Err(msg),
+                        result => self.push(result)?,
+                    }
+                }
+                Opcode::SetMember => {
+                    let name = self.pop();
+                    let left = self.pop();
+                    let value = self.pop();
+
+                    let result = match name {
+                        Object::String(name) => set_member(left, &name, value),
+                        name => Object::Error(format!(
+                            "member name must be STRING, got {}",
+                            name.type_name()
+                        )),
+                    };
+
+                    match result {
+                        Object::Error(msg) => return Err(msg),
+                        result => self.push(result)?,
+                    }
+                }
+                Opcode::Iter => {
+                    let iter = Iter::new(self.pop())?;
+                    self.push(Object::Iterator(Box::new(iter)))?;
+                }
+                // Leaves the iterator under the values for the next iteration,
+                // first value on top, or drops it and jumps once it is done.
+                Opcode::IterNext => {
+                    let target = self.read_u16();
+                    let count = self.read_u8();
+
+                    let values = match self.stack.last_mut() {
+                        Some(Object::Iterator(iter)) => iter.next_values(count),
+                        _ => return Err("for loop without an iterator".to_string()),
+                    };
+
+                    match values {
+                        Some(values) => {
+                            for value in values.into_iter().rev() {
+                                self.push(value)?;
+                            }
+                        }
+                        None => {
+                            self.pop();
+                            self.current_frame_mut().ip = target;
+                        }
+                    }
+                }
+                // Leaves the subject under the values the pattern binds, first
+                // value on top, or jumps to the next arm if it does not match.
+                Opcode::Match => {
+                    let target = self.read_u16();
+                    let matcher = self.read_u16();
+
+                    let mut values = Vec::new();
+                    let matched = match (&self.constants[matcher], self.stack.last()) {
+                        (Object::Matcher(matcher), Some(subject)) => {
+                            match_pattern(matcher, subject, &mut values)
+                        }
+                        _ => return Err("match without a pattern".to_string()),
+                    };
+
+                    match matched {
+                        true => {
+                            for value in values.into_iter().rev() {
+                                self.push(value)?;
+                            }
+                        }
+                        false => self.current_frame_mut().ip = target,
+                    }
+                }
+                Opcode::NoMatch => {
+                    let subject = self.pop();
+                    return Err(no_match_arm(&subject));
+                }
+                Opcode::Call => {
+                    let num_args = self.read_u8();
+                    self.call_function(num_args)?;
+                }
+                Opcode::TailCall => {
+                    let num_args = self.read_u8();
+                    self.tail_call_function(num_args)?;
+                }
+                Opcode::ReturnValue => {
+                    let value = self.pop();
+                    if self.return_from_frame(value)? {
+                        return Ok(());
+                    }
+                }
+                Opcode::Return => {
+                    if self.return_from_frame(Object::Null)? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_opcode(&mut self) -> VmError<Option<Opcode>> {
+        let frame = self.current_frame_mut();
+        let byte = match frame.instructions().get(frame.ip) {
+            Some(byte) => *byte,
+            None => return Ok(None),
+        };
+        frame.ip += 1;
+
+        Opcode::from_byte(byte)
+            .map(Some)
+            .ok_or(format!("opcode {} undefined", byte))
+    }
+
+    fn read_u16(&mut self) -> usize {
+        let frame = self.current_frame_mut();
+        let value = code::read_u16(&frame.instructions()[frame.ip..]) as usize;
+        frame.ip += 2;
+        value
+    }
+
+    fn read_u8(&mut self) -> usize {
+        let frame = self.current_frame_mut();
+        let value = frame.instructions()[frame.ip] as usize;
+        frame.ip += 1;
+        value
+    }
+
+    fn current_frame(&self) -> &Frame {
+        self.frames.last().expect("vm always has a frame")
+    }
+
+    fn current_frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("vm always has a frame")
+    }
+
+    fn push(&mut self, obj: Object) -> VmError<()> {
+        if self.stack.len() >= STACK_SIZE {
+            return Err("stack overflow".to_string());
+        }
+
+        self.stack.push(obj);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Object {
+        self.stack.pop().unwrap_or(Object::Null)
+    }
+
+    /// Pops the top `count` values, returning them bottom first.
+    fn pop_many(&mut self, count: usize) -> VmError<Vec<Object>> {
+        match self.stack.len().checked_sub(count) {
+            Some(start) => Ok(self.stack.split_off(start)),
+            None => Err("stack underflow".to_string()),
+        }
+    }
+
+    /// The value `depth` places below the top of the stack.
+    fn peek(&self, depth: usize) -> VmError<&Object> {
+        match self.stack.len().checked_sub(depth + 1) {
+            Some(index) => Ok(&self.stack[index]),
+            None => Err("stack underflow".to_string()),
+        }
+    }
+
+    /// Reads a local's index operand and finds its slot on the stack. Loaded
+    /// bytecode is not trusted to stay within the frame's locals.
+    fn local_slot(&mut self) -> VmError<usize> {
+        let index = self.read_u8();
+        let frame = self.current_frame();
+        let slot = frame.base_pointer + index;
+
+        match index < frame.closure.function.num_locals && slot < self.stack.len() {
+            true => Ok(slot),
+            false => Err(format!("local {} out of range", index)),
+        }
+    }
+
+    fn execute_binary_operation(&mut self, operator: &str) -> VmError<()> {
+        let right = self.pop();
+        let left = self.pop();
+
+        match eval_infix_expression(operator, left, right) {
+            Object::Error(msg) => Err(msg),
+            result => self.push(result),
+        }
+    }
+
+    fn execute_prefix_operation(&mut self, operator: &str) -> VmError<()> {
+        let right = self.pop();
+
+        match eval_prefix_expression(operator, right) {
+            Object::Error(msg) => Err(msg),
+            result => self.push(result),
+        }
+    }
+
+    fn push_closure(&mut self, index: usize, num_free: usize) -> VmError<()> {
+        let function = match &self.constants[index] {
+            Object::CompiledFunction(function) => Rc::clone(function),
+            other => return Err(format!("not a function: {}", other.type_name())),
+        };
+
+        let free = self.pop_many(num_free)?;
+
+        self.push(Object::Closure(Rc::new(Closure { function, free })))
+    }
+
+    fn callee(&self, num_args: usize) -> VmError<Rc<Closure>> {
+        let closure = match self.peek(num_args)? {
+            Object::Closure(closure) => Rc::clone(closure),
+            other => return Err(format!("not a function: {}", other.type_name())),
+        };
+
+        if closure.function.num_parameters != num_args {
+            return Err(format!(
+                "wrong number of arguments: want={}, got={}",
+                closure.function.num_parameters, num_args
+            ));
+        }
+
+        Ok(closure)
+    }
+
+    fn native_callee(&self, num_args: usize) -> Option<Rc<NativeFunction>> {
+        match self.peek(num_args) {
+            Ok(Object::Native(native)) => Some(Rc::clone(native)),
+            _ => None,
+        }
+    }
+
+    fn call_function(&mut self, num_args: usize) -> VmError<()> {
+        if let Some(native) = self.native_callee(num_args) {
+            return self.call_native(&native, num_args);
+        }
+
+        let closure = self.callee(num_args)?;
+
+        if self.frames.len() >= MAX_FRAMES {
+            return Err("stack overflow".to_string());
+        }
+
+        let base_pointer = self.stack.len() - num_args;
+        let num_locals = closure.function.num_locals;
+
+        if base_pointer + num_locals > STACK_SIZE {
+            return Err("stack overflow".to_string());
+        }
+        self.stack.resize(base_pointer + num_locals, Object::Null);
+
+        self.frames.push(Frame::new(closure, base_pointer));
+
+        Ok(())
+    }
+
+    /// Replaces the current frame with the callee's instead of pushing a new
+    /// one. The callee and its arguments are moved down to where the current
+    /// function and its arguments sit, so the stack does not grow either.
+    fn tail_call_function(&mut self, num_args: usize) -> VmError<()> {
+        if self.frames.len() == 1 {
+            return self.call_function(num_args);
+        }
+
+        // Natives run without a frame, so there is nothing to replace.
+        if let Some(native) = self.native_callee(num_args) {
+            return self.call_native(&native, num_args);
+        }
+
+        let closure = self.callee(num_args)?;
+
+        let base_pointer = self.current_frame().base_pointer;
+        let callee_start = self.stack.len() - 1 - num_args;
+        if callee_start < base_pointer - 1 {
+            return Err("stack underflow".to_string());
+        }
+        self.stack.drain(base_pointer - 1..callee_start);
+
+        let num_locals = closure.function.num_locals;
+        if base_pointer + num_locals > STACK_SIZE {
+            return Err("stack overflow".to_string());
+        }
+        self.stack.resize(base_pointer + num_locals, Object::Null);
+
+        *self.current_frame_mut() = Frame::new(closure, base_pointer);
+
+        Ok(())
+    }
+
+    fn call_native(&mut self, native: &NativeFunction, num_args: usize) -> VmError<()> {
+        let args = self.pop_many(num_args)?;
+        self.pop();
+
+        let result = native.call(&args)?;
+        self.push(result)
+    }
+
+    /// Pops the current frame and pushes `value` for the caller. Returns true
+    /// when the main program itself returned and execution should stop.
+    fn return_from_frame(&mut self, value: Object) -> VmError<bool> {
+        if self.frames.len() == 1 {
+            self.last_popped = value;
+            return Ok(true);
+        }
+
+        let frame = self.frames.pop().expect("vm always has a frame");
+        self.stack.truncate(frame.base_pointer - 1);
+        self.push(value)?;
+
+        Ok(false)
+    }
+}
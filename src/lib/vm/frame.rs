@@ -0,0 +1,24 @@
+use crate::code::Instructions;
+use crate::object::Closure;
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub closure: Rc<Closure>,
+    pub ip: usize,
+    pub base_pointer: usize,
+}
+
+impl Frame {
+    pub fn new(closure: Rc<Closure>, base_pointer: usize) -> Self {
+        Self {
+            closure,
+            ip: 0,
+            base_pointer,
+        }
+    }
+
+    pub fn instructions(&self) -> &Instructions {
+        &self.closure.function.instructions
+    }
+}
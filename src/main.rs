@@ -1,6 +1,5 @@
 use rust_monkey::repl;
 use std::io;
-use whoami;
 
 fn main() {
     let user = whoami::username();
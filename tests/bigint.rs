@@ -0,0 +1,111 @@
+use rust_monkey::object::bigint::BigInt;
+
+fn big(s: &str) -> BigInt {
+    BigInt::from_decimal(s).unwrap()
+}
+
+#[test]
+fn test_decimal_round_trip() {
+    let tests = [
+        "0",
+        "1",
+        "-1",
+        "4294967296",
+        "9223372036854775808",
+        "-9223372036854775809",
+        "1000000000000000000000000000000",
+        "123456789012345678901234567890123456789",
+    ];
+
+    for input in tests.iter() {
+        assert_eq!(big(input).to_string(), *input);
+    }
+
+    assert_eq!(big("-0").to_string(), "0");
+    assert_eq!(big("000123").to_string(), "123");
+    assert!(BigInt::from_decimal("").is_none());
+    assert!(BigInt::from_decimal("-").is_none());
+    assert!(BigInt::from_decimal("12a").is_none());
+}
+
+#[test]
+fn test_i64_conversions() {
+    for value in [0, 1, -1, i64::MAX, i64::MIN, 1 << 32, -(1 << 40)] {
+        let converted = BigInt::from(value);
+        assert_eq!(converted.to_string(), value.to_string());
+        assert_eq!(converted.to_i64(), Some(value));
+    }
+
+    assert_eq!(big("9223372036854775808").to_i64(), None);
+    assert_eq!(big("-9223372036854775809").to_i64(), None);
+    assert_eq!(big("18446744073709551616").to_f64(), 18446744073709551616.0);
+    assert_eq!(big("-4294967296").to_f64(), -4294967296.0);
+}
+
+#[test]
+fn test_arithmetic() {
+    let a = big("123456789012345678901234567890");
+    let b = big("-987654321098765432109876543210");
+
+    assert_eq!((&a + &b).to_string(), "-864197532086419753208641975320");
+    assert_eq!((&a - &b).to_string(), "1111111110111111111011111111100");
+    assert_eq!(
+        (&a * &b).to_string(),
+        "-121932631137021795226185032733622923332237463801111263526900"
+    );
+    assert_eq!((&a + &-&a).to_string(), "0");
+    assert!(!(&a - &a).is_negative());
+
+    assert_eq!(b.checked_div(&a).unwrap().to_string(), "-8");
+    assert_eq!(
+        b.checked_rem(&a).unwrap().to_string(),
+        "-9000000000900000000090"
+    );
+    assert_eq!(
+        a.checked_div(&BigInt::from(-7)).unwrap().to_string(),
+        "-17636684144620811271604938270"
+    );
+    assert_eq!(a.checked_rem(&BigInt::from(7)).unwrap().to_string(), "0");
+    assert!(a.checked_div(&BigInt::from(0)).is_none());
+}
+
+#[test]
+fn test_ordering() {
+    let mut values = [
+        big("18446744073709551616"),
+        big("-18446744073709551616"),
+        BigInt::from(0),
+        BigInt::from(-1),
+        big("4294967296"),
+    ];
+    values.sort();
+
+    let sorted: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    assert_eq!(
+        sorted,
+        [
+            "-18446744073709551616",
+            "-1",
+            "0",
+            "4294967296",
+            "18446744073709551616"
+        ]
+    );
+}
+
+#[test]
+fn test_bits_and_pow() {
+    assert_eq!(big("0").bits(), 0);
+    assert_eq!(big("1").bits(), 1);
+    assert_eq!(big("-255").bits(), 8);
+    assert_eq!(big("4294967296").bits(), 33);
+
+    assert_eq!(
+        big("2").pow(100).to_string(),
+        "1267650600228229401496703205376"
+    );
+    assert_eq!(big("-3").pow(3).to_string(), "-27");
+    assert_eq!(big("-3").pow(4).to_string(), "81");
+    assert_eq!(big("12345").pow(0).to_string(), "1");
+    assert_eq!(big("0").pow(5).to_string(), "0");
+}
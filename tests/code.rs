@@ -0,0 +1,91 @@
+use rust_monkey::code::{self, Opcode};
+
+#[test]
+fn test_make() {
+    let tests = [
+        (
+            Opcode::Constant,
+            vec![65534],
+            vec![Opcode::Constant as u8, 255, 254],
+        ),
+        (Opcode::Add, vec![], vec![Opcode::Add as u8]),
+        (
+            Opcode::GetLocal,
+            vec![255],
+            vec![Opcode::GetLocal as u8, 255],
+        ),
+        (
+            Opcode::Closure,
+            vec![65534, 255],
+            vec![Opcode::Closure as u8, 255, 254, 255],
+        ),
+    ];
+
+    for (op, operands, expected) in tests.iter() {
+        let instruction = code::make(*op, operands);
+
+        assert_eq!(
+            instruction, *expected,
+            "instruction has wrong encoding. want {:?}, got {:?}",
+            expected, instruction
+        );
+    }
+}
+
+#[test]
+fn test_read_operands() {
+    let tests = [
+        (Opcode::Constant, vec![65535], 2),
+        (Opcode::GetLocal, vec![255], 1),
+        (Opcode::Closure, vec![65535, 255], 3),
+    ];
+
+    for (op, operands, bytes_read) in tests.iter() {
+        let instruction = code::make(*op, operands);
+        let def = op.definition();
+
+        let (operands_read, n) = code::read_operands(&def, &instruction[1..]);
+
+        assert_eq!(n, *bytes_read, "n wrong. want {}, got {}", bytes_read, n);
+        assert_eq!(operands_read, *operands);
+    }
+}
+
+#[test]
+fn test_opcode_from_byte() {
+    for op in [Opcode::Constant, Opcode::Add, Opcode::CurrentClosure] {
+        assert_eq!(Opcode::from_byte(op as u8), Some(op));
+    }
+
+    assert_eq!(Opcode::from_byte(255), None);
+}
+
+#[test]
+fn test_try_make_rejects_wide_operands() {
+    let tests = [
+        (
+            Opcode::Constant,
+            vec![65536],
+            "operand 65536 of OpConstant is larger than the limit of 65535",
+        ),
+        (
+            Opcode::GetLocal,
+            vec![256],
+            "operand 256 of OpGetLocal is larger than the limit of 255",
+        ),
+        (
+            Opcode::Closure,
+            vec![1, 300],
+            "operand 300 of OpClosure is larger than the limit of 255",
+        ),
+    ];
+
+    for (op, operands, expected) in tests.iter() {
+        assert_eq!(code::try_make(*op, operands), Err(expected.to_string()));
+    }
+
+    assert_eq!(
+        code::try_make(Opcode::Constant, &[65535]),
+        Ok(vec![Opcode::Constant as u8, 255, 255])
+    );
+}
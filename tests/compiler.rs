@@ -0,0 +1,1237 @@
+use rust_monkey::code::{self, make, Instructions, Opcode};
+use rust_monkey::compiler::symbol_table::{Symbol, SymbolScope, SymbolTable};
+use rust_monkey::compiler::Compiler;
+use rust_monkey::lexer;
+use rust_monkey::object::native::NativeRegistry;
+use rust_monkey::object::Object;
+use rust_monkey::parser;
+
+enum Constant {
+    Int(i64),
+    Float(f64),
+    Str(&'static str),
+    Function(Vec<Instructions>),
+    StructType(&'static str),
+    Matcher(&'static str),
+}
+
+struct Test {
+    input: &'static str,
+    expected_constants: Vec<Constant>,
+    expected_instructions: Vec<Instructions>,
+}
+
+fn run_compiler_tests(tests: Vec<Test>) {
+    for tt in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(tt.input.to_string()));
+        let program = parser::Parser::new(l).parse_program().unwrap();
+
+        let mut compiler = Compiler::new();
+        if let Err(err) = compiler.compile(&program) {
+            panic!("compiler error: {}", err);
+        }
+
+        let bytecode = compiler.bytecode();
+
+        test_instructions(&tt.expected_instructions, &bytecode.instructions, tt.input);
+        test_constants(&tt.expected_constants, &bytecode.constants, tt.input);
+    }
+}
+
+fn test_instructions(expected: &[Instructions], actual: &Instructions, input: &str) {
+    let expected: Instructions = expected.concat();
+
+    assert_eq!(
+        *actual, expected,
+        "wrong instructions for {:?}.\nwant {:?}\ngot  {:?}",
+        input, expected, actual
+    );
+}
+
+fn test_constants(expected: &[Constant], actual: &[Object], input: &str) {
+    assert_eq!(
+        actual.len(),
+        expected.len(),
+        "wrong number of constants for {:?}. got {:#?}",
+        input,
+        actual
+    );
+
+    for (i, constant) in expected.iter().enumerate() {
+        match (constant, &actual[i]) {
+            (Constant::Int(want), Object::Integer(got)) => {
+                assert_eq!(got, want, "constant {} has wrong value", i)
+            }
+            (Constant::Float(want), Object::Float(got)) => {
+                assert_eq!(got, want, "constant {} has wrong value", i)
+            }
+            (Constant::Str(want), Object::String(got)) => {
+                assert_eq!(got, want, "constant {} has wrong value", i)
+            }
+            (Constant::Function(want), Object::CompiledFunction(got)) => {
+                test_instructions(want, &got.instructions, input)
+            }
+            (Constant::StructType(want), Object::StructType(got)) => {
+                assert_eq!(got.to_string(), *want, "constant {} has wrong value", i)
+            }
+            (Constant::Matcher(want), Object::Matcher(got)) => {
+                assert_eq!(got.to_string(), *want, "constant {} has wrong value", i)
+            }
+            (_, got) => panic!("constant {} has wrong type. got {:#?}", i, got),
+        }
+    }
+}
+
+#[test]
+fn test_integer_arithmetic() {
+    run_compiler_tests(vec![
+        Test {
+            input: "1 + 2",
+            expected_constants: vec![Constant::Int(1), Constant::Int(2)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Add, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "1; 2",
+            expected_constants: vec![Constant::Int(1), Constant::Int(2)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "2 / 1",
+            expected_constants: vec![Constant::Int(2), Constant::Int(1)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Div, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "-1",
+            expected_constants: vec![Constant::Int(1)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Minus, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_boolean_expressions() {
+    run_compiler_tests(vec![
+        Test {
+            input: "true",
+            expected_constants: vec![],
+            expected_instructions: vec![make(Opcode::True, &[]), make(Opcode::Pop, &[])],
+        },
+        Test {
+            input: "1 < 2",
+            expected_constants: vec![Constant::Int(2), Constant::Int(1)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::GreaterThan, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "1 >= 2",
+            expected_constants: vec![Constant::Int(1), Constant::Int(2)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::GreaterEqual, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "1 <= 2",
+            expected_constants: vec![Constant::Int(2), Constant::Int(1)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::GreaterEqual, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "true != false",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                make(Opcode::True, &[]),
+                make(Opcode::False, &[]),
+                make(Opcode::NotEqual, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "!true",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                make(Opcode::True, &[]),
+                make(Opcode::Bang, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_conditionals() {
+    run_compiler_tests(vec![
+        Test {
+            input: "if (true) { 10 }; 3333;",
+            expected_constants: vec![Constant::Int(10), Constant::Int(3333)],
+            expected_instructions: vec![
+                // 0000
+                make(Opcode::True, &[]),
+                // 0001
+                make(Opcode::JumpNotTruthy, &[10]),
+                // 0004
+                make(Opcode::Constant, &[0]),
+                // 0007
+                make(Opcode::Jump, &[11]),
+                // 0010
+                make(Opcode::Null, &[]),
+                // 0011
+                make(Opcode::Pop, &[]),
+                // 0012
+                make(Opcode::Constant, &[1]),
+                // 0015
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "if (true) { 10 } else { 20 }; 3333;",
+            expected_constants: vec![Constant::Int(10), Constant::Int(20), Constant::Int(3333)],
+            expected_instructions: vec![
+                // 0000
+                make(Opcode::True, &[]),
+                // 0001
+                make(Opcode::JumpNotTruthy, &[10]),
+                // 0004
+                make(Opcode::Constant, &[0]),
+                // 0007
+                make(Opcode::Jump, &[13]),
+                // 0010
+                make(Opcode::Constant, &[1]),
+                // 0013
+                make(Opcode::Pop, &[]),
+                // 0014
+                make(Opcode::Constant, &[2]),
+                // 0017
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "1; if (true) { }",
+            expected_constants: vec![Constant::Int(1)],
+            expected_instructions: vec![
+                // 0000
+                make(Opcode::Constant, &[0]),
+                // 0003
+                make(Opcode::Pop, &[]),
+                // 0004
+                make(Opcode::True, &[]),
+                // 0005
+                make(Opcode::JumpNotTruthy, &[12]),
+                // 0008
+                make(Opcode::Null, &[]),
+                // 0009
+                make(Opcode::Jump, &[13]),
+                // 0012
+                make(Opcode::Null, &[]),
+                // 0013
+                make(Opcode::Pop, &[]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_string_expressions() {
+    run_compiler_tests(vec![
+        Test {
+            input: r#""monkey""#,
+            expected_constants: vec![Constant::Str("monkey")],
+            expected_instructions: vec![make(Opcode::Constant, &[0]), make(Opcode::Pop, &[])],
+        },
+        Test {
+            input: r#""mon" + "key""#,
+            expected_constants: vec![Constant::Str("mon"), Constant::Str("key")],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Add, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_array_literals() {
+    run_compiler_tests(vec![
+        Test {
+            input: "[]",
+            expected_constants: vec![],
+            expected_instructions: vec![make(Opcode::Array, &[0]), make(Opcode::Pop, &[])],
+        },
+        Test {
+            input: "[1, 2 + 3][0]",
+            expected_constants: vec![
+                Constant::Int(1),
+                Constant::Int(2),
+                Constant::Int(3),
+                Constant::Int(0),
+            ],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Constant, &[2]),
+                make(Opcode::Add, &[]),
+                make(Opcode::Array, &[2]),
+                make(Opcode::Constant, &[3]),
+                make(Opcode::Index, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_hash_literals() {
+    run_compiler_tests(vec![
+        Test {
+            input: "{}",
+            expected_constants: vec![],
+            expected_instructions: vec![make(Opcode::Hash, &[0]), make(Opcode::Pop, &[])],
+        },
+        Test {
+            input: "{1: 2, 3: 4 * 5}[1]",
+            expected_constants: vec![
+                Constant::Int(1),
+                Constant::Int(2),
+                Constant::Int(3),
+                Constant::Int(4),
+                Constant::Int(5),
+                Constant::Int(1),
+            ],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Constant, &[2]),
+                make(Opcode::Constant, &[3]),
+                make(Opcode::Constant, &[4]),
+                make(Opcode::Mul, &[]),
+                make(Opcode::Hash, &[4]),
+                make(Opcode::Constant, &[5]),
+                make(Opcode::Index, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_float_literals() {
+    run_compiler_tests(vec![Test {
+        input: "1.5 + 2",
+        expected_constants: vec![Constant::Float(1.5), Constant::Int(2)],
+        expected_instructions: vec![
+            make(Opcode::Constant, &[0]),
+            make(Opcode::Constant, &[1]),
+            make(Opcode::Add, &[]),
+            make(Opcode::Pop, &[]),
+        ],
+    }]);
+}
+
+#[test]
+fn test_optional_operators() {
+    run_compiler_tests(vec![
+        Test {
+            input: "null ?? 1",
+            expected_constants: vec![Constant::Int(1)],
+            expected_instructions: vec![
+                make(Opcode::Null, &[]),         // 0000
+                make(Opcode::JumpNotNull, &[7]), // 0001
+                make(Opcode::Constant, &[0]),    // 0004
+                make(Opcode::Pop, &[]),          // 0007
+            ],
+        },
+        Test {
+            input: "true && false",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                make(Opcode::True, &[]),       // 0000
+                make(Opcode::JumpFalsy, &[5]), // 0001
+                make(Opcode::False, &[]),      // 0004
+                make(Opcode::Pop, &[]),        // 0005
+            ],
+        },
+        Test {
+            input: "false || true",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                make(Opcode::False, &[]),       // 0000
+                make(Opcode::JumpTruthy, &[5]), // 0001
+                make(Opcode::True, &[]),        // 0004
+                make(Opcode::Pop, &[]),         // 0005
+            ],
+        },
+        Test {
+            input: "[1]?[0]",
+            expected_constants: vec![Constant::Int(1), Constant::Int(0)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),  // 0000
+                make(Opcode::Array, &[1]),     // 0003
+                make(Opcode::JumpNull, &[13]), // 0006
+                make(Opcode::Constant, &[1]),  // 0009
+                make(Opcode::Index, &[]),      // 0012
+                make(Opcode::Pop, &[]),        // 0013
+            ],
+        },
+        Test {
+            input: "null?.name",
+            expected_constants: vec![Constant::Str("name")],
+            expected_instructions: vec![
+                make(Opcode::Null, &[]),      // 0000
+                make(Opcode::JumpNull, &[8]), // 0001
+                make(Opcode::Constant, &[0]), // 0004
+                make(Opcode::Member, &[]),    // 0007
+                make(Opcode::Pop, &[]),       // 0008
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_ranges() {
+    run_compiler_tests(vec![
+        Test {
+            input: "1..2",
+            expected_constants: vec![Constant::Int(1), Constant::Int(2)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Range, &[code::RANGE_START | code::RANGE_END]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "..=2",
+            expected_constants: vec![Constant::Int(2)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Range, &[code::RANGE_END | code::RANGE_INCLUSIVE]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "[1][0..]",
+            expected_constants: vec![Constant::Int(1), Constant::Int(0)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Array, &[1]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Range, &[code::RANGE_START]),
+                make(Opcode::Index, &[]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_tuples_and_destructuring() {
+    run_compiler_tests(vec![
+        Test {
+            input: "(1, 2)",
+            expected_constants: vec![Constant::Int(1), Constant::Int(2)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Tuple, &[2]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "let (a, _) = (1, 2);",
+            expected_constants: vec![Constant::Int(1), Constant::Int(2)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Tuple, &[2]),
+                make(Opcode::Unpack, &[2, 0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "let [a, ...b] = [];",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                make(Opcode::Array, &[0]),
+                make(Opcode::Unpack, &[1, code::UNPACK_ARRAY | code::UNPACK_REST]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::SetGlobal, &[1]),
+            ],
+        },
+        Test {
+            input: "let ((a, b), c) = ();",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                make(Opcode::Tuple, &[0]),
+                make(Opcode::Unpack, &[2, 0]),
+                make(Opcode::Unpack, &[2, 0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::SetGlobal, &[1]),
+                make(Opcode::SetGlobal, &[2]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_arithmetic_and_bitwise_operators() {
+    let tests = [
+        ("1 % 2", Opcode::Mod),
+        ("1 ** 2", Opcode::Pow),
+        ("1 & 2", Opcode::BitAnd),
+        ("1 | 2", Opcode::BitOr),
+        ("1 ^ 2", Opcode::BitXor),
+        ("1 << 2", Opcode::ShiftLeft),
+        ("1 >> 2", Opcode::ShiftRight),
+    ];
+
+    run_compiler_tests(
+        tests
+            .into_iter()
+            .map(|(input, op)| Test {
+                input,
+                expected_constants: vec![Constant::Int(1), Constant::Int(2)],
+                expected_instructions: vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::Constant, &[1]),
+                    make(op, &[]),
+                    make(Opcode::Pop, &[]),
+                ],
+            })
+            .collect(),
+    );
+
+    run_compiler_tests(vec![Test {
+        input: "~1",
+        expected_constants: vec![Constant::Int(1)],
+        expected_instructions: vec![
+            make(Opcode::Constant, &[0]),
+            make(Opcode::BitNot, &[]),
+            make(Opcode::Pop, &[]),
+        ],
+    }]);
+}
+
+#[test]
+fn test_structs() {
+    run_compiler_tests(vec![Test {
+        input: "struct Point { x, y } Point { x: 1, y: 2 }.x",
+        expected_constants: vec![
+            Constant::StructType("struct Point { x, y }"),
+            Constant::Str("x"),
+            Constant::Int(1),
+            Constant::Str("y"),
+            Constant::Int(2),
+            Constant::Str("x"),
+        ],
+        expected_instructions: vec![
+            make(Opcode::Constant, &[0]),
+            make(Opcode::SetGlobal, &[0]),
+            make(Opcode::GetGlobal, &[0]),
+            make(Opcode::Constant, &[1]),
+            make(Opcode::Constant, &[2]),
+            make(Opcode::Constant, &[3]),
+            make(Opcode::Constant, &[4]),
+            make(Opcode::Struct, &[4]),
+            make(Opcode::Constant, &[5]),
+            make(Opcode::Member, &[]),
+            make(Opcode::Pop, &[]),
+        ],
+    }]);
+}
+
+#[test]
+fn test_global_let_statements() {
+    run_compiler_tests(vec![
+        Test {
+            input: "let one = 1; let two = 2;",
+            expected_constants: vec![Constant::Int(1), Constant::Int(2)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::SetGlobal, &[1]),
+            ],
+        },
+        Test {
+            input: "let one = 1; let two = one; two;",
+            expected_constants: vec![Constant::Int(1)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::SetGlobal, &[1]),
+                make(Opcode::GetGlobal, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_functions() {
+    run_compiler_tests(vec![
+        Test {
+            input: "fn() { return 5 + 10 }",
+            expected_constants: vec![
+                Constant::Int(5),
+                Constant::Int(10),
+                Constant::Function(vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::Constant, &[1]),
+                    make(Opcode::Add, &[]),
+                    make(Opcode::ReturnValue, &[]),
+                ]),
+            ],
+            expected_instructions: vec![make(Opcode::Closure, &[2, 0]), make(Opcode::Pop, &[])],
+        },
+        Test {
+            input: "fn() { 1; 2 }",
+            expected_constants: vec![
+                Constant::Int(1),
+                Constant::Int(2),
+                Constant::Function(vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::Pop, &[]),
+                    make(Opcode::Constant, &[1]),
+                    make(Opcode::ReturnValue, &[]),
+                ]),
+            ],
+            expected_instructions: vec![make(Opcode::Closure, &[2, 0]), make(Opcode::Pop, &[])],
+        },
+        Test {
+            input: "fn() { }",
+            expected_constants: vec![Constant::Function(vec![make(Opcode::Return, &[])])],
+            expected_instructions: vec![make(Opcode::Closure, &[0, 0]), make(Opcode::Pop, &[])],
+        },
+    ]);
+}
+
+#[test]
+fn test_function_calls() {
+    run_compiler_tests(vec![
+        Test {
+            input: "let one_arg = fn(a) { a }; one_arg(24);",
+            expected_constants: vec![
+                Constant::Function(vec![
+                    make(Opcode::GetLocal, &[0]),
+                    make(Opcode::ReturnValue, &[]),
+                ]),
+                Constant::Int(24),
+            ],
+            expected_instructions: vec![
+                make(Opcode::Closure, &[0, 0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Call, &[1]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "fn() { let num = 55; num }",
+            expected_constants: vec![
+                Constant::Int(55),
+                Constant::Function(vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::SetLocal, &[0]),
+                    make(Opcode::GetLocal, &[0]),
+                    make(Opcode::ReturnValue, &[]),
+                ]),
+            ],
+            expected_instructions: vec![make(Opcode::Closure, &[1, 0]), make(Opcode::Pop, &[])],
+        },
+    ]);
+}
+
+#[test]
+fn test_closures() {
+    run_compiler_tests(vec![Test {
+        input: "fn(a) { fn(b) { a + b } }",
+        expected_constants: vec![
+            Constant::Function(vec![
+                make(Opcode::GetFree, &[0]),
+                make(Opcode::GetLocal, &[0]),
+                make(Opcode::Add, &[]),
+                make(Opcode::ReturnValue, &[]),
+            ]),
+            Constant::Function(vec![
+                make(Opcode::GetLocal, &[0]),
+                make(Opcode::Closure, &[0, 1]),
+                make(Opcode::ReturnValue, &[]),
+            ]),
+        ],
+        expected_instructions: vec![make(Opcode::Closure, &[1, 0]), make(Opcode::Pop, &[])],
+    }]);
+}
+
+#[test]
+fn test_recursive_functions() {
+    run_compiler_tests(vec![Test {
+        input: "let wrapper = fn() { let count_down = fn(x) { count_down(x - 1); }; count_down(1); }; wrapper();",
+        expected_constants: vec![
+            Constant::Int(1),
+            Constant::Function(vec![
+                make(Opcode::CurrentClosure, &[]),
+                make(Opcode::GetLocal, &[0]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Sub, &[]),
+                make(Opcode::TailCall, &[1]),
+                make(Opcode::ReturnValue, &[]),
+            ]),
+            Constant::Int(1),
+            Constant::Function(vec![
+                make(Opcode::Closure, &[1, 0]),
+                make(Opcode::SetLocal, &[0]),
+                make(Opcode::GetLocal, &[0]),
+                make(Opcode::Constant, &[2]),
+                make(Opcode::TailCall, &[1]),
+                make(Opcode::ReturnValue, &[]),
+            ]),
+        ],
+        expected_instructions: vec![
+            make(Opcode::Closure, &[3, 0]),
+            make(Opcode::SetGlobal, &[0]),
+            make(Opcode::GetGlobal, &[0]),
+            make(Opcode::Call, &[0]),
+            make(Opcode::Pop, &[]),
+        ],
+    }]);
+}
+
+#[test]
+fn test_undefined_identifier() {
+    let l = Box::new(lexer::Lexer::new("let a = b;".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+
+    let mut compiler = Compiler::new();
+
+    assert_eq!(
+        compiler.compile(&program),
+        Err("identifier not found: b".to_string())
+    );
+}
+
+#[test]
+fn test_operand_limits() {
+    // Identifiers cannot contain digits, so the locals are named vaa, vab, ...
+    let name = |i: usize| {
+        format!(
+            "v{}{}",
+            (b'a' + (i / 26) as u8) as char,
+            (b'a' + (i % 26) as u8) as char
+        )
+    };
+    let locals = (0..300)
+        .map(|i| format!("let {} = {};", name(i), i))
+        .collect::<String>();
+    let constants = "1;".repeat(65537);
+
+    let tests = [
+        (
+            format!("fn() {{ {} {} }}", locals, name(299)),
+            "operand 256 of OpSetLocal is larger than the limit of 255",
+        ),
+        (
+            constants,
+            "operand 65536 of OpConstant is larger than the limit of 65535",
+        ),
+        (
+            format!("if (true) {{ {} }}", "[];".repeat(20000)),
+            "operand 80006 of OpJumpNotTruthy is larger than the limit of 65535",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let program = parser::Parser::new(l).parse_program().unwrap();
+
+        let mut compiler = Compiler::new();
+        assert_eq!(compiler.compile(&program), Err(expected.to_string()));
+    }
+}
+
+#[test]
+fn test_resolve_nested_symbols() {
+    let mut global = SymbolTable::new();
+    global.define("a");
+
+    let mut first_local = SymbolTable::new_enclosed(global);
+    first_local.define("c");
+
+    let mut second_local = SymbolTable::new_enclosed(first_local);
+    second_local.define("e");
+
+    let tests = [
+        ("a", SymbolScope::Global, 0),
+        ("c", SymbolScope::Free, 0),
+        ("e", SymbolScope::Local, 0),
+    ];
+
+    for (name, scope, index) in tests.iter() {
+        let expected = Symbol {
+            name: name.to_string(),
+            scope: *scope,
+            index: *index,
+            mutable: false,
+            cell: false,
+        };
+
+        assert_eq!(second_local.resolve(name), Some(expected));
+    }
+
+    assert_eq!(second_local.resolve("unknown"), None);
+    assert_eq!(second_local.free_symbols.len(), 1);
+    assert_eq!(second_local.free_symbols[0].scope, SymbolScope::Local);
+}
+
+#[test]
+fn test_tail_calls() {
+    run_compiler_tests(vec![
+        Test {
+            input: "fn(f) { 1 + f(); f() }",
+            expected_constants: vec![
+                Constant::Int(1),
+                Constant::Function(vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::GetLocal, &[0]),
+                    make(Opcode::Call, &[0]),
+                    make(Opcode::Add, &[]),
+                    make(Opcode::Pop, &[]),
+                    make(Opcode::GetLocal, &[0]),
+                    make(Opcode::TailCall, &[0]),
+                    make(Opcode::ReturnValue, &[]),
+                ]),
+            ],
+            expected_instructions: vec![make(Opcode::Closure, &[1, 0]), make(Opcode::Pop, &[])],
+        },
+        Test {
+            input: "fn(f) { if (f) { f() } else { return f(); } }",
+            expected_constants: vec![Constant::Function(vec![
+                // 0000
+                make(Opcode::GetLocal, &[0]),
+                // 0002
+                make(Opcode::JumpNotTruthy, &[12]),
+                // 0005
+                make(Opcode::GetLocal, &[0]),
+                // 0007
+                make(Opcode::TailCall, &[0]),
+                // 0009
+                make(Opcode::Jump, &[18]),
+                // 0012
+                make(Opcode::GetLocal, &[0]),
+                // 0014
+                make(Opcode::TailCall, &[0]),
+                // 0016
+                make(Opcode::ReturnValue, &[]),
+                // 0017
+                make(Opcode::Null, &[]),
+                // 0018
+                make(Opcode::ReturnValue, &[]),
+            ])],
+            expected_instructions: vec![make(Opcode::Closure, &[0, 0]), make(Opcode::Pop, &[])],
+        },
+        Test {
+            input: "let f = fn() { 1 }; f(); return f();",
+            expected_constants: vec![
+                Constant::Int(1),
+                Constant::Function(vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::ReturnValue, &[]),
+                ]),
+            ],
+            expected_instructions: vec![
+                make(Opcode::Closure, &[1, 0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Call, &[0]),
+                make(Opcode::Pop, &[]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Call, &[0]),
+                make(Opcode::ReturnValue, &[]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_native_functions() {
+    let mut natives = NativeRegistry::new();
+    natives.register("len", 1, |_| Ok(Object::Null));
+    natives.register("puts", 1, |_| Ok(Object::Null));
+
+    let l = Box::new(lexer::Lexer::new(
+        "puts(len(1)); fn() { len }; let len = 2; len".to_string(),
+    ));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+
+    let mut compiler = Compiler::new();
+    compiler.register_natives(&natives);
+    compiler.compile(&program).unwrap();
+
+    let bytecode = compiler.bytecode();
+    let input = "natives";
+
+    test_instructions(
+        &[
+            make(Opcode::GetNative, &[1]),
+            make(Opcode::GetNative, &[0]),
+            make(Opcode::Constant, &[0]),
+            make(Opcode::Call, &[1]),
+            make(Opcode::Call, &[1]),
+            make(Opcode::Pop, &[]),
+            make(Opcode::Closure, &[1, 0]),
+            make(Opcode::Pop, &[]),
+            make(Opcode::Constant, &[2]),
+            make(Opcode::SetGlobal, &[0]),
+            make(Opcode::GetGlobal, &[0]),
+            make(Opcode::Pop, &[]),
+        ],
+        &bytecode.instructions,
+        input,
+    );
+    test_constants(
+        &[
+            Constant::Int(1),
+            Constant::Function(vec![
+                make(Opcode::GetNative, &[0]),
+                make(Opcode::ReturnValue, &[]),
+            ]),
+            Constant::Int(2),
+        ],
+        &bytecode.constants,
+        input,
+    );
+}
+
+#[test]
+fn test_assignment() {
+    run_compiler_tests(vec![
+        Test {
+            input: "let mut x = 1; x += 2;",
+            expected_constants: vec![Constant::Int(1), Constant::Int(2)],
+            expected_instructions: vec![
+                make(Opcode::Constant, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Add, &[]),
+                make(Opcode::Dup, &[]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "let mut a = []; a[0] = 1;",
+            expected_constants: vec![Constant::Int(0), Constant::Int(1)],
+            expected_instructions: vec![
+                make(Opcode::Array, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Dup, &[]),
+                make(Opcode::Bury, &[3]),
+                make(Opcode::Bury, &[2]),
+                make(Opcode::SetIndex, &[]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "let mut p = {}; p.x = 1;",
+            expected_constants: vec![Constant::Str("x"), Constant::Int(1)],
+            expected_instructions: vec![
+                make(Opcode::Hash, &[0]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::GetGlobal, &[0]),
+                make(Opcode::Constant, &[0]),
+                make(Opcode::Constant, &[1]),
+                make(Opcode::Dup, &[]),
+                make(Opcode::Bury, &[3]),
+                make(Opcode::Bury, &[2]),
+                make(Opcode::SetMember, &[]),
+                make(Opcode::SetGlobal, &[0]),
+                make(Opcode::Pop, &[]),
+            ],
+        },
+        Test {
+            input: "fn() { let mut n = 1; n = 2 }",
+            expected_constants: vec![
+                Constant::Int(1),
+                Constant::Int(2),
+                Constant::Function(vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::Cell, &[]),
+                    make(Opcode::SetLocal, &[0]),
+                    make(Opcode::Constant, &[1]),
+                    make(Opcode::Dup, &[]),
+                    make(Opcode::GetLocal, &[0]),
+                    make(Opcode::SetCell, &[]),
+                    make(Opcode::ReturnValue, &[]),
+                ]),
+            ],
+            expected_instructions: vec![make(Opcode::Closure, &[2, 0]), make(Opcode::Pop, &[])],
+        },
+        Test {
+            input: "fn() { let mut n = 1; fn() { n = n + 1 } }",
+            expected_constants: vec![
+                Constant::Int(1),
+                Constant::Int(1),
+                Constant::Function(vec![
+                    make(Opcode::GetFree, &[0]),
+                    make(Opcode::Deref, &[]),
+                    make(Opcode::Constant, &[1]),
+                    make(Opcode::Add, &[]),
+                    make(Opcode::Dup, &[]),
+                    make(Opcode::GetFree, &[0]),
+                    make(Opcode::SetCell, &[]),
+                    make(Opcode::ReturnValue, &[]),
+                ]),
+                Constant::Function(vec![
+                    make(Opcode::Constant, &[0]),
+                    make(Opcode::Cell, &[]),
+                    make(Opcode::SetLocal, &[0]),
+                    make(Opcode::GetLocal, &[0]),
+                    make(Opcode::Closure, &[2, 1]),
+                    make(Opcode::ReturnValue, &[]),
+                ]),
+            ],
+            expected_instructions: vec![make(Opcode::Closure, &[3, 0]), make(Opcode::Pop, &[])],
+        },
+    ]);
+
+    let tests = [
+        (
+            "let x = 1; x = 2;",
+            "cannot assign to immutable variable x at 1:12",
+        ),
+        ("y = 2;", "cannot assign to undefined variable y at 1:1"),
+        (
+            "let f = fn() { f = 1 };",
+            "cannot assign to immutable variable f at 1:16",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let program = parser::Parser::new(l).parse_program().unwrap();
+
+        let mut compiler = Compiler::new();
+        assert_eq!(
+            compiler.compile(&program),
+            Err(expected.to_string()),
+            "{:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn test_while_loops() {
+    run_compiler_tests(vec![
+        Test {
+            input: "while (true) { 1; }",
+            expected_constants: vec![Constant::Int(1)],
+            expected_instructions: vec![
+                // 0000
+                make(Opcode::True, &[]),
+                // 0001
+                make(Opcode::JumpNotTruthy, &[11]),
+                // 0004
+                make(Opcode::Constant, &[0]),
+                // 0007
+                make(Opcode::Pop, &[]),
+                // 0008
+                make(Opcode::Jump, &[0]),
+            ],
+        },
+        Test {
+            input: "'outer: while (true) { while (false) { break 'outer; continue; } break; }",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                // 0000
+                make(Opcode::True, &[]),
+                // 0001
+                make(Opcode::JumpNotTruthy, &[23]),
+                // 0004
+                make(Opcode::False, &[]),
+                // 0005
+                make(Opcode::JumpNotTruthy, &[17]),
+                // 0008
+                make(Opcode::Jump, &[23]),
+                // 0011
+                make(Opcode::Jump, &[4]),
+                // 0014
+                make(Opcode::Jump, &[4]),
+                // 0017
+                make(Opcode::Jump, &[23]),
+                // 0020
+                make(Opcode::Jump, &[0]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_for_loops() {
+    run_compiler_tests(vec![
+        Test {
+            input: "for (x in [1]) { x; }",
+            expected_constants: vec![Constant::Int(1)],
+            expected_instructions: vec![
+                // 0000
+                make(Opcode::Constant, &[0]),
+                // 0003
+                make(Opcode::Array, &[1]),
+                // 0006
+                make(Opcode::Iter, &[]),
+                // 0007
+                make(Opcode::IterNext, &[21, 1]),
+                // 0011
+                make(Opcode::SetGlobal, &[0]),
+                // 0014
+                make(Opcode::GetGlobal, &[0]),
+                // 0017
+                make(Opcode::Pop, &[]),
+                // 0018
+                make(Opcode::Jump, &[7]),
+            ],
+        },
+        Test {
+            input: "'o: for (x in []) { for (y in []) { break 'o; continue 'o; } }",
+            expected_constants: vec![],
+            expected_instructions: vec![
+                // 0000
+                make(Opcode::Array, &[0]),
+                // 0003
+                make(Opcode::Iter, &[]),
+                // 0004
+                make(Opcode::IterNext, &[37, 1]),
+                // 0008
+                make(Opcode::SetGlobal, &[0]),
+                // 0011
+                make(Opcode::Array, &[0]),
+                // 0014
+                make(Opcode::Iter, &[]),
+                // 0015
+                make(Opcode::IterNext, &[34, 1]),
+                // 0019
+                make(Opcode::SetGlobal, &[1]),
+                // 0022
+                make(Opcode::Pop, &[]),
+                // 0023
+                make(Opcode::Pop, &[]),
+                // 0024
+                make(Opcode::Jump, &[37]),
+                // 0027
+                make(Opcode::Pop, &[]),
+                // 0028
+                make(Opcode::Jump, &[4]),
+                // 0031
+                make(Opcode::Jump, &[15]),
+                // 0034
+                make(Opcode::Jump, &[4]),
+            ],
+        },
+        Test {
+            input: "for (x in []) { fn() { x }; }",
+            expected_constants: vec![Constant::Function(vec![
+                make(Opcode::GetFree, &[0]),
+                make(Opcode::ReturnValue, &[]),
+            ])],
+            expected_instructions: vec![
+                // 0000
+                make(Opcode::Array, &[0]),
+                // 0003
+                make(Opcode::Iter, &[]),
+                // 0004
+                make(Opcode::IterNext, &[22, 1]),
+                // 0008
+                make(Opcode::SetGlobal, &[0]),
+                // 0011
+                make(Opcode::GetGlobal, &[0]),
+                // 0014
+                make(Opcode::Closure, &[0, 1]),
+                // 0018
+                make(Opcode::Pop, &[]),
+                // 0019
+                make(Opcode::Jump, &[4]),
+            ],
+        },
+    ]);
+}
+
+#[test]
+fn test_match_expressions() {
+    run_compiler_tests(vec![Test {
+        input: "match (1) { 1 => 2, n if n => n }",
+        expected_constants: vec![
+            Constant::Int(1),
+            Constant::Matcher("1"),
+            Constant::Int(2),
+            Constant::Matcher("n"),
+        ],
+        expected_instructions: vec![
+            // 0000
+            make(Opcode::Constant, &[0]),
+            // 0003
+            make(Opcode::Match, &[15, 1]),
+            // 0008
+            make(Opcode::Pop, &[]),
+            // 0009
+            make(Opcode::Constant, &[2]),
+            // 0012
+            make(Opcode::Jump, &[37]),
+            // 0015
+            make(Opcode::Match, &[36, 3]),
+            // 0020
+            make(Opcode::SetGlobal, &[0]),
+            // 0023
+            make(Opcode::GetGlobal, &[0]),
+            // 0026
+            make(Opcode::JumpNotTruthy, &[36]),
+            // 0029
+            make(Opcode::Pop, &[]),
+            // 0030
+            make(Opcode::GetGlobal, &[0]),
+            // 0033
+            make(Opcode::Jump, &[37]),
+            // 0036
+            make(Opcode::NoMatch, &[]),
+            // 0037
+            make(Opcode::Pop, &[]),
+        ],
+    }]);
+}
@@ -0,0 +1,135 @@
+use rust_monkey::code::{make, Opcode};
+use rust_monkey::compiler::{Bytecode, Compiler};
+use rust_monkey::disassembler;
+use rust_monkey::lexer;
+use rust_monkey::mkc;
+use rust_monkey::parser;
+use rust_monkey::token::TokenType;
+
+fn compile(input: &str) -> Bytecode {
+    let l = Box::new(lexer::Lexer::new(input.to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+
+    let mut compiler = Compiler::new();
+    compiler.compile(&program).unwrap();
+    compiler.bytecode()
+}
+
+#[test]
+fn test_disassemble_instructions() {
+    let instructions = [
+        make(Opcode::Add, &[]),
+        make(Opcode::GetLocal, &[1]),
+        make(Opcode::Constant, &[2]),
+        make(Opcode::Constant, &[65535]),
+        make(Opcode::Closure, &[65535, 255]),
+    ]
+    .concat();
+
+    let expected = "0000 OpAdd
+0001 OpGetLocal 1
+0003 OpConstant 2
+0006 OpConstant 65535
+0009 OpClosure 65535 255
+";
+
+    assert_eq!(
+        disassembler::disassemble_instructions(&instructions),
+        expected
+    );
+}
+
+#[test]
+fn test_disassemble_program() {
+    let bytecode = compile("let add = fn(a, b) { a + b }; add(1, 2);");
+
+    let expected = "\
+0000 OpClosure 0 0          ; <compiled fn/2> locals=2  | let
+    0000 OpGetLocal 0                                   | a
+    0002 OpGetLocal 1                                   | b
+    0004 OpAdd                                          | +
+    0005 OpReturnValue                                  | a
+0004 OpSetGlobal 0                                      | let
+0007 OpGetGlobal 0                                      | add
+0010 OpConstant 1           ; 1                         | 1
+0013 OpConstant 2           ; 2                         | 2
+0016 OpCall 2                                           | )
+0018 OpPop                                              | add
+";
+
+    assert_eq!(disassembler::disassemble(&bytecode), expected);
+}
+
+#[test]
+fn test_nested_functions_print_recursively() {
+    let bytecode = compile("fn(a) { fn(b) { a + b } }");
+    let listing = disassembler::disassemble(&bytecode);
+    let lines: Vec<&str> = listing.lines().collect();
+
+    assert!(lines[0].starts_with("0000 OpClosure 1 0"), "{}", listing);
+    assert!(lines[1].starts_with("    0000 OpGetLocal 0"), "{}", listing);
+    assert!(
+        lines[2].starts_with("    0002 OpClosure 0 1"),
+        "{}",
+        listing
+    );
+    assert!(
+        lines[3].starts_with("        0000 OpGetFree 0"),
+        "{}",
+        listing
+    );
+    assert!(
+        lines[4].starts_with("        0002 OpGetLocal 0"),
+        "{}",
+        listing
+    );
+    assert!(lines[5].starts_with("        0004 OpAdd"), "{}", listing);
+}
+
+#[test]
+fn test_instructions_map_to_tokens() {
+    let bytecode = compile("1 + 2 * 3");
+    let decoded = disassembler::decode(&bytecode.instructions, &bytecode.source_map).unwrap();
+
+    let tests = [
+        (Opcode::Constant, TokenType::Int, "1"),
+        (Opcode::Constant, TokenType::Int, "2"),
+        (Opcode::Constant, TokenType::Int, "3"),
+        (Opcode::Mul, TokenType::Asterisk, "*"),
+        (Opcode::Add, TokenType::Plus, "+"),
+        (Opcode::Pop, TokenType::Int, "1"),
+    ];
+
+    assert_eq!(decoded.len(), tests.len());
+
+    for (ins, (opcode, token_type, literal)) in decoded.iter().zip(tests.iter()) {
+        assert_eq!(ins.opcode, *opcode);
+
+        let token = ins.token.as_ref().unwrap();
+        assert_eq!(token.token_type, *token_type);
+        assert_eq!(token.literal, *literal);
+    }
+}
+
+#[test]
+fn test_loaded_programs_have_no_tokens() {
+    let bytes = mkc::encode(&compile("1 + 2")).unwrap();
+    let bytecode = mkc::decode(&bytes).unwrap();
+
+    let expected = "\
+0000 OpConstant 0           ; 1
+0003 OpConstant 1           ; 2
+0006 OpAdd
+0007 OpPop
+";
+
+    assert_eq!(disassembler::disassemble(&bytecode), expected);
+}
+
+#[test]
+fn test_decode_rejects_unknown_opcodes() {
+    assert_eq!(
+        disassembler::decode(&[255], &Default::default()),
+        Err("opcode 255 undefined".to_string())
+    );
+}
@@ -0,0 +1,1656 @@
+use rust_monkey::eval::Evaluator;
+use rust_monkey::lexer;
+use rust_monkey::object::{HashKey, Object};
+use rust_monkey::parser;
+
+fn test_eval(input: &str) -> Object {
+    let l = Box::new(lexer::Lexer::new(input.to_string()));
+    let mut p = parser::Parser::new(l);
+    let program = p.parse_program().unwrap();
+
+    let mut evaluator = Evaluator::new();
+    evaluator.eval_program(&program)
+}
+
+fn test_integer_object(obj: &Object, expected: i64) {
+    match obj {
+        Object::Integer(value) => assert_eq!(
+            *value, expected,
+            "object has wrong value. got {}, want {}",
+            value, expected
+        ),
+        _ => panic!("object is not Integer. got {:#?}", obj),
+    }
+}
+
+fn test_boolean_object(obj: &Object, expected: bool) {
+    match obj {
+        Object::Boolean(value) => assert_eq!(
+            *value, expected,
+            "object has wrong value. got {}, want {}",
+            value, expected
+        ),
+        _ => panic!("object is not Boolean. got {:#?}", obj),
+    }
+}
+
+fn test_null_object(obj: &Object) {
+    assert!(
+        matches!(obj, Object::Null),
+        "object is not Null. got {:#?}",
+        obj
+    );
+}
+
+#[test]
+fn test_eval_integer_expression() {
+    let tests = [
+        ("5", 5),
+        ("10", 10),
+        ("-5", -5),
+        ("-10", -10),
+        ("5 + 5 + 5 + 5 - 10", 10),
+        ("2 * 2 * 2 * 2 * 2", 32),
+        ("-50 + 100 + -50", 0),
+        ("5 * 2 + 10", 20),
+        ("5 + 2 * 10", 25),
+        ("20 + 2 * -10", 0),
+        ("50 / 2 * 2 + 10", 60),
+        ("2 * (5 + 10)", 30),
+        ("3 * 3 * 3 + 10", 37),
+        ("3 * (3 * 3) + 10", 37),
+        ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(&test_eval(input), *expected);
+    }
+}
+
+#[test]
+fn test_eval_boolean_expression() {
+    let tests = [
+        ("true", true),
+        ("false", false),
+        ("1 < 2", true),
+        ("1 > 2", false),
+        ("1 < 1", false),
+        ("1 > 1", false),
+        ("1 == 1", true),
+        ("1 != 1", false),
+        ("1 == 2", false),
+        ("1 != 2", true),
+        ("true == true", true),
+        ("false == false", true),
+        ("true == false", false),
+        ("true != false", true),
+        ("(1 < 2) == true", true),
+        ("(1 > 2) == true", false),
+        ("1 <= 2", true),
+        ("2 <= 2", true),
+        ("3 <= 2", false),
+        ("1 >= 2", false),
+        ("2 >= 2", true),
+        ("1.5 >= 1", true),
+        ("9223372036854775808 >= 9223372036854775807", true),
+        ("true && true", true),
+        ("true && false", false),
+        ("false || true", true),
+        ("false || false", false),
+        ("1 < 2 && 2 < 3", true),
+        ("1 > 2 || 2 > 3", false),
+        ("7 % 2 == 1 && 2 ** 3 == 8", true),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_boolean_object(&test_eval(input), *expected);
+    }
+}
+
+#[test]
+fn test_bang_operator() {
+    let tests = [
+        ("!true", false),
+        ("!false", true),
+        ("!5", false),
+        ("!!true", true),
+        ("!!false", false),
+        ("!!5", true),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_boolean_object(&test_eval(input), *expected);
+    }
+}
+
+#[test]
+fn test_if_else_expressions() {
+    let tests = [
+        ("if (true) { 10 }", Some(10)),
+        ("if (false) { 10 }", None),
+        ("if (1) { 10 }", Some(10)),
+        ("if (1 < 2) { 10 }", Some(10)),
+        ("if (1 > 2) { 10 }", None),
+        ("if (1 > 2) { 10 } else { 20 }", Some(20)),
+        ("if (1 < 2) { 10 } else { 20 }", Some(10)),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let evaluated = test_eval(input);
+        match expected {
+            Some(expected) => test_integer_object(&evaluated, *expected),
+            None => test_null_object(&evaluated),
+        }
+    }
+}
+
+#[test]
+fn test_return_statements() {
+    let tests = [
+        ("return 10;", 10),
+        ("return 10; 9;", 10),
+        ("return 2 * 5; 9;", 10),
+        ("9; return 2 * 5; 9;", 10),
+        (
+            r#"
+if (10 > 1) {
+  if (10 > 1) {
+    return 10;
+  }
+
+  return 1;
+}
+"#,
+            10,
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(&test_eval(input), *expected);
+    }
+}
+
+#[test]
+fn test_error_handling() {
+    let tests = [
+        ("5 + true;", "type mismatch: INTEGER + BOOLEAN"),
+        ("5 + true; 5;", "type mismatch: INTEGER + BOOLEAN"),
+        ("-true", "unknown operator: -BOOLEAN"),
+        ("true + false;", "unknown operator: BOOLEAN + BOOLEAN"),
+        ("5; true + false; 5", "unknown operator: BOOLEAN + BOOLEAN"),
+        (
+            "if (10 > 1) { true + false; }",
+            "unknown operator: BOOLEAN + BOOLEAN",
+        ),
+        ("foobar", "identifier not found: foobar"),
+        ("10 / 0", "division by zero"),
+        (
+            "let f = fn(x) { x }; f(1, 2)",
+            "wrong number of arguments: want=1, got=2",
+        ),
+        ("5(1)", "not a function: INTEGER"),
+        (r#""Hello" - "World""#, "unknown operator: STRING - STRING"),
+        (r#""a" + 1"#, "type mismatch: STRING + INTEGER"),
+        ("1[0]", "index operator not supported: INTEGER"),
+        (r#"[1][true]"#, "array index must be INTEGER, got BOOLEAN"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(
+                msg, *expected,
+                "wrong error message. expected {}, got {}",
+                expected, msg
+            ),
+            other => panic!("no error object returned. got {:#?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_string_expressions() {
+    let tests = [
+        (r#""Hello World!""#, "Hello World!"),
+        (r#""Hello" + " " + "World!""#, "Hello World!"),
+        (r#""tab\there\n""#, "tab\there\n"),
+        (
+            r#"let greet = fn(name) { "Hi, " + name }; greet("\u{1F600}")"#,
+            "Hi, \u{1F600}",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::String(value) => assert_eq!(value, *expected),
+            other => panic!("object is not String. got {:#?}", other),
+        }
+    }
+
+    let tests = [
+        (r#""a" == "a""#, true),
+        (r#""a" == "b""#, false),
+        (r#""a" != "b""#, true),
+        (r#""a" + "b" == "ab""#, true),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_boolean_object(&test_eval(input), *expected);
+    }
+}
+
+#[test]
+fn test_let_statements() {
+    let tests = [
+        ("let a = 5; a;", 5),
+        ("let a = 5 * 5; a;", 25),
+        ("let a = 5; let b = a; b;", 5),
+        ("let a = 5; let b = a; let c = a + b + 5; c;", 15),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(&test_eval(input), *expected);
+    }
+}
+
+#[test]
+fn test_function_object() {
+    let evaluated = test_eval("fn(x) { x + 2; };");
+
+    let function = match evaluated {
+        Object::Function(function) => function,
+        other => panic!("object is not Function. got {:#?}", other),
+    };
+
+    assert_eq!(
+        function.parameters.len(),
+        1,
+        "function has wrong parameters. got {:#?}",
+        function.parameters
+    );
+    assert_eq!(function.parameters[0].value, "x");
+    assert_eq!(function.body.to_string(), "(x + 2)");
+}
+
+#[test]
+fn test_function_application() {
+    let tests = [
+        ("let identity = fn(x) { x; }; identity(5);", 5),
+        ("let identity = fn(x) { return x; }; identity(5);", 5),
+        ("let double = fn(x) { x * 2; }; double(5);", 10),
+        ("let add = fn(x, y) { x + y; }; add(5, 5);", 10),
+        ("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));", 20),
+        ("fn(x) { x; }(5)", 5),
+        (
+            "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(5);",
+            120,
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(&test_eval(input), *expected);
+    }
+}
+
+#[test]
+fn test_closures() {
+    let tests = [
+        (
+            r#"
+let new_adder = fn(x) {
+  fn(y) { x + y };
+};
+
+let add_two = new_adder(2);
+add_two(2);
+"#,
+            4,
+        ),
+        (
+            r#"
+let add = fn(a) { fn(b) { fn(c) { a + b + c } } };
+add(1)(2)(3);
+"#,
+            6,
+        ),
+        (
+            r#"
+let curry = fn(f) { fn(a) { fn(b) { f(a, b) } } };
+let mul = fn(a, b) { a * b };
+let triple = curry(mul)(3);
+triple(5);
+"#,
+            15,
+        ),
+        (
+            r#"
+let apply_twice = fn(f, x) { f(f(x)) };
+let inc = fn(x) { x + 1 };
+apply_twice(inc, 5);
+"#,
+            7,
+        ),
+        (
+            "let x = 1; let f = fn() { x }; let g = fn(x) { f() }; g(2);",
+            1,
+        ),
+        ("let x = 1; let f = fn(x) { x }; f(10) + x;", 11),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(&test_eval(input), *expected);
+    }
+}
+
+/// The same programs as the VM's `test_mutable_captures`.
+#[test]
+fn test_mutable_captures() {
+    let tests = [
+        (
+            "let counter = fn() { let mut n = 0; fn() { n += 1 } }; let c = counter(); c(); c(); c()",
+            "3",
+        ),
+        (
+            "let f = fn() { let mut n = 1; let get = fn() { n }; n = 5; get() }; f()",
+            "5",
+        ),
+        (
+            "let pair = fn() { let mut n = 0; (fn() { n += 1 }, fn() { n }) }; let (inc, get) = pair(); inc(); inc(); get()",
+            "2",
+        ),
+        (
+            "let mut f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; let g = f; f = fn(n) { 99 }; g(1)",
+            "99",
+        ),
+        (
+            "let run = fn() { let mut f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; let g = f; f = fn(n) { 99 }; g(1) }; run()",
+            "99",
+        ),
+        (
+            "let run = fn() { let mut f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(10) }; run()",
+            "0",
+        ),
+        (
+            "let mut out = 0; for (i in 0..3) { let mut x = i; let add = fn() { x += 10 }; add(); out += x } out",
+            "33",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+}
+
+#[test]
+fn test_recursive_functions() {
+    let input = r#"
+let counter = fn(x) {
+  if (x > 100) {
+    return true;
+  } else {
+    let foobar = 9999;
+    counter(x + 1);
+  }
+};
+
+counter(0);
+"#;
+
+    test_boolean_object(&test_eval(input), true);
+
+    let input = r#"
+let make_counter = fn(limit) {
+  let count = fn(n) { if (n == limit) { n } else { count(n + 1) } };
+  count;
+};
+
+make_counter(50)(0);
+"#;
+
+    test_integer_object(&test_eval(input), 50);
+}
+
+#[test]
+fn test_environment_persists_between_programs() {
+    let mut evaluator = Evaluator::new();
+
+    for input in ["let a = 5;", "let add = fn(b) { a + b };"] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let program = parser::Parser::new(l).parse_program().unwrap();
+        evaluator.eval_program(&program);
+    }
+
+    let l = Box::new(lexer::Lexer::new("add(10)".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+
+    test_integer_object(&evaluator.eval_program(&program), 15);
+    test_integer_object(&evaluator.environment().borrow().get("a").unwrap(), 5);
+}
+
+#[test]
+fn test_tail_calls() {
+    let tests = [
+        (
+            "let loop = fn(n) { if (n == 0) { 0 } else { loop(n - 1) } }; loop(1000000);",
+            0,
+        ),
+        (
+            "let sum = fn(n, acc) { if (n == 0) { return acc; } sum(n - 1, acc + n) }; sum(100000, 0);",
+            5000050000,
+        ),
+        (
+            "let count = fn(n) { if (n == 0) { return 0; } return count(n - 1); }; count(100000);",
+            0,
+        ),
+        (
+            "let even = fn(n) { if (n == 0) { true } else { odd(n - 1) } }; let odd = fn(n) { if (n == 0) { false } else { even(n - 1) } }; if (even(100001)) { 1 } else { 0 };",
+            0,
+        ),
+        ("let f = fn(x) { x * 2 }; return f(21);", 42),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(&test_eval(input), *expected);
+    }
+}
+
+fn eval_with(evaluator: &mut Evaluator, input: &str) -> Object {
+    let l = Box::new(lexer::Lexer::new(input.to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+
+    evaluator.eval_program(&program)
+}
+
+#[test]
+fn test_collects_recursive_closures() {
+    let mut evaluator = Evaluator::new();
+    let baseline = evaluator.heap_stats().live_objects;
+
+    let input = r#"
+let make = fn() { let f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f };
+let churn = fn(n) { if (n == 0) { 0 } else { make(); churn(n - 1) } };
+churn(100);
+"#;
+    test_integer_object(&eval_with(&mut evaluator, input), 0);
+
+    let before = evaluator.heap_stats();
+    let reclaimed = evaluator.collect_garbage();
+    let after = evaluator.heap_stats();
+
+    assert!(
+        reclaimed >= 100,
+        "expected 100 cycles freed, got {}",
+        reclaimed
+    );
+    assert_eq!(after.collections, before.collections + 1);
+    assert_eq!(after.reclaimed, before.reclaimed + reclaimed);
+    // Only the global environment and the two top-level functions remain.
+    assert_eq!(after.live_objects, baseline + 2);
+}
+
+#[test]
+fn test_collection_keeps_reachable_values() {
+    let mut evaluator = Evaluator::new();
+
+    eval_with(
+        &mut evaluator,
+        "let make = fn(limit) { let count = fn(n) { if (n == limit) { n } else { count(n + 1) } }; count }; let counter = make(10);",
+    );
+    let held = eval_with(&mut evaluator, "make(20)");
+
+    evaluator.collect_garbage();
+
+    test_integer_object(&eval_with(&mut evaluator, "counter(0)"), 10);
+    match held {
+        Object::Function(function) => {
+            assert_eq!(
+                function.env.borrow().get("limit").map(|v| v.to_string()),
+                Some("20".to_string())
+            )
+        }
+        other => panic!("object is not Function. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_collects_automatically() {
+    let mut evaluator = Evaluator::new();
+
+    let input = r#"
+let make = fn() { let f = fn() { f }; f };
+let churn = fn(n) { if (n == 0) { 0 } else { make(); churn(n - 1) } };
+churn(10000);
+"#;
+    test_integer_object(&eval_with(&mut evaluator, input), 0);
+
+    let stats = evaluator.heap_stats();
+    assert!(stats.collections > 0, "no collection ran");
+    assert!(stats.reclaimed > 0, "no cycles were freed");
+    assert!(
+        stats.live_objects < 10000,
+        "heap kept {} objects alive",
+        stats.live_objects
+    );
+}
+
+#[test]
+fn test_dropping_evaluator_frees_cycles() {
+    let mut evaluator = Evaluator::new();
+    eval_with(&mut evaluator, "let f = fn() { f };");
+
+    let env = std::rc::Rc::downgrade(evaluator.environment());
+    drop(evaluator);
+
+    assert!(env.upgrade().is_none(), "global environment leaked");
+}
+
+#[test]
+fn test_native_functions() {
+    let mut evaluator = Evaluator::new();
+    evaluator.register_native("double", 1, |args| match &args[0] {
+        Object::Integer(value) => Ok(Object::Integer(value * 2)),
+        other => Err(format!(
+            "double: expected INTEGER, got {}",
+            other.type_name()
+        )),
+    });
+    evaluator.register_native("answer", 0, |_| Ok(Object::Integer(42)));
+
+    let tests = [
+        ("double(21)", 42),
+        ("answer() + double(1)", 44),
+        ("let f = fn(x) { double(x) }; f(5)", 10),
+        ("let apply = fn(g, x) { g(x) }; apply(double, 4)", 8),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(&eval_with(&mut evaluator, input), *expected);
+    }
+
+    let errors = [
+        ("double(1, 2)", "wrong number of arguments: want=1, got=2"),
+        ("double(true)", "double: expected INTEGER, got BOOLEAN"),
+    ];
+
+    for (input, expected) in errors.iter() {
+        match eval_with(&mut evaluator, input) {
+            Object::Error(msg) => assert_eq!(msg, *expected),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+
+    test_integer_object(
+        &eval_with(&mut evaluator, "let double = fn(x) { x }; double(3)"),
+        3,
+    );
+}
+
+#[test]
+fn test_array_literals() {
+    match test_eval("[1, 2 * 2, 3 + 3]") {
+        Object::Array(elements) => {
+            assert_eq!(elements.len(), 3);
+            test_integer_object(&elements[0], 1);
+            test_integer_object(&elements[1], 4);
+            test_integer_object(&elements[2], 6);
+        }
+        other => panic!("object is not Array. got {:#?}", other),
+    }
+
+    assert_eq!(
+        test_eval(r#"[1, "two", [3]]"#).to_string(),
+        r#"[1, "two", [3]]"#
+    );
+}
+
+#[test]
+fn test_array_index_expressions() {
+    let tests = [
+        ("[1, 2, 3][0]", Some(1)),
+        ("[1, 2, 3][1]", Some(2)),
+        ("[1, 2, 3][2]", Some(3)),
+        ("let i = 0; [1][i];", Some(1)),
+        ("[1, 2, 3][1 + 1];", Some(3)),
+        ("let my_array = [1, 2, 3]; my_array[2];", Some(3)),
+        (
+            "let my_array = [1, 2, 3]; my_array[0] + my_array[1] + my_array[2];",
+            Some(6),
+        ),
+        ("let f = fn() { [fn(x) { x * 2 }] }; f()[0](21)", Some(42)),
+        ("[[1, 2], [3, 4]][1][0]", Some(3)),
+        ("[1, 2, 3][3]", None),
+        ("[1, 2, 3][-1]", None),
+        ("[][0]", None),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let evaluated = test_eval(input);
+        match expected {
+            Some(expected) => test_integer_object(&evaluated, *expected),
+            None => test_null_object(&evaluated),
+        }
+    }
+}
+
+#[test]
+fn test_collection_follows_functions_in_arrays() {
+    let mut evaluator = Evaluator::new();
+
+    eval_with(
+        &mut evaluator,
+        "let make = fn(n) { let f = fn() { n }; [f, f] }; let kept = make(7);",
+    );
+    let held = eval_with(&mut evaluator, "let tmp = make(8); tmp");
+    eval_with(&mut evaluator, "let tmp = 0; make(9); make(10);");
+
+    assert!(evaluator.collect_garbage() >= 2);
+
+    test_integer_object(&eval_with(&mut evaluator, "kept[1]()"), 7);
+    match held {
+        Object::Array(elements) => match &elements[0] {
+            Object::Function(function) => {
+                test_integer_object(&function.env.borrow().get("n").unwrap(), 8)
+            }
+            other => panic!("object is not Function. got {:#?}", other),
+        },
+        other => panic!("object is not Array. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_hash_literals() {
+    let input = r#"let two = "two";
+    {
+        "one": 10 - 9,
+        two: 1 + 1,
+        "thr" + "ee": 6 / 2,
+        4: 4,
+        true: 5,
+        false: 6
+    }"#;
+
+    let pairs = match test_eval(input) {
+        Object::Hash(pairs) => pairs,
+        other => panic!("object is not Hash. got {:#?}", other),
+    };
+
+    let expected = [
+        (HashKey::String("one".to_string()), 1),
+        (HashKey::String("two".to_string()), 2),
+        (HashKey::String("three".to_string()), 3),
+        (HashKey::Integer(4), 4),
+        (HashKey::Boolean(true), 5),
+        (HashKey::Boolean(false), 6),
+    ];
+
+    assert_eq!(pairs.len(), expected.len());
+    for (key, value) in expected.iter() {
+        test_integer_object(&pairs[key], *value);
+    }
+
+    assert_eq!(
+        test_eval(r#"{"b": [1], 2: "x", true: {}, 1: 1, 2: "y"}"#).to_string(),
+        r#"{1: 1, 2: "y", true: {}, "b": [1]}"#
+    );
+}
+
+#[test]
+fn test_hash_index_expressions() {
+    let tests = [
+        (r#"{"foo": 5}["foo"]"#, Some(5)),
+        (r#"{"foo": 5}["bar"]"#, None),
+        (r#"let key = "foo"; {"foo": 5}[key]"#, Some(5)),
+        (r#"{}["foo"]"#, None),
+        ("{5: 5}[5]", Some(5)),
+        ("{true: 5}[true]", Some(5)),
+        ("{false: 5}[false]", Some(5)),
+        ("{1: 5}[true]", None),
+        (r#"let h = {"f": fn(x) { x * 2 }}; h["f"](21)"#, Some(42)),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let evaluated = test_eval(input);
+        match expected {
+            Some(expected) => test_integer_object(&evaluated, *expected),
+            None => test_null_object(&evaluated),
+        }
+    }
+}
+
+#[test]
+fn test_unusable_hash_keys() {
+    let tests = [
+        (
+            r#"{"name": "Monkey"}[fn(x) { x }];"#,
+            "unusable as hash key: FUNCTION",
+        ),
+        ("{[1]: 2}", "unusable as hash key: ARRAY"),
+        ("{{}: 2}", "unusable as hash key: HASH"),
+        ("{1: 2}[[1]]", "unusable as hash key: ARRAY"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, *expected),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_collection_follows_functions_in_hashes() {
+    let mut evaluator = Evaluator::new();
+
+    eval_with(
+        &mut evaluator,
+        r#"let make = fn(n) { let f = fn() { n }; {"f": f} }; let kept = make(7);"#,
+    );
+    eval_with(&mut evaluator, "make(8); make(9);");
+
+    assert!(evaluator.collect_garbage() >= 2);
+    test_integer_object(&eval_with(&mut evaluator, r#"kept["f"]()"#), 7);
+}
+
+fn test_float_object(obj: &Object, expected: f64) {
+    match obj {
+        Object::Float(value) => assert_eq!(
+            *value, expected,
+            "object has wrong value. got {}, want {}",
+            value, expected
+        ),
+        _ => panic!("object is not Float. got {:#?}", obj),
+    }
+}
+
+#[test]
+fn test_eval_float_expression() {
+    let tests = [
+        ("2.75", 2.75),
+        ("-2.5", -2.5),
+        ("1e-9", 1e-9),
+        ("0.5 + 0.25", 0.75),
+        ("1 + 0.5", 1.5),
+        ("0.5 + 1", 1.5),
+        ("2 * 1.5", 3.0),
+        ("7 / 2.0", 3.5),
+        ("7.0 / 2", 3.5),
+        ("10 - 0.5 * 4", 8.0),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_float_object(&test_eval(input), *expected);
+    }
+
+    test_integer_object(&test_eval("7 / 2"), 3);
+    test_integer_object(&test_eval("-7 / 2"), -3);
+    assert_eq!(test_eval("3.0").to_string(), "3.0");
+    assert_eq!(test_eval("[0.1, 2e20]").to_string(), "[0.1, 2e20]");
+}
+
+#[test]
+fn test_float_comparisons() {
+    let tests = [
+        ("1.5 < 2.5", true),
+        ("1 < 1.5", true),
+        ("1.5 > 2", false),
+        ("1 == 1.0", true),
+        ("1.0 != 1", false),
+        ("0.1 + 0.2 == 0.3", false),
+        ("-0.0 == 0.0", true),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_boolean_object(&test_eval(input), *expected);
+    }
+}
+
+#[test]
+fn test_float_errors() {
+    let tests = [
+        ("1.5 / 0", "division by zero"),
+        ("1 / 0.0", "division by zero"),
+        ("1.5 + true", "type mismatch: FLOAT + BOOLEAN"),
+        (r#""a" + 1.5"#, "type mismatch: STRING + FLOAT"),
+        ("{1.5: 1}", "unusable as hash key: FLOAT"),
+        ("[1][0.0]", "array index must be INTEGER, got FLOAT"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, *expected),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_integers_promote_to_bignums() {
+    let tests = [
+        ("9223372036854775807 + 1", "9223372036854775808"),
+        ("-9223372036854775807 - 2", "-9223372036854775809"),
+        ("9223372036854775807 * 2", "18446744073709551614"),
+        ("-(-9223372036854775807 - 1)", "9223372036854775808"),
+        ("(-9223372036854775807 - 1) / -1", "9223372036854775808"),
+        ("18446744073709551616", "18446744073709551616"),
+        ("18446744073709551616 / 4294967296", "4294967296"),
+        ("-18446744073709551616 / 7", "-2635249153387078802"),
+        (
+            "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(30)",
+            "265252859812191058636308480000000",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let evaluated = test_eval(input);
+        assert!(
+            matches!(evaluated, Object::Integer(_) | Object::BigInt(_)),
+            "{:?} is not an integer. got {:#?}",
+            input,
+            evaluated
+        );
+        assert_eq!(evaluated.to_string(), *expected, "{:?}", input);
+    }
+}
+
+#[test]
+fn test_bignums_demote_when_they_fit() {
+    let tests = [
+        ("9223372036854775808 - 1", i64::MAX),
+        ("-9223372036854775808", i64::MIN),
+        ("18446744073709551616 / 18446744073709551616", 1),
+        ("(9223372036854775807 + 10) - 10", i64::MAX),
+        ("-(9223372036854775807 + 1) + 5", i64::MIN + 5),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(&test_eval(input), *expected);
+    }
+}
+
+#[test]
+fn test_bignum_comparisons_and_mixing() {
+    let tests = [
+        ("18446744073709551616 > 9223372036854775807", true),
+        ("-18446744073709551616 < 1", true),
+        ("18446744073709551616 == 18446744073709551616", true),
+        ("18446744073709551616 != 18446744073709551617", true),
+        ("18446744073709551616 == 18446744073709551616.0", true),
+        ("1.5 < 18446744073709551616", true),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_boolean_object(&test_eval(input), *expected);
+    }
+
+    test_float_object(
+        &test_eval("18446744073709551616 * 0.5"),
+        9223372036854775808.0,
+    );
+    test_null_object(&test_eval("[1][18446744073709551616]"));
+    test_integer_object(
+        &test_eval("{18446744073709551616: 3}[9223372036854775807 * 2 + 2]"),
+        3,
+    );
+
+    match test_eval("18446744073709551616 / 0") {
+        Object::Error(msg) => assert_eq!(msg, "division by zero"),
+        other => panic!("no error object returned. got {:#?}", other),
+    }
+    match test_eval("18446744073709551616 + true") {
+        Object::Error(msg) => assert_eq!(msg, "type mismatch: INTEGER + BOOLEAN"),
+        other => panic!("no error object returned. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_null_values() {
+    let tests = [
+        "null",
+        "if (false) { 1 }",
+        "let f = fn() {}; f()",
+        r#"{"a": 1}["b"]"#,
+        "null?.a",
+        "null?[0]",
+        "null?.a?.b?[1]",
+        r#"{"a": {}}?.a?.b"#,
+        "null ?? null",
+    ];
+
+    for input in tests.iter() {
+        test_null_object(&test_eval(input));
+    }
+
+    test_boolean_object(&test_eval("null == null"), true);
+    test_boolean_object(&test_eval("null != null"), false);
+    test_boolean_object(&test_eval(r#"{}["a"] == null"#), true);
+    test_boolean_object(&test_eval("1 == null"), false);
+    test_boolean_object(&test_eval("null != [1]"), true);
+    test_boolean_object(&test_eval("!null"), true);
+    assert_eq!(test_eval("[null]").to_string(), "[null]");
+}
+
+#[test]
+fn test_optional_chaining_and_coalescing() {
+    let tests = [
+        ("null ?? 5", 5),
+        ("3 ?? 5", 3),
+        ("false ?? 5 == false", 1),
+        (r#"let config = {"port": 80}; config?.port"#, 80),
+        (r#"let config = {"port": 80}; config?.host ?? 8080"#, 8080),
+        (
+            r#"let config = {"db": {"port": 5432}}; config?.db?.port"#,
+            5432,
+        ),
+        (r#"let config = {}; config?.db?.port ?? 1"#, 1),
+        ("[1, 2]?[1]", 2),
+        ("let xs = null; xs?[0] ?? 7", 7),
+        ("[[1, 9]]?[0]?[1]", 9),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Boolean(false) => assert_eq!(*expected, 1, "{:?}", input),
+            evaluated => test_integer_object(&evaluated, *expected),
+        }
+    }
+}
+
+#[test]
+fn test_optional_operators_short_circuit() {
+    let mut evaluator = Evaluator::new();
+
+    let evaluated = eval_with(
+        &mut evaluator,
+        "let a = 1 ?? missing; let b = null?[missing]; let c = null?.x ?? 2; c",
+    );
+    test_integer_object(&evaluated, 2);
+
+    let tests = [
+        ("null ?? missing", "identifier not found: missing"),
+        ("1?.a", "member access not supported: INTEGER"),
+        ("[1]?.len", "member access not supported: ARRAY"),
+        ("1?[0]", "index operator not supported: INTEGER"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, *expected),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_range_values() {
+    let tests = [
+        ("0..10", "0..10"),
+        ("0..=10", "0..=10"),
+        ("let n = 3; ..n * 2", "..6"),
+        ("-2..", "-2.."),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Range(range) => assert_eq!(range.to_string(), *expected),
+            other => panic!("object is not Range. got {:#?}", other),
+        }
+    }
+
+    assert_eq!(test_eval("[1..2, ..]").to_string(), "[1..2, ..]");
+
+    let tests = [
+        ("1.5..2", "range bounds must be INTEGER, got FLOAT"),
+        (r#"0..="a""#, "range bounds must be INTEGER, got STRING"),
+        ("0..18446744073709551616", "range bound too large"),
+        ("{0..1: 1}", "unusable as hash key: RANGE"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, *expected),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+}
+
+/// Slice bounds that are left out run to that end of the sequence, negative
+/// bounds count back from the end, and bounds past either end are clamped,
+/// so slicing never fails or returns null.
+#[test]
+fn test_slice_expressions() {
+    let tests = [
+        ("[1, 2, 3, 4, 5][1..3]", "[2, 3]"),
+        ("[1, 2, 3, 4, 5][1..=3]", "[2, 3, 4]"),
+        ("[1, 2, 3, 4, 5][..2]", "[1, 2]"),
+        ("[1, 2, 3, 4, 5][3..]", "[4, 5]"),
+        ("[1, 2, 3, 4, 5][..]", "[1, 2, 3, 4, 5]"),
+        ("[1, 2, 3, 4, 5][-2..]", "[4, 5]"),
+        ("[1, 2, 3, 4, 5][..-1]", "[1, 2, 3, 4]"),
+        ("[1, 2, 3, 4, 5][..=-1]", "[1, 2, 3, 4, 5]"),
+        ("[1, 2, 3, 4, 5][-3..-1]", "[3, 4]"),
+        ("[1, 2, 3, 4, 5][3..1]", "[]"),
+        ("[1, 2, 3, 4, 5][2..99]", "[3, 4, 5]"),
+        ("[1, 2, 3, 4, 5][-99..1]", "[1]"),
+        ("[1, 2, 3, 4, 5][..=-99]", "[]"),
+        ("[1, 2, 3][3..]", "[]"),
+        ("[][0..1]", "[]"),
+        ("let r = 1..; [1, 2, 3][r]", "[2, 3]"),
+        (r#""hello"[1..3]"#, "el"),
+        (r#""hello"[..2]"#, "he"),
+        (r#""hello"[-3..]"#, "llo"),
+        (r#""hello"[..=-2]"#, "hell"),
+        (r#""héllo wörld"[1..=4]"#, "éllo"),
+        (r#""abc"[5..]"#, ""),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Array(elements) => {
+                assert_eq!(
+                    Object::Array(elements).to_string(),
+                    *expected,
+                    "{:?}",
+                    input
+                )
+            }
+            Object::String(value) => assert_eq!(value, *expected, "{:?}", input),
+            other => panic!("{:?} did not slice. got {:#?}", input, other),
+        }
+    }
+
+    match test_eval("{}[0..1]") {
+        Object::Error(msg) => assert_eq!(msg, "unusable as hash key: RANGE"),
+        other => panic!("no error object returned. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_tuple_values() {
+    let tests = [
+        (r#"(1, "x")"#, r#"(1, "x")"#),
+        ("(1 + 1,)", "(2,)"),
+        ("()", "()"),
+        ("((1, 2), [3])", "((1, 2), [3])"),
+        ("(1)", "1"),
+        ("(1, 2, 3)[1]", "2"),
+        ("(1, 2)[2]", "null"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+
+    match test_eval(r#"(1, 2)["a"]"#) {
+        Object::Error(msg) => assert_eq!(msg, "tuple index must be INTEGER, got STRING"),
+        other => panic!("no error object returned. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_destructuring_let() {
+    let tests = [
+        ("let (a, b) = (1, 2); a * 10 + b", "12"),
+        ("let pair = (1, 2); let (a, _) = pair; a", "1"),
+        ("let (a,) = (5,); a", "5"),
+        ("let [head, ...rest] = [1, 2, 3]; rest", "[2, 3]"),
+        ("let [head, ...rest] = [1, 2, 3]; head", "1"),
+        ("let [a, b, ...rest] = [1, 2]; rest", "[]"),
+        ("let [a, b] = [1, 2]; a + b", "3"),
+        ("let [(a, b), [c]] = [(1, 2), [3]]; a + b + c", "6"),
+        ("let (x, [y, ..._]) = (1, [2]); x + y", "3"),
+        ("let f = fn(p) { let (a, b) = p; a - b }; f((5, 3))", "2"),
+        ("let a = 1; let (a, b) = (a + 1, a); a * 10 + b", "21"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+}
+
+#[test]
+fn test_destructuring_errors() {
+    let tests = [
+        ("let (a, b) = [1, 2];", "cannot destructure ARRAY as tuple"),
+        ("let [a, b] = (1, 2);", "cannot destructure TUPLE as array"),
+        (
+            "let (a, b) = (1, 2, 3);",
+            "tuple pattern expects 2 elements, got 3",
+        ),
+        (
+            "let [a] = [1, 2];",
+            "array pattern expects 1 elements, got 2",
+        ),
+        (
+            "let [a, b, ...c] = [1];",
+            "array pattern expects at least 2 elements, got 1",
+        ),
+        (
+            "let (a, (b, c)) = (1, 2);",
+            "cannot destructure INTEGER as tuple",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, *expected),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+
+    // A failed match binds none of the names.
+    let mut evaluator = Evaluator::new();
+    eval_with(&mut evaluator, "let (a, (b, c)) = (1, 2);");
+    assert!(evaluator.environment().borrow().get("a").is_none());
+}
+
+#[test]
+fn test_structs() {
+    let tests = [
+        (
+            "struct Point { x, y } Point { x: 1, y: 2 }",
+            "Point { x: 1, y: 2 }",
+        ),
+        (
+            r#"struct Named { name, tags } Named { tags: ["a"], name: "n" }"#,
+            r#"Named { name: "n", tags: ["a"] }"#,
+        ),
+        ("struct Point { x, y } Point", "struct Point { x, y }"),
+        (
+            "struct Point { x, y } let p = Point { x: 1, y: 2 }; p.x + p.y",
+            "3",
+        ),
+        (
+            "struct Box { value } Box { value: Box { value: 5 } }.value.value",
+            "5",
+        ),
+        (
+            "struct Counter { step } let c = Counter { step: fn(n) { n + 1 } }; c.step(1)",
+            "2",
+        ),
+        ("struct Empty {} Empty {}", "Empty {  }"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+
+    let tests = [
+        (
+            "struct Point { x, y } Point { x: 1 }",
+            "missing field y for struct Point",
+        ),
+        (
+            "struct Point { x, y } Point { x: 1, y: 2, z: 3 }",
+            "unknown field z for struct Point",
+        ),
+        (
+            "struct Point { x, y } Point { x: 1, x: 2, y: 3 }",
+            "field x given more than once for struct Point",
+        ),
+        (
+            "struct Point { x, y } Point { x: 1, y: 2 }.z",
+            "unknown field z for struct Point",
+        ),
+        ("let Point = 1; Point { x: 1 }", "not a struct: INTEGER"),
+        ("Point { x: 1 }", "identifier not found: Point"),
+        ("[1].x", "member access not supported: ARRAY"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, *expected),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_collection_follows_functions_in_structs() {
+    let mut evaluator = Evaluator::new();
+
+    eval_with(
+        &mut evaluator,
+        "struct Holder { f } let make = fn(n) { let h = Holder { f: fn() { n } }; h }; let kept = make(7);",
+    );
+    eval_with(&mut evaluator, "make(8); make(9);");
+
+    assert!(evaluator.collect_garbage() >= 2);
+    test_integer_object(&eval_with(&mut evaluator, "kept.f()"), 7);
+}
+
+#[test]
+fn test_logical_operators_short_circuit() {
+    let tests = [
+        ("false && missing", "false"),
+        ("true || missing", "true"),
+        ("null && missing", "null"),
+        ("1 && 2", "2"),
+        ("0 || 5", "0"),
+        ("null || 5", "5"),
+        (r#"false || "fallback""#, "fallback"),
+        ("let xs = []; xs[0] && xs[0] > 1", "null"),
+        (
+            "let calls = fn(n) { if (n > 0) { calls(n - 1) || false } else { true } }; calls(3)",
+            "true",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+
+    match test_eval("true && missing") {
+        Object::Error(msg) => assert_eq!(msg, "identifier not found: missing"),
+        other => panic!("no error object returned. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_arithmetic_and_bitwise_operators() {
+    let tests = [
+        ("7 % 3", "1"),
+        ("-7 % 3", "-1"),
+        ("7 % -3", "1"),
+        ("2 + 7 % 4 * 2", "8"),
+        ("2 ** 10", "1024"),
+        ("2 ** 3 ** 2", "512"),
+        ("(2 ** 3) ** 2", "64"),
+        ("-2 ** 2", "-4"),
+        ("(-2) ** 3", "-8"),
+        ("2 ** 0", "1"),
+        ("2 ** -1", "0.5"),
+        ("2 ** 64", "18446744073709551616"),
+        ("(2 ** 64) ** 2", "340282366920938463463374607431768211456"),
+        ("(2 ** 64 + 1) % 10", "7"),
+        ("-9223372036854775808 % -1", "0"),
+        ("2.0 ** 0.5 > 1.41", "true"),
+        ("7.5 % 2", "1.5"),
+        ("12 & 10", "8"),
+        ("12 | 10", "14"),
+        ("12 ^ 10", "6"),
+        ("~0", "-1"),
+        ("~5", "-6"),
+        ("~(2 ** 64)", "-18446744073709551617"),
+        ("1 << 4", "16"),
+        ("1 | 2 << 2", "9"),
+        ("1 << 63", "9223372036854775808"),
+        ("3 << 100", "3802951800684688204490109616128"),
+        ("-1 << 64", "-18446744073709551616"),
+        ("256 >> 4", "16"),
+        ("-17 >> 2", "-5"),
+        ("1 >> 100", "0"),
+        ("-1 >> 100", "-1"),
+        ("(2 ** 100) >> 98", "4"),
+        ("-(2 ** 100) >> 98", "-4"),
+        ("(-(2 ** 100) - 1) >> 98", "-5"),
+        ("(2 ** 100) >> 200", "0"),
+        ("-(2 ** 100) >> 200", "-1"),
+        ("1 ** 300000", "1"),
+        ("1 ** (2 ** 100)", "1"),
+        ("0 ** (2 ** 100)", "0"),
+        ("(-1) ** 300001", "-1"),
+        ("(-1) ** (2 ** 100)", "1"),
+        ("(2 ** 64) ** 0", "1"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+
+    let tests = [
+        ("1 % 0", "division by zero"),
+        ("1.5 % 0", "division by zero"),
+        ("(2 ** 64) % 0", "division by zero"),
+        ("0 ** -1", "division by zero"),
+        ("(-8) ** 0.5", "negative base with fractional exponent"),
+        ("2 ** 300000", "integer result too large"),
+        ("(2 ** 200000) * (2 ** 200000)", "integer result too large"),
+        ("1 << 300000", "integer result too large"),
+        ("1 << -1", "negative shift amount"),
+        ("1 >> -1", "negative shift amount"),
+        ("(2 ** 64) & 1", "integer too large for &"),
+        ("1.5 & 1", "unknown operator: FLOAT & FLOAT"),
+        ("~1.5", "unknown operator: ~FLOAT"),
+        ("true ** 2", "type mismatch: BOOLEAN ** INTEGER"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, *expected, "{:?}", input),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_assignment() {
+    let tests = [
+        ("let mut x = 1; x = 2; x", "2"),
+        ("let mut x = 1; x = x + 1", "2"),
+        ("let mut x = 10; x += 5; x -= 3; x *= 2; x /= 4; x", "6"),
+        (r#"let mut s = "a"; s += "b"; s"#, "ab"),
+        ("let mut x = 1; let mut y = 2; x = y = 3; [x, y]", "[3, 3]"),
+        ("let mut a = [1, 2, 3]; a[1] = 20; a", "[1, 20, 3]"),
+        ("let mut a = [1, 2, 3]; a[0] += 10; a", "[11, 2, 3]"),
+        ("let mut a = [[1, 2], [3]]; a[0][1] = 5; a", "[[1, 5], [3]]"),
+        ("let a = [1]; let mut b = a; b[0] = 2; [a, b]", "[[1], [2]]"),
+        (
+            r#"let mut h = {"a": 1}; h["b"] = 2; h"#,
+            r#"{"a": 1, "b": 2}"#,
+        ),
+        (r#"let mut h = {"a": 1}; h.a = 5; h.a"#, "5"),
+        (
+            "struct P { x, y } let mut p = P { x: 1, y: 2 }; p.x += 10; p",
+            "P { x: 11, y: 2 }",
+        ),
+        (
+            "let mut sum = 0; let add = fn(n) { sum += n }; add(1); add(2); sum",
+            "3",
+        ),
+        ("let f = fn() { let mut n = 1; n = n * 5; n }; f()", "5"),
+        ("let mut x = 1; let x = 2; x", "2"),
+        ("let mut (a, b) = (1, 2); a = b; a", "2"),
+        (
+            "let mut n = 0; let i = fn() { n += 1; 0 }; let mut a = [1]; a[i()] += 5; [a, n]",
+            "[[6], 1]",
+        ),
+        (
+            "let mut n = 0; let i = fn() { n += 1; n }; let mut a = [[1, 2, 3]]; a[i() - 1][i()] *= 10; [a, n]",
+            "[[[1, 2, 30]], 2]",
+        ),
+        (
+            r#"let mut n = 0; let i = fn() { n += 1; 0 }; let mut h = {"k": [5]}; h.k[i()] = n; [h, n]"#,
+            r#"[{"k": [1]}, 1]"#,
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+
+    let tests = [
+        (
+            "let x = 1; x = 2",
+            "cannot assign to immutable variable x at 1:12",
+        ),
+        ("y = 2", "cannot assign to undefined variable y at 1:1"),
+        (
+            "let mut x = 1; let x = 2;\nx += 1",
+            "cannot assign to immutable variable x at 2:1",
+        ),
+        (
+            "let a = [1]; a[0] = 2",
+            "cannot assign to immutable variable a at 1:14",
+        ),
+        (
+            "let f = fn(n) { n = 2 }; f(1)",
+            "cannot assign to immutable variable n at 1:17",
+        ),
+        ("x += 1", "identifier not found: x"),
+        (
+            "let mut a = [1]; a[1] = 2",
+            "array index out of bounds: 1 for length 1",
+        ),
+        (
+            r#"let mut a = [1]; a["0"] = 2"#,
+            "array index must be INTEGER, got STRING",
+        ),
+        (
+            "let mut t = (1, 2); t[0] = 2",
+            "index assignment not supported: TUPLE",
+        ),
+        (
+            "let mut n = 1; n.x = 2",
+            "member assignment not supported: INTEGER",
+        ),
+        (
+            "struct P { x } let mut p = P { x: 1 }; p.y = 2",
+            "unknown field y for struct P",
+        ),
+        (
+            "let mut x = 1; x += true",
+            "type mismatch: INTEGER + BOOLEAN",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, *expected, "{:?}", input),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_while_loops() {
+    let tests = [
+        (
+            "let mut i = 0; let mut sum = 0; while (i < 5) { i += 1; sum += i; } sum",
+            "15",
+        ),
+        ("while (false) { 1 }", "null"),
+        (
+            "let mut i = 0; while (true) { if (i == 3) { break; } i += 1; } i",
+            "3",
+        ),
+        (
+            "let mut i = 0; let mut s = 0; while (i < 10) { i += 1; if (i % 2 == 0) { continue; } s += i; } s",
+            "25",
+        ),
+        (
+            "let mut found = null; let mut i = 0;
+             'outer: while (i < 5) {
+                 let mut j = 0;
+                 while (j < 5) {
+                     if (i * j == 6) { found = [i, j]; break 'outer; }
+                     j += 1;
+                 }
+                 i += 1;
+             }
+             found",
+            "[2, 3]",
+        ),
+        (
+            "let mut n = 0; let mut i = 0;
+             'rows: while (i < 3) {
+                 i += 1;
+                 let mut j = 0;
+                 while (true) { j += 1; if (j > i) { continue 'rows; } n += 1; }
+             }
+             n",
+            "6",
+        ),
+        (
+            "let f = fn() { let mut i = 0; while (true) { i += 1; if (i == 4) { return i * 10; } } }; f()",
+            "40",
+        ),
+        (
+            "let f = fn(n) { let mut i = 0; while (i < n) { i += 1; } i }; f(100000)",
+            "100000",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+
+    match test_eval("while (missing) { 1 }") {
+        Object::Error(msg) => assert_eq!(msg, "identifier not found: missing"),
+        other => panic!("no error object returned. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_for_loops() {
+    let tests = [
+        ("for (x in [1, 2]) { x }", "null"),
+        (
+            "let mut s = 0; for (x in [1, 2, 3]) { s += x; } s",
+            "6",
+        ),
+        (
+            r#"let mut s = ""; for (c in "abc") { s = c + s; } s"#,
+            "cba",
+        ),
+        ("let mut s = 0; for (i in 1..=4) { s += i; } s", "10"),
+        ("let mut s = 0; for (x in (1, 2)) { s += x; } s", "3"),
+        (
+            r#"let mut s = ""; for (k in {"b": 2, "a": 1}) { s += k; } s"#,
+            "ab",
+        ),
+        (
+            "let mut s = 0; for (k, v in {1: 10, 2: 20}) { s += k * v; } s",
+            "50",
+        ),
+        (
+            "let mut s = 0; for (i, x in [5, 6, 7]) { s += i * x; } s",
+            "20",
+        ),
+        (
+            "let mut s = 0; for ((a, b) in [(1, 2), (3, 4)]) { s += a * b; } s",
+            "14",
+        ),
+        (
+            "let mut s = 0; for (i in 0..) { if (i == 5) { break; } s += i; } s",
+            "10",
+        ),
+        (
+            "let mut s = 0; for (i in 0..10) { if (i % 2 == 0) { continue; } s += i; } s",
+            "25",
+        ),
+        (
+            "let mut found = null;
+             'outer: for (i in 0..5) {
+                 for (j in 0..5) {
+                     if (i * j == 6) { found = [i, j]; break 'outer; }
+                 }
+             }
+             found",
+            "[2, 3]",
+        ),
+        (
+            "let f = fn() {
+                 let mut n = 0;
+                 'rows: for (i in 1..=3) {
+                     for (j in 1..) { if (j > i) { continue 'rows; } n += 1; }
+                 }
+                 n
+             }; f()",
+            "6",
+        ),
+        (
+            "let mut n = 0;
+             'outer: for (i in [1, 2]) {
+                 let mut j = 0;
+                 while (true) { j += 1; if (j == 3) { continue 'outer; } n += i; }
+             }
+             n",
+            "6",
+        ),
+        (
+            "let f = fn(xs) { for (x in xs) { if (x > 2) { return x * 10; } } 0 }; f([1, 2, 3, 4])",
+            "30",
+        ),
+        ("let f = fn() { for (x in []) { 1 } }; f()", "null"),
+        (
+            "let mut fs = [null, null, null]; for (i, x in [10, 20, 30]) { fs[i] = fn() { x }; } fs[0]() + fs[2]()",
+            "40",
+        ),
+        (
+            "let f = fn() { let mut fs = [null, null]; for (i in 0..2) { fs[i] = fn() { i }; } fs }; f()[0]()",
+            "0",
+        ),
+        ("let x = 5; for (x in [1, 2]) { } x", "5"),
+        (
+            "let f = fn(n) { let mut s = 0; for (i in 0..n) { s += i; } s }; f(100000)",
+            "4999950000",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+
+    for (input, error) in [
+        ("for (x in 5) { x }", "not iterable: INTEGER"),
+        (
+            "for (x in ..3) { x }",
+            "cannot iterate over range without a start: ..3",
+        ),
+        ("for (x in [1]) { } x", "identifier not found: x"),
+        ("for (x in [1]) { let y = x; } y", "identifier not found: y"),
+        (
+            "for ((a, b) in [1]) { a }",
+            "cannot destructure INTEGER as tuple",
+        ),
+    ] {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, error, "{:?}", input),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_match_expressions() {
+    let tests = [
+        (
+            r#"let f = fn(v) { match (v) { 0 => "zero", [a, b] => a + b, {"k": x} => x, n if n > 10 => "big", _ => "other" } };
+               [f(0), f([1, 2]), f({"k": 7, "j": 8}), f(11), f(5)]"#,
+            r#"["zero", 3, 7, "big", "other"]"#,
+        ),
+        (
+            "match (-2) { 2 => \"pos\", -2 => \"neg\", _ => \"other\" }",
+            "neg",
+        ),
+        ("match (1.5) { 1 => \"int\", 1.5 => \"float\", _ => \"other\" }", "float"),
+        ("match (1) { 1.0 => \"float\", 1 => \"int\" }", "int"),
+        (
+            "match ([true, null]) { [false, _] => 1, [true, null] => 2, _ => 3 }",
+            "2",
+        ),
+        ("match ([1, 2, 3, 4]) { [first, ...rest] => rest }", "[2, 3, 4]"),
+        ("match ([1, 2, 3]) { [a, b, ..._] => a * b }", "2"),
+        ("match ((1, (2, 3))) { (a, (b, c)) => a + b + c }", "6"),
+        (
+            "match (5) { n if n > 10 => \"big\", n if n > 3 => \"medium\", _ => \"small\" }",
+            "medium",
+        ),
+        ("let x = 1; match (2) { x => x }; x", "1"),
+        (
+            "let f = match ((3, 4)) { (a, b) => fn() { a * b } }; f()",
+            "12",
+        ),
+        (
+            "let f = fn(xs) { match (xs) { [] => 0, [x, ...rest] => x + f(rest) } }; f([1, 2, 3, 4])",
+            "10",
+        ),
+        (
+            "let mut s = 0; for (x in [1, 2, 3]) { s += match (x) { 2 => 20, n => n }; } s",
+            "24",
+        ),
+        (
+            r#"match ({"a": 1}) { {"b": x} => x, {"a": x} => x * 100 }"#,
+            "100",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        assert_eq!(test_eval(input).to_string(), *expected, "{:?}", input);
+    }
+
+    for (input, error) in [
+        ("match (5) { 1 => 1, [a] => a }", "no match arm for 5"),
+        ("match (5) { x if x > 10 => x }", "no match arm for 5"),
+        ("match (5) { x => x }; x", "identifier not found: x"),
+        ("match (1) { x if y => x }", "identifier not found: y"),
+        (
+            "match ([1]) { n if n > 10 => n, _ => 0 }",
+            "type mismatch: ARRAY > INTEGER",
+        ),
+    ] {
+        match test_eval(input) {
+            Object::Error(msg) => assert_eq!(msg, error, "{:?}", input),
+            other => panic!("no error object returned for {:?}. got {:#?}", input, other),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use rust_monkey::eval::limits::{LimitExceeded, Limits};
+use rust_monkey::interpreter::{Interpreter, InterpreterError};
+use rust_monkey::object::Object;
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
+fn test_integer_object(obj: &Object, expected: i64) {
+    match obj {
+        Object::Integer(value) => assert_eq!(
+            *value, expected,
+            "object has wrong value. got {}, want {}",
+            value, expected
+        ),
+        _ => panic!("object is not Integer. got {:#?}", obj),
+    }
+}
+
+#[test]
+fn test_eval_str_keeps_state() {
+    let mut interpreter = Interpreter::new();
+
+    test_integer_object(&interpreter.eval_str("let a = 5; a * 2").unwrap(), 10);
+    interpreter.eval_str("let add = fn(b) { a + b };").unwrap();
+    test_integer_object(&interpreter.eval_str("add(10)").unwrap(), 15);
+    assert!(matches!(interpreter.eval_str(""), Ok(Object::Null)));
+}
+
+#[test]
+fn test_errors() {
+    let mut interpreter = Interpreter::new();
+
+    match interpreter.eval_str("let a = 1 @ 2;") {
+        Err(InterpreterError::Lex(msg)) => assert_eq!(msg, "illegal character \"@\""),
+        other => panic!("expected lex error, got {:#?}", other),
+    }
+
+    match interpreter.eval_str(r#"let s = "abc;"#) {
+        Err(InterpreterError::Lex(msg)) => assert_eq!(msg, "unterminated string"),
+        other => panic!("expected lex error, got {:#?}", other),
+    }
+
+    match interpreter.eval_str("let = 5;") {
+        Err(InterpreterError::Parse(errors)) => assert!(
+            errors.contains(&"Expected Ident".to_string()),
+            "wrong parse errors: {:?}",
+            errors
+        ),
+        other => panic!("expected parse error, got {:#?}", other),
+    }
+
+    match interpreter.eval_str("if (true) { break; }") {
+        Err(InterpreterError::Parse(errors)) => {
+            assert_eq!(errors, vec!["break outside of a loop".to_string()])
+        }
+        other => panic!("expected parse error, got {:#?}", other),
+    }
+
+    match interpreter.eval_str("5 + true") {
+        Err(InterpreterError::Runtime(msg)) => {
+            assert_eq!(msg, "type mismatch: INTEGER + BOOLEAN")
+        }
+        other => panic!("expected runtime error, got {:#?}", other),
+    }
+
+    match interpreter.eval_file("/nonexistent/script.monkey") {
+        Err(InterpreterError::Io(_)) => (),
+        other => panic!("expected i/o error, got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_warnings() {
+    let mut interpreter = Interpreter::new();
+
+    test_integer_object(
+        &interpreter
+            .eval_str("match (2) { n => n, 3 => 0 }")
+            .unwrap(),
+        2,
+    );
+    assert_eq!(interpreter.warnings().len(), 1);
+    assert!(
+        interpreter.warnings()[0].starts_with("unreachable match arm 3"),
+        "wrong warnings: {:?}",
+        interpreter.warnings()
+    );
+
+    interpreter
+        .eval_str("match (2) { 3 => 0, n => n }")
+        .unwrap();
+    assert!(interpreter.warnings().is_empty());
+}
+
+#[test]
+fn test_eval_file() {
+    let path = env::temp_dir().join(format!("interpreter-{}.monkey", std::process::id()));
+    fs::write(&path, "let square = fn(x) { x * x };\nsquare(7);\n").unwrap();
+
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.eval_file(&path);
+    fs::remove_file(&path).unwrap();
+
+    test_integer_object(&result.unwrap(), 49);
+    assert!(interpreter.get_global("square").is_some());
+}
+
+#[test]
+fn test_globals_and_calls() {
+    let mut interpreter = Interpreter::new();
+
+    interpreter.set_global("base", Object::Integer(100));
+    interpreter
+        .eval_str("let offset = fn(x) { base + x };")
+        .unwrap();
+
+    test_integer_object(
+        &interpreter
+            .call_function("offset", vec![Object::Integer(5)])
+            .unwrap(),
+        105,
+    );
+    test_integer_object(&interpreter.get_global("base").unwrap(), 100);
+    assert!(interpreter.get_global("missing").is_none());
+
+    match interpreter.call_function("missing", vec![]) {
+        Err(InterpreterError::Runtime(msg)) => assert_eq!(msg, "identifier not found: missing"),
+        other => panic!("expected runtime error, got {:#?}", other),
+    }
+
+    match interpreter.call_function("offset", vec![]) {
+        Err(InterpreterError::Runtime(msg)) => {
+            assert_eq!(msg, "wrong number of arguments: want=1, got=0")
+        }
+        other => panic!("expected runtime error, got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_native_functions() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_native("fail", 0, |_| Err("host failure".to_string()));
+
+    match interpreter.eval_str("fail()") {
+        Err(InterpreterError::Runtime(msg)) => assert_eq!(msg, "host failure"),
+        other => panic!("expected runtime error, got {:#?}", other),
+    }
+
+    match interpreter.eval_str("fail = 1") {
+        Err(InterpreterError::Runtime(msg)) => {
+            assert_eq!(msg, "cannot assign to immutable variable fail at 1:1")
+        }
+        other => panic!("expected runtime error, got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_fuel_limit() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(Limits {
+        fuel: Some(10_000),
+        ..Limits::default()
+    });
+
+    match interpreter.eval_str("let f = fn() { f() }; f();") {
+        Err(InterpreterError::Limit(LimitExceeded::Fuel)) => (),
+        other => panic!("expected fuel limit, got {:#?}", other),
+    }
+    assert_eq!(interpreter.remaining_fuel(), Some(0));
+
+    interpreter.refill();
+    match interpreter.eval_str("while (true) {}") {
+        Err(InterpreterError::Limit(LimitExceeded::Fuel)) => (),
+        other => panic!("expected fuel limit, got {:#?}", other),
+    }
+
+    // An exhausted budget stays exhausted until refilled.
+    assert!(matches!(
+        interpreter.eval_str("1 + 1"),
+        Err(InterpreterError::Limit(LimitExceeded::Fuel))
+    ));
+
+    interpreter.refill();
+    assert_eq!(interpreter.remaining_fuel(), Some(10_000));
+    test_integer_object(&interpreter.eval_str("1 + 1").unwrap(), 2);
+    assert_eq!(interpreter.remaining_fuel(), Some(9_997));
+}
+
+#[test]
+fn test_call_depth_limit() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(Limits {
+        max_call_depth: Some(50),
+        ..Limits::default()
+    });
+
+    interpreter
+        .eval_str("let deep = fn(n) { if (n == 0) { 0 } else { 1 + deep(n - 1) } };")
+        .unwrap();
+
+    test_integer_object(&interpreter.eval_str("deep(40)").unwrap(), 40);
+    match interpreter.eval_str("deep(60)") {
+        Err(InterpreterError::Limit(LimitExceeded::CallDepth)) => (),
+        other => panic!("expected call depth limit, got {:#?}", other),
+    }
+
+    // Tail calls do not deepen the stack.
+    interpreter
+        .eval_str("let loop = fn(n) { if (n == 0) { 0 } else { loop(n - 1) } };")
+        .unwrap();
+    test_integer_object(&interpreter.eval_str("loop(1000)").unwrap(), 0);
+}
+
+#[test]
+fn test_allocation_limit() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(Limits {
+        max_allocations: Some(100),
+        ..Limits::default()
+    });
+
+    match interpreter.eval_str("let f = fn(n) { let g = fn() { n }; f(n + 1) }; f(0);") {
+        Err(InterpreterError::Limit(LimitExceeded::Allocations)) => (),
+        other => panic!("expected allocation limit, got {:#?}", other),
+    }
+    assert_eq!(interpreter.remaining_allocations(), Some(0));
+
+    interpreter.refill();
+    assert_eq!(interpreter.remaining_allocations(), Some(100));
+}
+
+#[test]
+fn test_allocation_limit_counts_sizes() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(Limits {
+        fuel: Some(10_000),
+        max_allocations: Some(10_000),
+        ..Limits::default()
+    });
+
+    for input in [
+        r#"let mut s = "ab"; while (true) { s = s + s; }"#,
+        r#"let mut s = "ab"; while (true) { s += s; }"#,
+        "let mut x = 3; while (true) { x = x * x; }",
+    ] {
+        interpreter.refill();
+        match interpreter.eval_str(input) {
+            Err(InterpreterError::Limit(LimitExceeded::Allocations)) => (),
+            other => panic!("expected allocation limit for {:?}, got {:?}", input, other),
+        }
+    }
+
+    interpreter.refill();
+    interpreter
+        .eval_str(r#"let s = "abc" + "def"; s[1..]"#)
+        .unwrap();
+    assert_eq!(interpreter.remaining_allocations(), Some(10_000 - 17));
+}
+
+#[test]
+fn test_deadline() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(Limits {
+        deadline: Some(Instant::now() + Duration::from_millis(50)),
+        ..Limits::default()
+    });
+
+    let start = Instant::now();
+    match interpreter.eval_str("let f = fn() { f() }; f();") {
+        Err(InterpreterError::Limit(LimitExceeded::Deadline)) => (),
+        other => panic!("expected deadline, got {:#?}", other),
+    }
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    interpreter.set_limits(Limits::default());
+    test_integer_object(&interpreter.eval_str("1").unwrap(), 1);
+}
+
+#[test]
+fn test_limit_errors_are_distinct_from_runtime_errors() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_limits(Limits {
+        fuel: Some(1_000),
+        ..Limits::default()
+    });
+
+    match interpreter.eval_str("5 + true") {
+        Err(InterpreterError::Runtime(msg)) => assert_eq!(msg, "type mismatch: INTEGER + BOOLEAN"),
+        other => panic!("expected runtime error, got {:#?}", other),
+    }
+
+    let err = interpreter
+        .eval_str("let f = fn() { f() }; f();")
+        .unwrap_err();
+    assert_eq!(err.to_string(), "limit exceeded: fuel exhausted");
+}
@@ -1,5 +1,5 @@
 use rust_monkey::lexer;
-use rust_monkey::token::TokenType;
+use rust_monkey::token::{Position, TokenType};
 
 struct Test {
     expected_type: TokenType,
@@ -340,10 +340,607 @@ if (5 < 10) {
         if tok.to_string() != tt.expected_literal {
             panic!(
                 "tests[{} - literal wrong. Expected {}, got {}]",
-                i,
-                tt.expected_literal,
-                tok.to_string()
+                i, tt.expected_literal, tok
             )
         }
     }
 }
+
+#[test]
+fn test_string_tokens() {
+    let input = r#""foobar" "foo bar" "a\nb\t\"c\"\\" "\u{48}\u{1F600}" "héllo";"#.to_string();
+
+    let tests = [
+        (TokenType::String, "foobar"),
+        (TokenType::String, "foo bar"),
+        (TokenType::String, "a\nb\t\"c\"\\"),
+        (TokenType::String, "H\u{1F600}"),
+        (TokenType::String, "héllo"),
+        (TokenType::Semicolon, ";"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+
+    assert!(l.errors().is_empty(), "unexpected errors {:?}", l.errors());
+}
+
+#[test]
+fn test_lexer_errors() {
+    let tests = [
+        (r#""abc"#, "unterminated string"),
+        (r#""abc\"#, "unterminated string"),
+        (r#""a\qb" 1"#, "unknown escape sequence \\q"),
+        (r#""\u{110000}" 1"#, "invalid unicode escape \\u{110000}"),
+        (r#""\u{41" 1"#, "invalid unicode escape \\u{41"),
+        (
+            r#""\u41" 1"#,
+            "invalid unicode escape: expected '{' after \\u",
+        ),
+        ("1 @ 1", "illegal character \"@\""),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let mut l = lexer::Lexer::new(input.to_string());
+
+        let mut types = vec![];
+        loop {
+            let tok = l.next_token();
+            if tok.token_type == TokenType::EOF {
+                break;
+            }
+            types.push(tok.token_type);
+        }
+
+        assert_eq!(
+            l.errors(),
+            [expected.to_string()],
+            "wrong errors for {:?}",
+            input
+        );
+        assert!(
+            types.contains(&TokenType::Illegal),
+            "no Illegal token for {:?}",
+            input
+        );
+        // Lexing resumes after the bad token.
+        if input.ends_with('1') {
+            assert_eq!(
+                types.last(),
+                Some(&TokenType::Int),
+                "did not recover for {:?}",
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn test_hash_tokens() {
+    let input = r#"{"one": 1, true: x}"#.to_string();
+
+    let tests = [
+        (TokenType::LBrace, "{"),
+        (TokenType::String, "one"),
+        (TokenType::Colon, ":"),
+        (TokenType::Int, "1"),
+        (TokenType::Comma, ","),
+        (TokenType::True, "true"),
+        (TokenType::Colon, ":"),
+        (TokenType::Ident, "x"),
+        (TokenType::RBrace, "}"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_number_tokens() {
+    let input = "3.14 1e-9 2.5E+3 7e2 10 3. 1.x 4e 2else".to_string();
+
+    let tests = [
+        (TokenType::Float, "3.14"),
+        (TokenType::Float, "1e-9"),
+        (TokenType::Float, "2.5E+3"),
+        (TokenType::Float, "7e2"),
+        (TokenType::Int, "10"),
+        (TokenType::Int, "3"),
+        (TokenType::Dot, "."),
+        (TokenType::Int, "1"),
+        (TokenType::Dot, "."),
+        (TokenType::Ident, "x"),
+        (TokenType::Int, "4"),
+        (TokenType::Ident, "e"),
+        (TokenType::Int, "2"),
+        (TokenType::Else, "else"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_optional_tokens() {
+    let input = "null ?? a?.b?[0] ? x".to_string();
+
+    let tests = [
+        (TokenType::Null, "null"),
+        (TokenType::NullCoalesce, "??"),
+        (TokenType::Ident, "a"),
+        (TokenType::OptionalDot, "?."),
+        (TokenType::Ident, "b"),
+        (TokenType::OptionalBracket, "?["),
+        (TokenType::Int, "0"),
+        (TokenType::RBracket, "]"),
+        (TokenType::Illegal, "?"),
+        (TokenType::Ident, "x"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+
+    assert_eq!(l.errors(), ["illegal character \"?\""]);
+}
+
+#[test]
+fn test_range_tokens() {
+    let input = "0..10 a[..=2] 1.5.. .".to_string();
+
+    let tests = [
+        (TokenType::Int, "0"),
+        (TokenType::DotDot, ".."),
+        (TokenType::Int, "10"),
+        (TokenType::Ident, "a"),
+        (TokenType::LBracket, "["),
+        (TokenType::DotDotEq, "..="),
+        (TokenType::Int, "2"),
+        (TokenType::RBracket, "]"),
+        (TokenType::Float, "1.5"),
+        (TokenType::DotDot, ".."),
+        (TokenType::Dot, "."),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_tuple_and_rest_pattern_tokens() {
+    let input = "let [a, ...rest] = (1, 2);".to_string();
+
+    let tests = [
+        (TokenType::Let, "let"),
+        (TokenType::LBracket, "["),
+        (TokenType::Ident, "a"),
+        (TokenType::Comma, ","),
+        (TokenType::Ellipsis, "..."),
+        (TokenType::Ident, "rest"),
+        (TokenType::RBracket, "]"),
+        (TokenType::Assign, "="),
+        (TokenType::LParen, "("),
+        (TokenType::Int, "1"),
+        (TokenType::Comma, ","),
+        (TokenType::Int, "2"),
+        (TokenType::RParen, ")"),
+        (TokenType::Semicolon, ";"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_struct_tokens() {
+    let input = "struct Point { x, y } Point { x: 1 }.x".to_string();
+
+    let tests = [
+        (TokenType::Struct, "struct"),
+        (TokenType::Ident, "Point"),
+        (TokenType::LBrace, "{"),
+        (TokenType::Ident, "x"),
+        (TokenType::Comma, ","),
+        (TokenType::Ident, "y"),
+        (TokenType::RBrace, "}"),
+        (TokenType::Ident, "Point"),
+        (TokenType::LBrace, "{"),
+        (TokenType::Ident, "x"),
+        (TokenType::Colon, ":"),
+        (TokenType::Int, "1"),
+        (TokenType::RBrace, "}"),
+        (TokenType::Dot, "."),
+        (TokenType::Ident, "x"),
+        (TokenType::EOF, "\0"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_comparison_and_logical_tokens() {
+    let input = "a <= b >= c && d || e < f > g".to_string();
+
+    let tests = [
+        (TokenType::Ident, "a"),
+        (TokenType::LtEq, "<="),
+        (TokenType::Ident, "b"),
+        (TokenType::GtEq, ">="),
+        (TokenType::Ident, "c"),
+        (TokenType::And, "&&"),
+        (TokenType::Ident, "d"),
+        (TokenType::Or, "||"),
+        (TokenType::Ident, "e"),
+        (TokenType::Lt, "<"),
+        (TokenType::Ident, "f"),
+        (TokenType::Gt, ">"),
+        (TokenType::Ident, "g"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_arithmetic_and_bitwise_tokens() {
+    let input = "a % b ** c & d | e ^ ~f << g >> h * i && j || k".to_string();
+
+    let tests = [
+        (TokenType::Ident, "a"),
+        (TokenType::Percent, "%"),
+        (TokenType::Ident, "b"),
+        (TokenType::Power, "**"),
+        (TokenType::Ident, "c"),
+        (TokenType::Ampersand, "&"),
+        (TokenType::Ident, "d"),
+        (TokenType::Pipe, "|"),
+        (TokenType::Ident, "e"),
+        (TokenType::Caret, "^"),
+        (TokenType::Tilde, "~"),
+        (TokenType::Ident, "f"),
+        (TokenType::ShiftLeft, "<<"),
+        (TokenType::Ident, "g"),
+        (TokenType::ShiftRight, ">>"),
+        (TokenType::Ident, "h"),
+        (TokenType::Asterisk, "*"),
+        (TokenType::Ident, "i"),
+        (TokenType::And, "&&"),
+        (TokenType::Ident, "j"),
+        (TokenType::Or, "||"),
+        (TokenType::Ident, "k"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_assignment_tokens() {
+    let input = "let mut x = 1; x += 2; x -= 3; x *= 4; x /= 5; x ** 2".to_string();
+
+    let tests = [
+        (TokenType::Let, "let"),
+        (TokenType::Mut, "mut"),
+        (TokenType::Ident, "x"),
+        (TokenType::Assign, "="),
+        (TokenType::Int, "1"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Ident, "x"),
+        (TokenType::PlusAssign, "+="),
+        (TokenType::Int, "2"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Ident, "x"),
+        (TokenType::MinusAssign, "-="),
+        (TokenType::Int, "3"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Ident, "x"),
+        (TokenType::AsteriskAssign, "*="),
+        (TokenType::Int, "4"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Ident, "x"),
+        (TokenType::SlashAssign, "/="),
+        (TokenType::Int, "5"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Ident, "x"),
+        (TokenType::Power, "**"),
+        (TokenType::Int, "2"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_token_positions() {
+    let input = "let x = 5;\n  x += \"a\nb\";\n\tfoo".to_string();
+
+    let tests = [
+        ("let", 1, 1),
+        ("x", 1, 5),
+        ("=", 1, 7),
+        ("5", 1, 9),
+        (";", 1, 10),
+        ("x", 2, 3),
+        ("+=", 2, 5),
+        ("a\nb", 2, 8),
+        (";", 3, 3),
+        ("foo", 4, 2),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_literal, line, column)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+        assert_eq!(
+            tok.position,
+            Position {
+                line: *line,
+                column: *column
+            },
+            "tests[{}] - position wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_loop_tokens() {
+    let input = "'outer: while (x) { break 'outer; continue; } ' 1".to_string();
+
+    let tests = [
+        (TokenType::Label, "outer"),
+        (TokenType::Colon, ":"),
+        (TokenType::While, "while"),
+        (TokenType::LParen, "("),
+        (TokenType::Ident, "x"),
+        (TokenType::RParen, ")"),
+        (TokenType::LBrace, "{"),
+        (TokenType::Break, "break"),
+        (TokenType::Label, "outer"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::Continue, "continue"),
+        (TokenType::Semicolon, ";"),
+        (TokenType::RBrace, "}"),
+        (TokenType::Illegal, "'"),
+        (TokenType::Int, "1"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_for_tokens() {
+    let input = "for (k, v in h) { inside }".to_string();
+
+    let tests = [
+        (TokenType::For, "for"),
+        (TokenType::LParen, "("),
+        (TokenType::Ident, "k"),
+        (TokenType::Comma, ","),
+        (TokenType::Ident, "v"),
+        (TokenType::In, "in"),
+        (TokenType::Ident, "h"),
+        (TokenType::RParen, ")"),
+        (TokenType::LBrace, "{"),
+        (TokenType::Ident, "inside"),
+        (TokenType::RBrace, "}"),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
+
+#[test]
+fn test_match_tokens() {
+    let input = "match (x) { 1 => y, _ => z } = ==".to_string();
+
+    let tests = [
+        (TokenType::Match, "match"),
+        (TokenType::LParen, "("),
+        (TokenType::Ident, "x"),
+        (TokenType::RParen, ")"),
+        (TokenType::LBrace, "{"),
+        (TokenType::Int, "1"),
+        (TokenType::FatArrow, "=>"),
+        (TokenType::Ident, "y"),
+        (TokenType::Comma, ","),
+        (TokenType::Ident, "_"),
+        (TokenType::FatArrow, "=>"),
+        (TokenType::Ident, "z"),
+        (TokenType::RBrace, "}"),
+        (TokenType::Assign, "="),
+        (TokenType::Eq, "=="),
+    ];
+
+    let mut l = lexer::Lexer::new(input);
+
+    for (i, (expected_type, expected_literal)) in tests.iter().enumerate() {
+        let tok = l.next_token();
+
+        assert_eq!(
+            tok.token_type, *expected_type,
+            "tests[{}] - token type wrong",
+            i
+        );
+        assert_eq!(
+            tok.literal, *expected_literal,
+            "tests[{}] - literal wrong",
+            i
+        );
+    }
+}
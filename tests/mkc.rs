@@ -0,0 +1,381 @@
+use rust_monkey::code::{make, Opcode, SourceMap};
+use rust_monkey::compiler::{Bytecode, Compiler};
+use rust_monkey::lexer;
+use rust_monkey::mkc::{self, MkcError, FORMAT_VERSION};
+use rust_monkey::object::{CompiledFunction, Matcher, Object};
+use rust_monkey::parser;
+use rust_monkey::vm::VM;
+use std::rc::Rc;
+
+fn compile(input: &str) -> Bytecode {
+    let l = Box::new(lexer::Lexer::new(input.to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+
+    let mut compiler = Compiler::new();
+    compiler.compile(&program).unwrap();
+    compiler.bytecode()
+}
+
+fn run(bytecode: Bytecode) -> Object {
+    let mut vm = VM::new(bytecode);
+    vm.run().unwrap();
+    vm.last_popped_stack_elem().clone()
+}
+
+const PROGRAM: &str = r#"
+let new_adder = fn(x) { fn(y) { x + y } };
+let fibonacci = fn(x) { if (x < 2) { x } else { fibonacci(x - 1) + fibonacci(x - 2) } };
+new_adder(-3)(fibonacci(10));
+"#;
+
+#[test]
+fn test_round_trip() {
+    let bytecode = compile(PROGRAM);
+    let bytes = mkc::encode(&bytecode).unwrap();
+
+    assert_eq!(&bytes[..4], b"MKC\0");
+
+    let loaded = mkc::decode(&bytes).unwrap();
+
+    assert_eq!(loaded.instructions, bytecode.instructions);
+    assert_eq!(loaded.constants.len(), bytecode.constants.len());
+
+    match run(loaded) {
+        Object::Integer(value) => assert_eq!(value, 52),
+        other => panic!("object is not Integer. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_round_trip_strings() {
+    let bytecode = compile(r#"let greet = fn(name) { "héllo, " + name }; greet("\u{1F600}")"#);
+    let loaded = mkc::decode(&mkc::encode(&bytecode).unwrap()).unwrap();
+
+    match run(loaded) {
+        Object::String(value) => assert_eq!(value, "héllo, \u{1F600}"),
+        other => panic!("object is not String. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_write_and_read_file() {
+    let path = std::env::temp_dir().join(format!("rust_monkey_{}.mkc", std::process::id()));
+
+    mkc::write_file(&path, &compile(PROGRAM)).unwrap();
+    let loaded = mkc::read_file(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    match run(loaded.unwrap()) {
+        Object::Integer(value) => assert_eq!(value, 52),
+        other => panic!("object is not Integer. got {:#?}", other),
+    }
+
+    assert!(matches!(mkc::read_file(&path), Err(MkcError::Io(_))));
+}
+
+#[test]
+fn test_rejects_bad_magic() {
+    let mut bytes = mkc::encode(&compile("1 + 2")).unwrap();
+    bytes[0] = b'X';
+
+    assert!(matches!(mkc::decode(&bytes), Err(MkcError::BadMagic)));
+}
+
+#[test]
+fn test_rejects_version_mismatch() {
+    let mut bytes = mkc::encode(&compile("1 + 2")).unwrap();
+    bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+
+    match mkc::decode(&bytes) {
+        Err(MkcError::UnsupportedVersion(version)) => assert_eq!(version, FORMAT_VERSION + 1),
+        other => panic!("expected UnsupportedVersion, got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_rejects_truncated_files() {
+    let bytes = mkc::encode(&compile(PROGRAM)).unwrap();
+
+    for len in 0..bytes.len() {
+        assert!(
+            mkc::decode(&bytes[..len]).is_err(),
+            "file truncated to {} bytes was accepted",
+            len
+        );
+    }
+
+    assert!(matches!(mkc::decode(&bytes[..3]), Err(MkcError::Truncated)));
+}
+
+#[test]
+fn test_rejects_corrupted_files() {
+    let bytes = mkc::encode(&compile(PROGRAM)).unwrap();
+
+    for i in 6..bytes.len() {
+        let mut corrupted = bytes.clone();
+        corrupted[i] ^= 0x5a;
+
+        assert!(
+            mkc::decode(&corrupted).is_err(),
+            "file corrupted at byte {} was accepted",
+            i
+        );
+    }
+
+    let mut corrupted = bytes.clone();
+    corrupted[10] ^= 0xff;
+    assert!(matches!(
+        mkc::decode(&corrupted),
+        Err(MkcError::ChecksumMismatch)
+    ));
+}
+
+#[test]
+fn test_rejects_invalid_instructions() {
+    let bytecode = Bytecode {
+        instructions: vec![255],
+        constants: vec![],
+        source_map: SourceMap::new(),
+    };
+    let bytes = mkc::encode(&bytecode).unwrap();
+    assert!(matches!(
+        mkc::decode(&bytes),
+        Err(MkcError::InvalidOpcode(255))
+    ));
+
+    let bytecode = Bytecode {
+        instructions: rust_monkey::code::make(rust_monkey::code::Opcode::Constant, &[3]),
+        constants: vec![Object::Integer(1)],
+        source_map: SourceMap::new(),
+    };
+    let bytes = mkc::encode(&bytecode).unwrap();
+    assert!(matches!(
+        mkc::decode(&bytes),
+        Err(MkcError::InvalidOperand { offset: 0 })
+    ));
+
+    let bytecode = Bytecode {
+        instructions: rust_monkey::code::make(rust_monkey::code::Opcode::IterNext, &[100, 1]),
+        constants: vec![],
+        source_map: SourceMap::new(),
+    };
+    let bytes = mkc::encode(&bytecode).unwrap();
+    assert!(matches!(
+        mkc::decode(&bytes),
+        Err(MkcError::InvalidOperand { offset: 0 })
+    ));
+
+    // A jump into the middle of an instruction.
+    let bytecode = Bytecode {
+        instructions: [
+            rust_monkey::code::make(rust_monkey::code::Opcode::Jump, &[5]),
+            rust_monkey::code::make(rust_monkey::code::Opcode::Constant, &[0]),
+        ]
+        .concat(),
+        constants: vec![Object::Integer(1)],
+        source_map: SourceMap::new(),
+    };
+    let bytes = mkc::encode(&bytecode).unwrap();
+    assert!(matches!(
+        mkc::decode(&bytes),
+        Err(MkcError::InvalidOperand { offset: 0 })
+    ));
+
+    let bytecode = Bytecode {
+        instructions: rust_monkey::code::make(rust_monkey::code::Opcode::Match, &[0, 1]),
+        constants: vec![Object::Integer(1)],
+        source_map: SourceMap::new(),
+    };
+    let bytes = mkc::encode(&bytecode).unwrap();
+    assert!(matches!(
+        mkc::decode(&bytes),
+        Err(MkcError::InvalidOperand { offset: 0 })
+    ));
+}
+
+#[test]
+fn test_rejects_unserializable_constants() {
+    let bytecode = Bytecode {
+        instructions: vec![],
+        constants: vec![Object::Boolean(true)],
+        source_map: SourceMap::new(),
+    };
+
+    assert!(matches!(
+        mkc::encode(&bytecode),
+        Err(MkcError::UnsupportedConstant("BOOLEAN"))
+    ));
+}
+
+#[test]
+fn test_round_trip_floats() {
+    let bytecode = compile("let scale = fn(x) { x * 0.5 }; scale(3) + 1e-3");
+    let loaded = mkc::decode(&mkc::encode(&bytecode).unwrap()).unwrap();
+
+    match run(loaded) {
+        Object::Float(value) => assert_eq!(value, 1.501),
+        other => panic!("object is not Float. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_round_trip_bignums() {
+    let bytecode = compile("-123456789012345678901234567890 * 2");
+    let loaded = mkc::decode(&mkc::encode(&bytecode).unwrap()).unwrap();
+
+    match run(loaded) {
+        Object::BigInt(value) => {
+            assert_eq!(value.to_string(), "-246913578024691357802469135780")
+        }
+        other => panic!("object is not BigInt. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_round_trip_struct_types() {
+    let bytecode = compile("struct Point { x, y } let p = Point { y: 2, x: 1 }; p");
+    let loaded = mkc::decode(&mkc::encode(&bytecode).unwrap()).unwrap();
+
+    assert_eq!(run(loaded).to_string(), "Point { x: 1, y: 2 }");
+}
+
+#[test]
+fn test_round_trip_match_patterns() {
+    let bytecode = compile(
+        r#"let f = fn(v) {
+               match (v) {
+                   -1 => "minus one",
+                   12345678901234567890 => "big",
+                   1.5 => "float",
+                   "s" => "string",
+                   true => "true",
+                   null => "null",
+                   (a, _) => a,
+                   [x, ...rest] => rest,
+                   {"k": [y], 2: z} => y + z,
+               }
+           };
+           [f(-1), f(12345678901234567890), f(1.5), f("s"), f(true), f(null),
+            f((1, 2)), f([1, 2]), f({"k": [3], 2: 4})]"#,
+    );
+    let loaded = mkc::decode(&mkc::encode(&bytecode).unwrap()).unwrap();
+
+    assert_eq!(
+        run(loaded).to_string(),
+        r#"["minus one", "big", "float", "string", "true", "null", 1, [2], 7]"#
+    );
+}
+
+#[test]
+fn test_rejects_deeply_nested_match_patterns() {
+    let mut matcher = Matcher::Wildcard;
+    for _ in 0..1000 {
+        matcher = Matcher::Tuple(vec![matcher]);
+    }
+
+    let bytecode = Bytecode {
+        instructions: vec![],
+        constants: vec![Object::Matcher(Rc::new(matcher))],
+        source_map: SourceMap::new(),
+    };
+    let bytes = mkc::encode(&bytecode).unwrap();
+    assert!(matches!(mkc::decode(&bytes), Err(MkcError::PatternTooDeep)));
+}
+
+#[test]
+fn test_loaded_bytecode_fails_without_panicking() {
+    let function = |num_locals: usize, instructions: Vec<Vec<u8>>| {
+        Object::CompiledFunction(Rc::new(CompiledFunction {
+            instructions: instructions.concat(),
+            num_locals,
+            num_parameters: 0,
+            source_map: SourceMap::new(),
+        }))
+    };
+    let call = [make(Opcode::Closure, &[0, 0]), make(Opcode::Call, &[0])].concat();
+
+    let tests = [
+        (
+            call.clone(),
+            vec![function(
+                0,
+                vec![make(Opcode::GetLocal, &[3]), make(Opcode::ReturnValue, &[])],
+            )],
+            "local 3 out of range",
+        ),
+        (
+            call.clone(),
+            vec![function(
+                0,
+                vec![make(Opcode::Null, &[]), make(Opcode::SetLocal, &[0])],
+            )],
+            "local 0 out of range",
+        ),
+        (
+            call.clone(),
+            vec![function(
+                0,
+                vec![make(Opcode::GetFree, &[0]), make(Opcode::ReturnValue, &[])],
+            )],
+            "free variable 0 out of range",
+        ),
+        (
+            call,
+            vec![function(
+                1,
+                vec![make(Opcode::SetLocal, &[0]), make(Opcode::Return, &[])],
+            )],
+            "stack underflow",
+        ),
+        (make(Opcode::Array, &[5]), vec![], "stack underflow"),
+        (make(Opcode::Call, &[0]), vec![], "stack underflow"),
+        (
+            [make(Opcode::Null, &[]), make(Opcode::Call, &[2])].concat(),
+            vec![],
+            "stack underflow",
+        ),
+        (
+            make(Opcode::Closure, &[0, 3]),
+            vec![function(0, vec![])],
+            "stack underflow",
+        ),
+    ];
+
+    for (instructions, constants, expected) in tests {
+        let bytecode = Bytecode {
+            instructions,
+            constants,
+            source_map: SourceMap::new(),
+        };
+        let loaded = mkc::decode(&mkc::encode(&bytecode).unwrap()).unwrap();
+
+        let mut vm = VM::new(loaded);
+        assert_eq!(vm.run(), Err(expected.to_string()));
+    }
+}
+
+#[test]
+fn test_rejects_oversized_functions() {
+    let tests = [
+        (70000, 0, "function locals count"),
+        (0, 300, "function parameter count"),
+    ];
+
+    for (num_locals, num_parameters, expected) in tests {
+        let bytecode = Bytecode {
+            instructions: vec![],
+            constants: vec![Object::CompiledFunction(Rc::new(CompiledFunction {
+                instructions: vec![],
+                num_locals,
+                num_parameters,
+                source_map: SourceMap::new(),
+            }))],
+            source_map: SourceMap::new(),
+        };
+
+        match mkc::encode(&bytecode) {
+            Err(MkcError::TooLarge(what)) => assert_eq!(what, expected),
+            other => panic!("expected TooLarge, got {:?}", other),
+        }
+    }
+}
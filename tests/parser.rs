@@ -3,6 +3,7 @@ use rust_monkey::ast::statement;
 use rust_monkey::ast::NodeInterface;
 use rust_monkey::lexer;
 use rust_monkey::parser;
+use rust_monkey::token::Position;
 
 #[test]
 fn test_let_statements() {
@@ -34,7 +35,7 @@ let foobar = 838383;
         program.statements.len()
     );
 
-    let tests = vec![Test::new("x"), Test::new("y"), Test::new("foobar")];
+    let tests = [Test::new("x"), Test::new("y"), Test::new("foobar")];
 
     for (i, tt) in tests.iter().enumerate() {
         let stmt = &program.statements[i];
@@ -52,18 +53,23 @@ fn test_let(s: &statement::Statement, name: String) {
         let_statement.token_literal()
     );
 
+    let ident = match let_statement.name.identifier() {
+        Some(ident) => ident,
+        None => panic!("Was expecting an identifier got {}", let_statement.name),
+    };
+
     assert_eq!(
-        let_statement.name.value, name,
+        ident.value, name,
         "let_statement.name.value not {} got {}",
-        name, let_statement.name.value
+        name, ident.value
     );
 
     assert_eq!(
-        let_statement.name.token.to_string(),
+        ident.token.to_string(),
         name,
         "let_statement.name.token.to_string not {}, got {}",
         name,
-        let_statement.name.token.to_string()
+        ident.token
     )
 }
 
@@ -95,7 +101,7 @@ fn test_return_statement() {
             return_statement.token.to_string(),
             "return".to_string(),
             "return_statement.token_literal() not 'return', got {} instead",
-            return_statement.token.to_string()
+            return_statement.token
         );
 
         assert!(return_statement.return_value.is_some());
@@ -209,6 +215,41 @@ fn test_integer_literal() {
     );
 }
 
+#[test]
+fn test_string_literal() {
+    let input = r#""hello\n world";"#.to_string();
+
+    let l = Box::new(lexer::Lexer::new(input));
+    let mut p = parser::Parser::new(l);
+    let program = p.parse_program().unwrap();
+    check_parser_errors(&p);
+
+    let stmt = match program.statements[0].expression_statement() {
+        Some(stmt) => stmt,
+        None => panic!("was expecting an ExpressionStatement, got None instead"),
+    };
+
+    let literal = match stmt.expression.string_literal() {
+        Some(literal) => literal,
+        None => panic!(
+            "was expecting a StringLiteral, got {:?} instead",
+            stmt.expression
+        ),
+    };
+
+    assert_eq!(literal.value, "hello\n world");
+    assert_eq!(program.to_string(), r#""hello\n world""#);
+}
+
+#[test]
+fn test_lexer_errors_are_reported() {
+    let l = Box::new(lexer::Lexer::new(r#"1 + "abc"#.to_string()));
+    let mut p = parser::Parser::new(l);
+
+    assert!(p.parse_program().is_err());
+    assert_eq!(p.errors().first(), Some(&"unterminated string".to_string()));
+}
+
 #[test]
 fn test_parsing_prefix_expression() {
     struct Test {
@@ -226,7 +267,7 @@ fn test_parsing_prefix_expression() {
         }
     }
 
-    let prefix_test = vec![
+    let prefix_test = [
         Test::new("!15;".to_string(), "!".to_string(), 15),
         Test::new("-15;".to_string(), "-".to_string(), 15),
     ];
@@ -303,7 +344,7 @@ fn test_parsing_infix_expression() {
         }
     }
 
-    let infix_tests = vec![
+    let infix_tests = [
         new("5 + 5".to_string(), 5, "+".to_string(), 5),
         new("5 - 5".to_string(), 5, "-".to_string(), 5),
         new("5 * 5".to_string(), 5, "*".to_string(), 5),
@@ -391,6 +432,41 @@ fn test_operator_precedence_parsing() {
         new("2 / (5 + 5)", "(2 / (5 + 5))"),
         new("-(5 + 5)", "(-(5 + 5))"),
         new("!(true == true)", "(!(true == true))"),
+        new(
+            "a * [1, 2, 3, 4][b * c] * d",
+            "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+        ),
+        new(
+            "add(a * b[2], b[1], 2 * [1, 2][1])",
+            "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+        ),
+        new("f(x)[0]", "(f(x)[0])"),
+        new("-a[0]", "(-(a[0]))"),
+        new("a <= b == b >= a", "((a <= b) == (b >= a))"),
+        new("a || b && c", "(a || (b && c))"),
+        new("a && b || c", "((a && b) || c)"),
+        new("a == b && c != d", "((a == b) && (c != d))"),
+        new("!a || b < c + 1", "((!a) || (b < (c + 1)))"),
+        new("a ?? b || c", "(a ?? (b || c))"),
+        new("a * b % c", "((a * b) % c)"),
+        new("a + b % c", "(a + (b % c))"),
+        new("a ** b ** c", "(a ** (b ** c))"),
+        new("a ** b * c", "((a ** b) * c)"),
+        new("a * b ** c", "(a * (b ** c))"),
+        new("-a ** b", "(-(a ** b))"),
+        new("a ** -b", "(a ** (-b))"),
+        new("~a ** b", "(~(a ** b))"),
+        new("a[0] ** f(b)", "((a[0]) ** f(b))"),
+        new("a << b + c", "(a << (b + c))"),
+        new("a >> b << c", "((a >> b) << c)"),
+        new("a & b << c", "(a & (b << c))"),
+        new("a ^ b & c", "(a ^ (b & c))"),
+        new("a | b ^ c", "(a | (b ^ c))"),
+        new("a | b & c ^ d", "(a | ((b & c) ^ d))"),
+        new("a & b == c", "((a & b) == c)"),
+        new("a < b | c", "(a < (b | c))"),
+        new("a | b && c | d", "((a | b) && (c | d))"),
+        new("~a & ~b", "((~a) & (~b))"),
     ];
 
     for tt in tests.iter() {
@@ -422,7 +498,7 @@ fn test_bool() {
         }
     }
 
-    let tests = vec![new("true", "true"), new("false", "false")];
+    let tests = [new("true", "true"), new("false", "false")];
 
     for tt in tests.iter() {
         let l = Box::new(lexer::Lexer::new(tt.input.clone()));
@@ -589,3 +665,749 @@ fn test_call_expression_parsing() {
         call.arguments.len()
     );
 }
+
+#[test]
+fn test_array_literal_parsing() {
+    let l = Box::new(lexer::Lexer::new("[1, 2 * 2, fn(x) { x }]".to_string()));
+    let mut p = parser::Parser::new(l);
+    let program = p.parse_program().unwrap();
+    check_parser_errors(&p);
+
+    let stmt = match program.statements[0].expression_statement() {
+        Some(stmt) => stmt,
+        None => panic!("Was expecting a Expression got None"),
+    };
+
+    let array = match stmt.expression.array_literal() {
+        Some(array) => array,
+        None => panic!("Was expecting ArrayLiteral got {:?}", stmt.expression),
+    };
+
+    assert_eq!(array.elements.len(), 3);
+    integer_literal_test(&array.elements[0], 1);
+    assert_eq!(array.elements[1].to_string(), "(2 * 2)");
+    assert!(array.elements[2].function_literal().is_some());
+
+    let l = Box::new(lexer::Lexer::new("[]".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    assert_eq!(program.to_string(), "[]");
+}
+
+#[test]
+fn test_index_expression_parsing() {
+    let l = Box::new(lexer::Lexer::new("my_array[1 + 1]".to_string()));
+    let mut p = parser::Parser::new(l);
+    let program = p.parse_program().unwrap();
+    check_parser_errors(&p);
+
+    let stmt = match program.statements[0].expression_statement() {
+        Some(stmt) => stmt,
+        None => panic!("Was expecting a Expression got None"),
+    };
+
+    let index = match stmt.expression.index_expression() {
+        Some(index) => index,
+        None => panic!("Was expecting Index got {:?}", stmt.expression),
+    };
+
+    assert_eq!(index.left.to_string(), "my_array");
+    assert_eq!(index.index.to_string(), "(1 + 1)");
+}
+
+#[test]
+fn test_unclosed_lists_are_errors() {
+    for input in ["[1, 2", "a[1", "add(1, 2"] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert!(p.parse_program().is_err(), "{:?} should not parse", input);
+    }
+}
+
+#[test]
+fn test_hash_literal_parsing() {
+    let tests = [
+        (r#"{"one": 1, "two": 2}"#, r#"{"one": 1, "two": 2}"#),
+        (
+            r#"{1: true, false: "x" + "y"}"#,
+            r#"{1: true, false: ("x" + "y")}"#,
+        ),
+        ("{}", "{}"),
+        ("let h = {a: [1]}; h[a][0]", "let h = {a: [1]};((h[a])[0])"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+
+    let l = Box::new(lexer::Lexer::new(r#"{"one": 1, "two": 2}"#.to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let stmt = program.statements[0].expression_statement().unwrap();
+    let hash = match stmt.expression.hash_literal() {
+        Some(hash) => hash,
+        None => panic!("Was expecting HashLiteral got {:?}", stmt.expression),
+    };
+
+    assert_eq!(hash.pairs.len(), 2);
+    assert!(hash.pairs[0].0.string_literal().is_some());
+    integer_literal_test(&hash.pairs[1].1, 2);
+}
+
+#[test]
+fn test_hash_literals_and_blocks_are_told_apart() {
+    let tests = [
+        ("if (x) { {1: 2} } else { {} }", "ifx {1: 2}else {}"),
+        ("fn() { {} }", "fn(){}"),
+        ("if (x) { 1 } else { 2 }", "ifx 1else 2"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+}
+
+#[test]
+fn test_malformed_hash_literals_are_errors() {
+    for input in ["{1: 2", "{1 2}", "{1: 2 3: 4}", "{1}"] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert!(p.parse_program().is_err(), "{:?} should not parse", input);
+    }
+}
+
+#[test]
+fn test_float_literal() {
+    let tests = [("2.75", 2.75), ("1e-9", 1e-9), ("2.5E+3", 2500.0)];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        let stmt = program.statements[0].expression_statement().unwrap();
+        let literal = match stmt.expression.float_literal() {
+            Some(literal) => literal,
+            None => panic!("Was expecting FloatLiteral got {:?}", stmt.expression),
+        };
+
+        assert_eq!(literal.value, *expected);
+        assert_eq!(literal.to_string(), *input);
+    }
+
+    let l = Box::new(lexer::Lexer::new("-1.5 * 2".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    assert_eq!(program.to_string(), "((-1.5) * 2)");
+
+    let l = Box::new(lexer::Lexer::new("1e400".to_string()));
+    let mut p = parser::Parser::new(l);
+    assert!(p.parse_program().is_err());
+    assert_eq!(
+        p.errors(),
+        vec!["could not parse 1e400 as float. Err: out of range".to_string()]
+    );
+}
+
+#[test]
+fn test_big_integer_literal() {
+    let input = "123456789012345678901234567890";
+    let l = Box::new(lexer::Lexer::new(input.to_string()));
+    let mut p = parser::Parser::new(l);
+    let program = p.parse_program().unwrap();
+    check_parser_errors(&p);
+
+    let stmt = program.statements[0].expression_statement().unwrap();
+    let literal = match stmt.expression.big_int_literal() {
+        Some(literal) => literal,
+        None => panic!("Was expecting BigIntLiteral got {:?}", stmt.expression),
+    };
+
+    assert_eq!(literal.value.to_string(), input);
+    assert_eq!(literal.to_string(), input);
+
+    let l = Box::new(lexer::Lexer::new("9223372036854775807".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let stmt = program.statements[0].expression_statement().unwrap();
+    integer_literal_test(&stmt.expression, i64::MAX);
+}
+
+#[test]
+fn test_optional_chaining_and_coalescing_parsing() {
+    let tests = [
+        ("null", "null"),
+        ("a ?? b", "(a ?? b)"),
+        ("a ?? b ?? c", "((a ?? b) ?? c)"),
+        ("a ?? b == c", "(a ?? (b == c))"),
+        ("a + 1 ?? 2 * 3", "((a + 1) ?? (2 * 3))"),
+        ("a?.b", "(a?.b)"),
+        ("a?.b?.c", "((a?.b)?.c)"),
+        ("a?[1 + 1]", "(a?[(1 + 1)])"),
+        ("a?.b[0]?[1]", "(((a?.b)[0])?[1])"),
+        ("-a?.b", "(-(a?.b))"),
+        ("f()?.x ?? 0", "((f()?.x) ?? 0)"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+
+    let l = Box::new(lexer::Lexer::new("config?.name".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let stmt = program.statements[0].expression_statement().unwrap();
+    let member = stmt.expression.optional_member_expression().unwrap();
+    assert_eq!(member.left.to_string(), "config");
+    assert_eq!(member.member.value, "name");
+
+    for input in ["a?.", "a?.1", "a?[1"] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert!(p.parse_program().is_err(), "{:?} should not parse", input);
+    }
+}
+
+#[test]
+fn test_range_parsing() {
+    let tests = [
+        ("0..10", "(0..10)"),
+        ("0..=10", "(0..=10)"),
+        ("a..b + 1", "(a..(b + 1))"),
+        ("a * 2..n - 1", "((a * 2)..(n - 1))"),
+        ("0..n == r", "((0..n) == r)"),
+        ("arr[1..3]", "(arr[(1..3)])"),
+        ("s[..2]", "(s[(..2)])"),
+        ("s[2..]", "(s[(2..)])"),
+        ("s[..]", "(s[(..)])"),
+        ("s[..=-1]", "(s[(..=(-1))])"),
+        ("let r = 1..;", "let r = (1..);"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+
+    let l = Box::new(lexer::Lexer::new("1..=5".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let stmt = program.statements[0].expression_statement().unwrap();
+    let range = stmt.expression.range().unwrap();
+    assert!(range.inclusive);
+    integer_literal_test(range.start.as_ref().unwrap(), 1);
+    integer_literal_test(range.end.as_ref().unwrap(), 5);
+
+    let l = Box::new(lexer::Lexer::new("a[1..=]".to_string()));
+    let mut p = parser::Parser::new(l);
+    assert!(p.parse_program().is_err());
+    assert_eq!(
+        p.errors(),
+        vec!["expected an end for inclusive range".to_string()]
+    );
+}
+
+#[test]
+fn test_tuple_literal_parsing() {
+    let tests = [
+        ("(1, \"x\")", "(1, \"x\")"),
+        ("(1,)", "(1,)"),
+        ("()", "()"),
+        ("(1)", "1"),
+        ("(1 + 2) * 3", "((1 + 2) * 3)"),
+        ("(a, b + 1)", "(a, (b + 1))"),
+        ("((1, 2), 3)", "((1, 2), 3)"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+
+    let l = Box::new(lexer::Lexer::new("(1, 2)".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let stmt = program.statements[0].expression_statement().unwrap();
+    let tuple = stmt.expression.tuple_literal().unwrap();
+    assert_eq!(tuple.elements.len(), 2);
+    integer_literal_test(&tuple.elements[0], 1);
+    integer_literal_test(&tuple.elements[1], 2);
+}
+
+#[test]
+fn test_destructuring_let_parsing() {
+    let tests = [
+        ("let (a, b) = pair;", "let (a, b) = pair;"),
+        ("let (a,) = single;", "let (a,) = single;"),
+        ("let (a) = x;", "let a = x;"),
+        ("let [head, ...rest] = list;", "let [head, ...rest] = list;"),
+        ("let [_, (x, y)] = list;", "let [_, (x, y)] = list;"),
+        ("let [] = list;", "let [] = list;"),
+        ("let (_, [a, ..._]) = t;", "let (_, [a, ..._]) = t;"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+
+    let l = Box::new(lexer::Lexer::new("let [a, ...rest] = list;".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let stmt = program.statements[0].let_statement().unwrap();
+    let array = stmt.name.array_pattern().unwrap();
+    assert_eq!(array.elements.len(), 1);
+    assert_eq!(array.elements[0].identifier().unwrap().value, "a");
+    assert_eq!(array.rest.as_ref().unwrap().to_string(), "rest");
+
+    let tests = [
+        ("let [...rest, a] = list;", "rest pattern must come last"),
+        (
+            "let (a, ...rest) = t;",
+            "rest patterns are only allowed in array patterns",
+        ),
+        (
+            "let (1, 2) = t;",
+            "literal and hash patterns are only allowed in match arms, got (1, 2)",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert!(p.parse_program().is_err(), "{:?} should not parse", input);
+        assert_eq!(p.errors(), vec![expected.to_string()]);
+    }
+}
+
+#[test]
+fn test_struct_parsing() {
+    let tests = [
+        ("struct Point { x, y }", "struct Point { x, y }"),
+        ("struct Empty {};", "struct Empty {  }"),
+        ("struct P { x, y, }", "struct P { x, y }"),
+        ("Point { x: 1, y: 2 + 3 }", "Point { x: 1, y: (2 + 3) }"),
+        ("p.x", "(p.x)"),
+        ("a.b.c", "((a.b).c)"),
+        ("p.f(1)", "(p.f)(1)"),
+        ("-p.x * 2", "((-(p.x)) * 2)"),
+        ("Point { x: 1 }.x", "(Point { x: 1 }.x)"),
+        ("p?.q.r", "((p?.q).r)"),
+        ("if (p) { p.x }", "ifp (p.x)"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+
+    let l = Box::new(lexer::Lexer::new("struct Point { x, y }".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let declaration = program.statements[0].struct_statement().unwrap();
+    assert_eq!(declaration.name.value, "Point");
+    let fields: Vec<&str> = declaration
+        .fields
+        .iter()
+        .map(|field| field.value.as_str())
+        .collect();
+    assert_eq!(fields, ["x", "y"]);
+
+    let l = Box::new(lexer::Lexer::new("Point { x: 1 }".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let stmt = program.statements[0].expression_statement().unwrap();
+    let literal = stmt.expression.struct_literal().unwrap();
+    assert_eq!(literal.name.value, "Point");
+    assert_eq!(literal.fields[0].0.value, "x");
+    integer_literal_test(&literal.fields[0].1, 1);
+
+    let l = Box::new(lexer::Lexer::new("struct P { x, x }".to_string()));
+    let mut p = parser::Parser::new(l);
+    assert!(p.parse_program().is_err());
+    assert_eq!(
+        p.errors(),
+        vec!["duplicate field x in struct P".to_string()]
+    );
+
+    for input in ["struct { x }", "struct P { 1 }", "P { x 1 }", "p.1"] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert!(p.parse_program().is_err(), "{:?} should not parse", input);
+    }
+}
+
+#[test]
+fn test_assignment_parsing() {
+    let tests = [
+        ("let mut x = 1;", "let mut x = 1;"),
+        ("let mut (a, b) = t;", "let mut (a, b) = t;"),
+        ("x = 5", "(x = 5)"),
+        ("x += 1 + 2", "(x += (1 + 2))"),
+        ("x -= 1; x *= 2; x /= 3", "(x -= 1)(x *= 2)(x /= 3)"),
+        ("x = y = z", "(x = (y = z))"),
+        ("a[i] = v", "((a[i]) = v)"),
+        ("p.x = 1", "((p.x) = 1)"),
+        ("a[0].b += c ?? d", "(((a[0]).b) += (c ?? d))"),
+        ("f(x = 1)", "f((x = 1))"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+
+    let l = Box::new(lexer::Lexer::new("let mut total = 0;".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let let_statement = program.statements[0].let_statement().unwrap();
+    assert!(let_statement.mutable);
+    test_let(&program.statements[0], "total".to_string());
+
+    let l = Box::new(lexer::Lexer::new("\n  count += 2".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let stmt = program.statements[0].expression_statement().unwrap();
+    let assign = stmt.expression.assign_expression().unwrap();
+    assert_eq!(assign.operator, "+=");
+    assert_eq!(assign.compound_operator(), Some("+"));
+    let target = assign.target.identifier().unwrap();
+    assert_eq!(target.value, "count");
+    assert_eq!(target.token.position, Position { line: 2, column: 3 });
+    integer_literal_test(&assign.value, 2);
+
+    for (input, error) in [
+        ("1 = 2", "invalid assignment target: 1"),
+        ("a + b = c", "invalid assignment target: (a + b)"),
+        ("f() = 1", "invalid assignment target: f()"),
+        ("a?.b = 1", "invalid assignment target: (a?.b)"),
+    ] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert!(p.parse_program().is_err(), "{:?} should not parse", input);
+        assert_eq!(p.errors(), vec![error.to_string()]);
+    }
+}
+
+#[test]
+fn test_while_parsing() {
+    let tests = [
+        ("while (x < 3) { x += 1; }", "while (x < 3) { (x += 1) }"),
+        (
+            "while (true) { break; continue }",
+            "while true { break;continue; }",
+        ),
+        (
+            "'outer: while (a) { while (b) { break 'outer; continue 'outer; } }",
+            "'outer: while a { while b { break 'outer;continue 'outer; } }",
+        ),
+        ("while (a) { fn() { 1 }; break }", "while a { fn()1break; }"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+
+    let l = Box::new(lexer::Lexer::new(
+        "'scan: while (i) { break 'scan; }".to_string(),
+    ));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let while_statement = program.statements[0].while_statement().unwrap();
+    assert_eq!(while_statement.label.as_deref(), Some("scan"));
+    assert_eq!(while_statement.condition.identifier().unwrap().value, "i");
+    let break_statement = while_statement.body.statements[0]
+        .break_statement()
+        .unwrap();
+    assert_eq!(break_statement.label.as_deref(), Some("scan"));
+
+    for (input, error) in [
+        ("break;", "break outside of a loop"),
+        ("if (x) { continue; }", "continue outside of a loop"),
+        ("while (x) { fn() { break; } }", "break outside of a loop"),
+        ("'a: while (x) { break 'b; }", "undeclared loop label 'b"),
+        (
+            "'a: while (x) { fn() { continue 'a; } }",
+            "undeclared loop label 'a",
+        ),
+        ("if (true) { break; }; 5", "break outside of a loop"),
+        (
+            "while (x) { let y = if (x) { break; } else { 1 }; }",
+            "break out of an expression whose value is used",
+        ),
+        (
+            "while (x) { 1 + if (x) { continue; } else { 0 } }",
+            "continue out of an expression whose value is used",
+        ),
+        (
+            "while (x) { if (x) { break; } + 1 }",
+            "break out of an expression whose value is used",
+        ),
+        (
+            "while (x) { f(1, if (x) { if (y) { break; } }) }",
+            "break out of an expression whose value is used",
+        ),
+        (
+            "while (x) { [match (x) { _ => if (x) { break; } }] }",
+            "break out of an expression whose value is used",
+        ),
+        (
+            "while (x) { while (if (x) { break; } else { true }) {} }",
+            "break out of an expression whose value is used",
+        ),
+        (
+            "'a: while (x) { return if (x) { while (y) { continue 'a; } } }",
+            "continue out of an expression whose value is used",
+        ),
+        ("if (true) { ) }; 5", "unexpected token ')'"),
+        ("fn(x) { x + }(1)", "unexpected token '}'"),
+        ("fn(x) { x", "expected } before the end of the input"),
+        (
+            "while (x) { if (y) { 1 }",
+            "expected } before the end of the input",
+        ),
+        ("let x = ; x", "unexpected token ';'"),
+        ("fn() { return ) }", "unexpected token ')'"),
+    ] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert_eq!(
+            p.parse_program().err(),
+            Some(error.to_string()),
+            "{:?}",
+            input
+        );
+        assert_eq!(p.errors(), vec![error.to_string()], "{:?}", input);
+    }
+
+    // Leaving a loop is fine from an `if` or `match` whose value is unused,
+    // and from a loop inside an expression whose value is used.
+    for input in [
+        "while (x) { if (x) { if (y) { break; } } else { continue; } }",
+        "while (x) { match (x) { 1 => if (y) { break; }, _ => null } }",
+        "while (x) { let y = if (x) { while (y) { break; } 1 } else { 2 }; }",
+        "let f = fn() { while (x) { if (x) { return 1; } } }",
+        "let f = fn() { while (x) { return; } }",
+        "'outer: while (true) { break 'outer; }; 5",
+    ] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        p.parse_program().unwrap();
+        check_parser_errors(&p);
+    }
+
+    for input in ["while x { 1 }", "'a: x", "'a while (x) {}"] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert!(p.parse_program().is_err(), "{:?} should not parse", input);
+    }
+}
+
+#[test]
+fn test_for_parsing() {
+    let tests = [
+        ("for (x in xs) { x; }", "for (x in xs) { x }"),
+        ("for (k, v in h) { k + v }", "for (k, v in h) { (k + v) }"),
+        (
+            "for ((a, b) in pairs) { continue }",
+            "for ((a, b) in pairs) { continue; }",
+        ),
+        ("for (_ in 0..3) { }", "for (_ in (0..3)) {  }"),
+        (
+            "'outer: for (i in xs) { for (j in ys) { break 'outer; } }",
+            "'outer: for (i in xs) { for (j in ys) { break 'outer; } }",
+        ),
+        (
+            "for (i in 0..3) { x += i }; x",
+            "for (i in (0..3)) { (x += i) }x",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+    }
+
+    let l = Box::new(lexer::Lexer::new("for (k, v in h) { k }".to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let for_statement = program.statements[0].for_statement().unwrap();
+    assert_eq!(for_statement.label, None);
+    assert_eq!(for_statement.variables.len(), 2);
+    assert_eq!(for_statement.variables[0].identifier().unwrap().value, "k");
+    assert_eq!(for_statement.variables[1].identifier().unwrap().value, "v");
+    assert_eq!(for_statement.iterable.identifier().unwrap().value, "h");
+    assert_eq!(for_statement.body.statements.len(), 1);
+
+    for input in [
+        "for x in xs { 1 }",
+        "for (x xs) { 1 }",
+        "for (a, b, c in xs) { 1 }",
+        "for (x in xs) 1",
+    ] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert!(p.parse_program().is_err(), "{:?} should not parse", input);
+    }
+}
+
+#[test]
+fn test_match_parsing() {
+    let tests = [
+        (
+            r#"match (v) { 0 => "zero", [a, b] => a + b, {"k": x} => x, n if n > 10 => "big", _ => "other" }"#,
+            r#"match v { 0 => "zero", [a, b] => (a + b), {"k": x} => x, n if (n > 10) => "big", _ => "other" }"#,
+        ),
+        (
+            "match (x) { -1 => true, (a, [b, ...c]) => c, null => false, }",
+            "match x { (-1) => true, (a, [b, ...c]) => c, null => false }",
+        ),
+        (
+            "let y = match (x) { _ => 1 };",
+            "let y = match x { _ => 1 };",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        let program = p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(program.to_string(), *expected);
+        assert!(p.warnings().is_empty(), "{:?}: {:?}", input, p.warnings());
+    }
+
+    let l = Box::new(lexer::Lexer::new(
+        "match (x) { 1 if x => 2, n => n }".to_string(),
+    ));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+    let match_expression = program.statements[0]
+        .expression_statement()
+        .unwrap()
+        .expression
+        .match_expression()
+        .unwrap();
+    assert_eq!(match_expression.subject.identifier().unwrap().value, "x");
+    assert_eq!(match_expression.arms.len(), 2);
+    assert!(match_expression.arms[0].pattern.literal_pattern().is_some());
+    assert!(match_expression.arms[0].guard.is_some());
+    assert_eq!(
+        match_expression.arms[1].pattern.identifier().unwrap().value,
+        "n"
+    );
+    assert!(match_expression.arms[1].guard.is_none());
+
+    for (input, warning) in [
+        ("match (x) { _ => 1, 2 => 2 }", "unreachable match arm 2"),
+        (
+            "match (x) { n => 1, [a] => 2 }",
+            "unreachable match arm [a]",
+        ),
+        (
+            "match (x) { [a, ...r] => 1, [1, 2] => 2 }",
+            "unreachable match arm [1, 2]",
+        ),
+        (
+            r#"match (x) { "a" => 1, "a" => 2 }"#,
+            r#"unreachable match arm "a""#,
+        ),
+    ] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        p.parse_program().unwrap();
+        check_parser_errors(&p);
+
+        assert_eq!(p.warnings().len(), 1, "{:?}", input);
+        assert!(
+            p.warnings()[0].starts_with(warning),
+            "{:?}: {:?}",
+            input,
+            p.warnings()
+        );
+    }
+
+    for input in [
+        "match (x) { n if n > 1 => 1, 2 => 2 }",
+        "match (x) { [a] => 1, [a, b] => 2 }",
+        "match (x) { (a, 1) => 1, (a, 2) => 2 }",
+    ] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+        p.parse_program().unwrap();
+
+        assert!(p.warnings().is_empty(), "{:?}: {:?}", input, p.warnings());
+    }
+
+    for (input, error) in [
+        (
+            "let (a, 1) = x;",
+            "literal and hash patterns are only allowed in match arms, got (a, 1)",
+        ),
+        (
+            r#"for ({"k": v} in xs) { v }"#,
+            "literal and hash patterns are only allowed in match arms",
+        ),
+        ("match (x) { {k: v} => v }", "invalid hash pattern key: k"),
+    ] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        match p.parse_program() {
+            Err(err) => assert!(err.starts_with(error), "{:?}: {:?}", input, err),
+            Ok(program) => panic!("{:?} should not parse. got {}", input, program),
+        }
+    }
+
+    for input in [
+        "match x { _ => 1 }",
+        "match (x) { _ 1 }",
+        "match (x) { 1 => 2 3 => 4 }",
+        "match (x) _ => 1",
+    ] {
+        let l = Box::new(lexer::Lexer::new(input.to_string()));
+        let mut p = parser::Parser::new(l);
+
+        assert!(p.parse_program().is_err(), "{:?} should not parse", input);
+    }
+}
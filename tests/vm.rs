@@ -0,0 +1,1116 @@
+use rust_monkey::compiler::Compiler;
+use rust_monkey::lexer;
+use rust_monkey::object::native::NativeRegistry;
+use rust_monkey::object::Object;
+use rust_monkey::parser;
+use rust_monkey::vm::VM;
+
+fn run_vm(input: &str) -> Result<Object, String> {
+    let l = Box::new(lexer::Lexer::new(input.to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+
+    let mut compiler = Compiler::new();
+    compiler.compile(&program)?;
+
+    let mut vm = VM::new(compiler.bytecode());
+    vm.run()?;
+
+    Ok(vm.last_popped_stack_elem().clone())
+}
+
+fn test_integer_object(input: &str, expected: i64) {
+    match run_vm(input) {
+        Ok(Object::Integer(value)) => assert_eq!(
+            value, expected,
+            "{:?} has wrong value. got {}, want {}",
+            input, value, expected
+        ),
+        other => panic!("{:?} did not produce an Integer. got {:#?}", input, other),
+    }
+}
+
+fn test_boolean_object(input: &str, expected: bool) {
+    match run_vm(input) {
+        Ok(Object::Boolean(value)) => assert_eq!(
+            value, expected,
+            "{:?} has wrong value. got {}, want {}",
+            input, value, expected
+        ),
+        other => panic!("{:?} did not produce a Boolean. got {:#?}", input, other),
+    }
+}
+
+#[test]
+fn test_integer_arithmetic() {
+    let tests = [
+        ("1", 1),
+        ("1 + 2", 3),
+        ("1 - 2", -1),
+        ("4 / 2", 2),
+        ("50 / 2 * 2 + 10 - 5", 55),
+        ("5 * (2 + 10)", 60),
+        ("-50 + 100 + -50", 0),
+        ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(input, *expected);
+    }
+}
+
+#[test]
+fn test_boolean_expressions() {
+    let tests = [
+        ("true", true),
+        ("1 < 2", true),
+        ("1 > 2", false),
+        ("1 == 1", true),
+        ("1 != 1", false),
+        ("true == false", false),
+        ("(1 < 2) == true", true),
+        ("!5", false),
+        ("!!true", true),
+        ("!(if (false) { 5; })", true),
+        ("1 <= 2", true),
+        ("2 <= 1", false),
+        ("2 >= 2", true),
+        ("1 >= 2.5", false),
+        ("1 < 2 && 2 < 3", true),
+        ("1 > 2 || 3 > 2", true),
+        ("false && 1 / 0 == 1", false),
+        ("true || 1 / 0 == 1", true),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_boolean_object(input, *expected);
+    }
+}
+
+#[test]
+fn test_conditionals() {
+    let tests = [
+        ("if (true) { 10 }", Some(10)),
+        ("if (1 < 2) { 10 } else { 20 }", Some(10)),
+        ("if (1 > 2) { 10 } else { 20 }", Some(20)),
+        ("if (1 > 2) { 10 }", None),
+        ("if ((if (false) { 10 })) { 10 } else { 20 }", Some(20)),
+        ("if (true) { let a = 1; }", None),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match expected {
+            Some(expected) => test_integer_object(input, *expected),
+            None => assert!(matches!(run_vm(input), Ok(Object::Null))),
+        }
+    }
+}
+
+#[test]
+fn test_global_let_statements() {
+    let tests = [
+        ("let one = 1; one", 1),
+        ("let one = 1; let two = 2; one + two", 3),
+        ("let one = 1; let two = one + one; one + two", 3),
+        ("let a = 1; let a = a + 1; a", 2),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(input, *expected);
+    }
+}
+
+#[test]
+fn test_return_statements() {
+    let tests = [
+        ("return 10; 9;", 10),
+        ("9; return 2 * 5; 9;", 10),
+        ("if (10 > 1) { if (10 > 1) { return 10; } return 1; }", 10),
+        ("let f = fn() { return 99; 100; }; f();", 99),
+        (
+            "let f = fn() { if (true) { return 1; } return 2; }; f();",
+            1,
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(input, *expected);
+    }
+}
+
+#[test]
+fn test_calling_functions() {
+    let tests = [
+        ("let f = fn() { 5 + 10; }; f();", 15),
+        ("let one = fn() { 1; }; let two = fn() { 2; }; one() + two()", 3),
+        ("let identity = fn(a) { a; }; identity(4);", 4),
+        ("let sum = fn(a, b) { let c = a + b; c; }; sum(1, 2);", 3),
+        (
+            "let global = 10; let sum = fn(a, b) { let c = a + b; c + global; }; sum(1, 2) + sum(3, 4);",
+            30,
+        ),
+        ("fn(x) { x; }(5)", 5),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(input, *expected);
+    }
+
+    assert!(matches!(run_vm("let f = fn() { }; f();"), Ok(Object::Null)));
+}
+
+#[test]
+fn test_closures() {
+    let tests = [
+        (
+            "let new_adder = fn(x) { fn(y) { x + y } }; let add_two = new_adder(2); add_two(2);",
+            4,
+        ),
+        ("let add = fn(a) { fn(b) { fn(c) { a + b + c } } }; add(1)(2)(3);", 6),
+        (
+            "let curry = fn(f) { fn(a) { fn(b) { f(a, b) } } }; let mul = fn(a, b) { a * b }; curry(mul)(3)(5);",
+            15,
+        ),
+        ("let x = 1; let f = fn() { x }; let g = fn(x) { f() }; g(2);", 1),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(input, *expected);
+    }
+}
+
+/// Closures share the mutable variables they capture, as in the evaluator.
+#[test]
+fn test_mutable_captures() {
+    let tests = [
+        (
+            "let counter = fn() { let mut n = 0; fn() { n += 1 } }; let c = counter(); c(); c(); c()",
+            "3",
+        ),
+        (
+            "let f = fn() { let mut n = 1; let get = fn() { n }; n = 5; get() }; f()",
+            "5",
+        ),
+        (
+            "let pair = fn() { let mut n = 0; (fn() { n += 1 }, fn() { n }) }; let (inc, get) = pair(); inc(); inc(); get()",
+            "2",
+        ),
+        (
+            "let mut f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; let g = f; f = fn(n) { 99 }; g(1)",
+            "99",
+        ),
+        (
+            "let run = fn() { let mut f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; let g = f; f = fn(n) { 99 }; g(1) }; run()",
+            "99",
+        ),
+        (
+            "let run = fn() { let mut f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f(10) }; run()",
+            "0",
+        ),
+        (
+            "let mut out = 0; for (i in 0..3) { let mut x = i; let add = fn() { x += 10 }; add(); out += x } out",
+            "33",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+}
+
+#[test]
+fn test_recursive_functions() {
+    let tests = [
+        (
+            "let count_down = fn(x) { if (x == 0) { return 0; } else { count_down(x - 1); } }; count_down(10);",
+            0,
+        ),
+        (
+            "let wrapper = fn() { let count_down = fn(x) { if (x == 0) { 0 } else { count_down(x - 1) } }; count_down(10); }; wrapper();",
+            0,
+        ),
+        (
+            "let fibonacci = fn(x) { if (x < 2) { x } else { fibonacci(x - 1) + fibonacci(x - 2) } }; fibonacci(15);",
+            610,
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(input, *expected);
+    }
+}
+
+#[test]
+fn test_runtime_errors() {
+    let tests = [
+        ("5 + true;", "type mismatch: INTEGER + BOOLEAN"),
+        ("-true", "unknown operator: -BOOLEAN"),
+        ("true + false;", "unknown operator: BOOLEAN + BOOLEAN"),
+        ("10 / 0", "division by zero"),
+        (
+            "fn(x) { x }(1, 2)",
+            "wrong number of arguments: want=1, got=2",
+        ),
+        ("5(1)", "not a function: INTEGER"),
+        (r#""a" - "b""#, "unknown operator: STRING - STRING"),
+        ("1[0]", "index operator not supported: INTEGER"),
+        ("let f = fn() { 1 + f() }; f();", "stack overflow"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Err(msg) => assert_eq!(msg, *expected, "wrong error message for {:?}", input),
+            Ok(obj) => panic!("expected VM error for {:?}, got {:#?}", input, obj),
+        }
+    }
+}
+
+#[test]
+fn test_tail_calls() {
+    let tests = [
+        (
+            "let loop = fn(n) { if (n == 0) { 0 } else { loop(n - 1) } }; loop(1000000);",
+            0,
+        ),
+        (
+            "let sum = fn(n, acc) { if (n == 0) { return acc; } sum(n - 1, acc + n) }; sum(100000, 0);",
+            5000050000,
+        ),
+        (
+            "let count = fn(n) { if (n == 0) { return 0; } return count(n - 1); }; count(100000);",
+            0,
+        ),
+        (
+            "let outer = fn(x) { let inner = fn(a, b, c) { a + b + c }; inner(x, x, x) }; outer(1) + outer(2);",
+            9,
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        test_integer_object(input, *expected);
+    }
+
+    match run_vm("let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } }; f(100000);") {
+        Err(msg) => assert_eq!(msg, "stack overflow"),
+        other => panic!("non-tail recursion should overflow, got {:#?}", other),
+    }
+}
+
+fn run_vm_with_natives(input: &str, natives: NativeRegistry) -> Result<Object, String> {
+    let l = Box::new(lexer::Lexer::new(input.to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+
+    let mut compiler = Compiler::new();
+    compiler.register_natives(&natives);
+    compiler.compile(&program)?;
+
+    let mut vm = VM::new(compiler.bytecode());
+    vm.set_natives(natives);
+    vm.run()?;
+
+    Ok(vm.last_popped_stack_elem().clone())
+}
+
+#[test]
+fn test_native_functions() {
+    let mut natives = NativeRegistry::new();
+    natives.register("double", 1, |args| match &args[0] {
+        Object::Integer(value) => Ok(Object::Integer(value * 2)),
+        other => Err(format!(
+            "double: expected INTEGER, got {}",
+            other.type_name()
+        )),
+    });
+    natives.register("answer", 0, |_| Ok(Object::Integer(42)));
+
+    let tests = [
+        ("double(21)", Ok(42)),
+        ("answer() + double(1)", Ok(44)),
+        ("let f = fn(x) { double(x) }; f(5)", Ok(10)),
+        ("let apply = fn(g, x) { g(x) }; apply(double, 4)", Ok(8)),
+        ("let double = fn(x) { x }; double(3)", Ok(3)),
+        (
+            "double(1, 2)",
+            Err("wrong number of arguments: want=1, got=2"),
+        ),
+        ("double(true)", Err("double: expected INTEGER, got BOOLEAN")),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match (run_vm_with_natives(input, natives.clone()), expected) {
+            (Ok(Object::Integer(got)), Ok(want)) => assert_eq!(got, *want, "{:?}", input),
+            (Err(got), Err(want)) => assert_eq!(got, *want, "{:?}", input),
+            (got, _) => panic!("{:?} produced {:#?}", input, got),
+        }
+    }
+}
+
+#[test]
+fn test_string_expressions() {
+    let tests = [
+        (r#""monkey""#, "monkey"),
+        (r#""mon" + "key""#, "monkey"),
+        (r#""mon" + "key" + "banana""#, "monkeybanana"),
+        (r#"let f = fn(a) { a + "!" }; f("hi\n")"#, "hi\n!"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(Object::String(value)) => assert_eq!(value, *expected),
+            other => panic!("{:?} did not produce a String. got {:#?}", input, other),
+        }
+    }
+
+    test_boolean_object(r#""a" == "a""#, true);
+    test_boolean_object(r#""a" != "a""#, false);
+}
+
+#[test]
+fn test_arrays() {
+    let tests = [
+        ("[1, 2, 3][1]", Some(2)),
+        ("[[1, 1, 1]][0][0]", Some(1)),
+        ("let a = [1, 2 + 3]; a[0] + a[1]", Some(6)),
+        ("let f = fn(x) { [x, x * 2] }; f(4)[1]", Some(8)),
+        ("[1, 2, 3][99]", None),
+        ("[1, 2, 3][-1]", None),
+        ("[][0]", None),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match expected {
+            Some(expected) => test_integer_object(input, *expected),
+            None => assert!(matches!(run_vm(input), Ok(Object::Null)), "{:?}", input),
+        }
+    }
+
+    match run_vm("[1, 2 * 2]") {
+        Ok(Object::Array(elements)) => assert_eq!(elements.len(), 2),
+        other => panic!("did not produce an Array. got {:#?}", other),
+    }
+
+    // Longer than the stack, so it has to be built in chunks.
+    let elements: Vec<String> = (0..5000).map(|i| i.to_string()).collect();
+    let input = format!("[{}]", elements.join(", "));
+    match run_vm(&input) {
+        Ok(array @ Object::Array(_)) => assert_eq!(array.to_string(), input),
+        other => panic!("did not produce an Array. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_hashes() {
+    let tests = [
+        (r#"{"a": 1, "b": 2}["b"]"#, Some(2)),
+        ("{1: 1, 1 + 1: 2 * 2}[2]", Some(4)),
+        ("let h = {true: 5}; h[1 == 1]", Some(5)),
+        (r#"let f = fn(k) { {"x": k}["x"] }; f(7)"#, Some(7)),
+        ("{1: 1}[0]", None),
+        ("{}[0]", None),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match expected {
+            Some(expected) => test_integer_object(input, *expected),
+            None => assert!(matches!(run_vm(input), Ok(Object::Null)), "{:?}", input),
+        }
+    }
+
+    match run_vm(r#"{"b": 2, "a": 1}"#) {
+        Ok(Object::Hash(pairs)) => {
+            assert_eq!(pairs.len(), 2);
+            assert_eq!(Object::Hash(pairs).to_string(), r#"{"a": 1, "b": 2}"#);
+        }
+        other => panic!("did not produce a Hash. got {:#?}", other),
+    }
+
+    for (input, expected) in [
+        ("{[1]: 2}", "unusable as hash key: ARRAY"),
+        ("{1: 2}[fn() { 1 }]", "unusable as hash key: CLOSURE"),
+    ] {
+        match run_vm(input) {
+            Err(msg) => assert_eq!(msg, expected),
+            other => panic!("{:?} should have failed. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_float_arithmetic() {
+    let tests = [
+        ("1.5 + 1.5", 3.0),
+        ("1 + 0.5", 1.5),
+        ("-2.5 * 2", -5.0),
+        ("7 / 2.0", 3.5),
+        ("let half = fn(x) { x / 2.0 }; half(5)", 2.5),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(Object::Float(value)) => assert_eq!(value, *expected, "{:?}", input),
+            other => panic!("{:?} did not produce a Float. got {:#?}", input, other),
+        }
+    }
+
+    test_integer_object("7 / 2", 3);
+    test_boolean_object("1 < 1.5", true);
+    test_boolean_object("2.5 > 3", false);
+    test_boolean_object("2 == 2.0", true);
+
+    match run_vm("1.0 / 0") {
+        Err(msg) => assert_eq!(msg, "division by zero"),
+        other => panic!("should have failed. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_bignums() {
+    let tests = [
+        ("9223372036854775807 + 1", "9223372036854775808"),
+        ("-18446744073709551616 * 3", "-55340232221128654848"),
+        (
+            "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(30)",
+            "265252859812191058636308480000000",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(Object::BigInt(value)) => assert_eq!(value.to_string(), *expected),
+            other => panic!("{:?} did not produce a BigInt. got {:#?}", input, other),
+        }
+    }
+
+    test_integer_object("18446744073709551616 / 18446744073709551616", 1);
+    test_integer_object("-9223372036854775808", i64::MIN);
+    test_boolean_object("18446744073709551616 > 1", true);
+    test_boolean_object("1 < 18446744073709551616", true);
+}
+
+#[test]
+fn test_optional_chaining_and_coalescing() {
+    let tests = [
+        ("null ?? 5", Some(5)),
+        ("3 ?? 5", Some(3)),
+        ("null ?? null ?? 4", Some(4)),
+        (r#"let config = {"port": 80}; config?.port"#, Some(80)),
+        (
+            r#"let config = {"port": 80}; config?.host ?? 8080"#,
+            Some(8080),
+        ),
+        (
+            r#"let f = fn(c) { c?.db?.port ?? 1 }; f({"db": {"port": 5}}) + f(null)"#,
+            Some(6),
+        ),
+        ("let xs = null; xs?[0] ?? 7", Some(7)),
+        ("[[1, 9]]?[0]?[1]", Some(9)),
+        ("null", None),
+        ("null?.a?.b", None),
+        ("null?[1 + 1]", None),
+        (r#"{"a": 1}?.b"#, None),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match expected {
+            Some(expected) => test_integer_object(input, *expected),
+            None => assert!(matches!(run_vm(input), Ok(Object::Null)), "{:?}", input),
+        }
+    }
+
+    test_boolean_object("null == null", true);
+    test_boolean_object("1 != null", true);
+
+    match run_vm("1?.a") {
+        Err(msg) => assert_eq!(msg, "member access not supported: INTEGER"),
+        other => panic!("should have failed. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_ranges_and_slices() {
+    let tests = [
+        ("[1, 2, 3, 4, 5][1..3]", "[2, 3]"),
+        ("[1, 2, 3, 4, 5][1..=3]", "[2, 3, 4]"),
+        ("[1, 2, 3, 4, 5][..2]", "[1, 2]"),
+        ("[1, 2, 3, 4, 5][3..]", "[4, 5]"),
+        ("[1, 2, 3, 4, 5][-2..]", "[4, 5]"),
+        ("let f = fn(xs, n) { xs[..n] }; f([1, 2, 3], -1)", "[1, 2]"),
+        (r#""hello"[1..3]"#, "el"),
+        (r#""hello"[..]"#, "hello"),
+        ("0..=10", "0..=10"),
+        ("..5", "..5"),
+        ("3..", "3.."),
+        ("..", ".."),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+
+    match run_vm("true..2") {
+        Err(msg) => assert_eq!(msg, "range bounds must be INTEGER, got BOOLEAN"),
+        other => panic!("should have failed. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_tuples_and_destructuring() {
+    let tests = [
+        (r#"(1, "x")"#, r#"(1, "x")"#),
+        ("(1,)", "(1,)"),
+        ("(1, 2, 3)[2]", "3"),
+        ("let (a, b) = (1, 2); a * 10 + b", "12"),
+        ("let (_, b) = (1, 2); b", "2"),
+        ("let [head, ...rest] = [1, 2, 3]; rest", "[2, 3]"),
+        ("let [head, ...rest] = [1, 2, 3]; head", "1"),
+        ("let [(a, b), [c, ..._]] = [(1, 2), [3, 4]]; a + b + c", "6"),
+        (
+            "let f = fn(p) { let [x, ...xs] = p; let (y, z) = (x, xs); z }; f([1, 2])",
+            "[2]",
+        ),
+        ("let a = 1; let (a, b) = (a + 1, a); a * 10 + b", "21"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+
+    let tests = [
+        (
+            "let (a, b) = [1, 2]; a",
+            "cannot destructure ARRAY as tuple",
+        ),
+        (
+            "let [a, b, ...c] = [1]; a",
+            "array pattern expects at least 2 elements, got 1",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Err(msg) => assert_eq!(msg, *expected),
+            other => panic!("{:?} should have failed. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_structs() {
+    let tests = [
+        ("struct Point { x, y } Point { y: 2, x: 1 }", "Point { x: 1, y: 2 }"),
+        ("struct Point { x, y } let p = Point { x: 1, y: 2 }; p.x + p.y", "3"),
+        (
+            "struct Point { x, y } let f = fn(p) { Point { x: p.y, y: p.x } }; f(Point { x: 1, y: 2 })",
+            "Point { x: 2, y: 1 }",
+        ),
+        (r#"{"a": 1}.a"#, "1"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+
+    let tests = [
+        (
+            "struct Point { x, y } Point { x: 1 }",
+            "missing field y for struct Point",
+        ),
+        (
+            "struct Point { x, y } Point { x: 1, y: 2 }.z",
+            "unknown field z for struct Point",
+        ),
+        ("let Point = 1; Point { x: 1 }", "not a struct: INTEGER"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Err(msg) => assert_eq!(msg, *expected),
+            other => panic!("{:?} should have failed. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_logical_operators_yield_deciding_operand() {
+    let tests = [
+        ("1 && 2", "2"),
+        ("null && 1 / 0", "null"),
+        ("0 || 5", "0"),
+        ("null || 5", "5"),
+        (
+            "let f = fn(x) { x > 0 && x < 10 }; [f(5), f(50)]",
+            "[true, false]",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+
+    match run_vm("true && 1 / 0") {
+        Err(msg) => assert_eq!(msg, "division by zero"),
+        other => panic!("should have failed. got {:#?}", other),
+    }
+}
+
+#[test]
+fn test_arithmetic_and_bitwise_operators() {
+    let tests = [
+        ("7 % 3", "1"),
+        ("2 ** 3 ** 2", "512"),
+        ("-2 ** 2", "-4"),
+        ("2 ** 64", "18446744073709551616"),
+        ("1 ** 300000", "1"),
+        ("(-1) ** (2 ** 100 + 1)", "-1"),
+        ("12 & 10 | 1", "9"),
+        ("12 ^ 10", "6"),
+        ("~5", "-6"),
+        ("1 << 4 >> 2", "4"),
+        (
+            "let f = fn(n) { n % 2 == 0 }; [f(4), f(7)]",
+            "[true, false]",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+
+    let tests = [
+        ("1 % 0", "division by zero"),
+        ("(2 ** 200000) * (2 ** 200000)", "integer result too large"),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Err(msg) => assert_eq!(msg, *expected, "{:?}", input),
+            other => panic!("{:?} should have failed. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_assignment() {
+    let tests = [
+        ("let mut x = 1; x = 2; x", "2"),
+        ("let mut x = 10; x += 5; x -= 3; x *= 2; x /= 4; x", "6"),
+        ("let mut x = 1; let mut y = 2; x = y = 3; [x, y]", "[3, 3]"),
+        ("let mut a = [1, 2, 3]; a[1] = 20; a", "[1, 20, 3]"),
+        (
+            "let mut a = [[1, 2], [3]]; a[0][1] += 5; a",
+            "[[1, 7], [3]]",
+        ),
+        ("let a = [1]; let mut b = a; b[0] = 2; [a, b]", "[[1], [2]]"),
+        (
+            r#"let mut h = {"a": 1}; h.a = 5; h["b"] = h.a; h"#,
+            r#"{"a": 5, "b": 5}"#,
+        ),
+        (
+            "struct P { x, y } let mut p = P { x: 1, y: 2 }; p.y *= 10; p",
+            "P { x: 1, y: 20 }",
+        ),
+        (
+            "let mut sum = 0; let add = fn(n) { sum += n }; add(1); add(2); sum",
+            "3",
+        ),
+        ("let f = fn() { let mut n = 1; n = n * 5; n }; f()", "5"),
+        ("let f = fn() { let mut a = [0]; a[0] = 7; a }; f()", "[7]"),
+        ("let mut (a, b) = (1, 2); a = b; a", "2"),
+        (
+            "let mut n = 0; let i = fn() { n += 1; 0 }; let mut a = [1]; a[i()] += 5; [a, n]",
+            "[[6], 1]",
+        ),
+        (
+            "let mut n = 0; let i = fn() { n += 1; n }; let mut a = [[1, 2, 3]]; a[i() - 1][i()] *= 10; [a, n]",
+            "[[[1, 2, 30]], 2]",
+        ),
+        (
+            r#"let mut n = 0; let i = fn() { n += 1; 0 }; let mut h = {"k": [5]}; h.k[i()] = n; [h, n]"#,
+            r#"[{"k": [1]}, 1]"#,
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+
+    let tests = [
+        (
+            "let x = 1; x = 2",
+            "cannot assign to immutable variable x at 1:12",
+        ),
+        ("y = 2", "cannot assign to undefined variable y at 1:1"),
+        (
+            "let a = [1]; a[0] = 2",
+            "cannot assign to immutable variable a at 1:14",
+        ),
+        (
+            "let f = fn(n) { n = 2 }; f(1)",
+            "cannot assign to immutable variable n at 1:17",
+        ),
+        (
+            "let mut a = [1]; a[1] = 2",
+            "array index out of bounds: 1 for length 1",
+        ),
+        (
+            "struct P { x } let mut p = P { x: 1 }; p.y = 2",
+            "unknown field y for struct P",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Err(msg) => assert_eq!(msg, *expected, "{:?}", input),
+            other => panic!("{:?} should have failed. got {:#?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_while_loops() {
+    let tests = [
+        (
+            "let mut i = 0; let mut sum = 0; while (i < 5) { i += 1; sum += i; } sum",
+            "15",
+        ),
+        (
+            "let mut i = 0; while (true) { if (i == 3) { break; } i += 1; } i",
+            "3",
+        ),
+        (
+            "let mut i = 0; let mut s = 0; while (i < 10) { i += 1; if (i % 2 == 0) { continue; } s += i; } s",
+            "25",
+        ),
+        (
+            "let mut found = null; let mut i = 0;
+             'outer: while (i < 5) {
+                 let mut j = 0;
+                 while (j < 5) {
+                     if (i * j == 6) { found = [i, j]; break 'outer; }
+                     j += 1;
+                 }
+                 i += 1;
+             }
+             found",
+            "[2, 3]",
+        ),
+        (
+            "let f = fn() {
+                 let mut n = 0; let mut i = 0;
+                 'rows: while (i < 3) {
+                     i += 1;
+                     let mut j = 0;
+                     while (true) { j += 1; if (j > i) { continue 'rows; } n += 1; }
+                 }
+                 n
+             }; f()",
+            "6",
+        ),
+        (
+            "let f = fn() { let mut i = 0; while (true) { i += 1; if (i == 4) { return i * 10; } } }; f()",
+            "40",
+        ),
+        ("let f = fn() { while (false) { 1 } }; f()", "null"),
+        // Leaving a loop many more times than the stack has slots must not
+        // leave anything behind on it.
+        (
+            "let mut i = 0; while (true) { i += 1; if (i > 5000) { break; } else { if (i % 2 == 0) { continue; } } } i",
+            "5001",
+        ),
+        (
+            "let mut i = 0; while (true) { i += 1; match (i % 3) { 0 => if (i > 5000) { break; } else { continue; }, _ => null } } i",
+            "5001",
+        ),
+        (
+            "let mut n = 0; 'outer: for (i in 0..5000) { for (j in 0..3) { if (j == 1) { continue 'outer; } n += 1 } } n",
+            "5000",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+}
+
+#[test]
+fn test_for_loops() {
+    let tests = [
+        (
+            "let mut s = 0; for (x in [1, 2, 3]) { s += x; } s",
+            "6",
+        ),
+        (
+            r#"let mut s = ""; for (c in "abc") { s = c + s; } s"#,
+            "cba",
+        ),
+        ("let mut s = 0; for (i in 1..=4) { s += i; } s", "10"),
+        ("let mut s = 0; for (x in (1, 2)) { s += x; } s", "3"),
+        (
+            r#"let mut s = ""; for (k in {"b": 2, "a": 1}) { s += k; } s"#,
+            "ab",
+        ),
+        (
+            "let mut s = 0; for (k, v in {1: 10, 2: 20}) { s += k * v; } s",
+            "50",
+        ),
+        (
+            "let mut s = 0; for (i, x in [5, 6, 7]) { s += i * x; } s",
+            "20",
+        ),
+        (
+            "let mut s = 0; for ((a, b) in [(1, 2), (3, 4)]) { s += a * b; } s",
+            "14",
+        ),
+        (
+            "let mut s = 0; for (i in 0..) { if (i == 5) { break; } s += i; } s",
+            "10",
+        ),
+        (
+            "let mut s = 0; for (i in 0..10) { if (i % 2 == 0) { continue; } s += i; } s",
+            "25",
+        ),
+        (
+            "let mut found = null;
+             'outer: for (i in 0..5) {
+                 for (j in 0..5) {
+                     if (i * j == 6) { found = [i, j]; break 'outer; }
+                 }
+             }
+             found",
+            "[2, 3]",
+        ),
+        (
+            "let f = fn() {
+                 let mut n = 0;
+                 'rows: for (i in 1..=3) {
+                     for (j in 1..) { if (j > i) { continue 'rows; } n += 1; }
+                 }
+                 n
+             }; f()",
+            "6",
+        ),
+        (
+            "let mut n = 0;
+             'outer: for (i in [1, 2]) {
+                 let mut j = 0;
+                 while (true) { j += 1; if (j == 3) { continue 'outer; } n += i; }
+             }
+             n",
+            "6",
+        ),
+        (
+            "let f = fn(xs) { for (x in xs) { if (x > 2) { return x * 10; } } 0 }; f([1, 2, 3, 4])",
+            "30",
+        ),
+        ("let f = fn() { for (x in []) { 1 } }; f()", "null"),
+        (
+            "let mut fs = [null, null, null]; for (i, x in [10, 20, 30]) { fs[i] = fn() { x }; } fs[0]() + fs[2]()",
+            "40",
+        ),
+        (
+            "let f = fn() { let mut fs = [null, null]; for (i in 0..2) { fs[i] = fn() { i }; } fs }; f()[0]()",
+            "0",
+        ),
+        ("let x = 5; for (x in [1, 2]) { } x", "5"),
+        (
+            "let f = fn(n) { let mut s = 0; for (i in 0..n) { s += i; } s }; f(100000)",
+            "4999950000",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+
+    for (input, error) in [
+        ("for (x in 5) { x }", "not iterable: INTEGER"),
+        (
+            "for (x in ..3) { x }",
+            "cannot iterate over range without a start: ..3",
+        ),
+        ("for (x in [1]) { } x", "identifier not found: x"),
+        ("for (x in [1]) { let y = x; } y", "identifier not found: y"),
+        (
+            "for ((a, b) in [1]) { a }",
+            "cannot destructure INTEGER as tuple",
+        ),
+    ] {
+        match run_vm(input) {
+            Err(err) => assert_eq!(err, error, "{:?}", input),
+            other => panic!("{:?} should fail. got {:?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_match_expressions() {
+    let tests = [
+        (
+            r#"let f = fn(v) { match (v) { 0 => "zero", [a, b] => a + b, {"k": x} => x, n if n > 10 => "big", _ => "other" } };
+               [f(0), f([1, 2]), f({"k": 7, "j": 8}), f(11), f(5)]"#,
+            r#"["zero", 3, 7, "big", "other"]"#,
+        ),
+        (
+            "match (-2) { 2 => \"pos\", -2 => \"neg\", _ => \"other\" }",
+            "neg",
+        ),
+        ("match (1.5) { 1 => \"int\", 1.5 => \"float\", _ => \"other\" }", "float"),
+        ("match (1) { 1.0 => \"float\", 1 => \"int\" }", "int"),
+        (
+            "match ([true, null]) { [false, _] => 1, [true, null] => 2, _ => 3 }",
+            "2",
+        ),
+        ("match ([1, 2, 3, 4]) { [first, ...rest] => rest }", "[2, 3, 4]"),
+        ("match ([1, 2, 3]) { [a, b, ..._] => a * b }", "2"),
+        ("match ((1, (2, 3))) { (a, (b, c)) => a + b + c }", "6"),
+        (
+            "match (5) { n if n > 10 => \"big\", n if n > 3 => \"medium\", _ => \"small\" }",
+            "medium",
+        ),
+        ("let x = 1; match (2) { x => x }; x", "1"),
+        (
+            "let f = match ((3, 4)) { (a, b) => fn() { a * b } }; f()",
+            "12",
+        ),
+        (
+            "let f = fn(xs) { match (xs) { [] => 0, [x, ...rest] => x + f(rest) } }; f([1, 2, 3, 4])",
+            "10",
+        ),
+        (
+            "let mut s = 0; for (x in [1, 2, 3]) { s += match (x) { 2 => 20, n => n }; } s",
+            "24",
+        ),
+        (
+            r#"match ({"a": 1}) { {"b": x} => x, {"a": x} => x * 100 }"#,
+            "100",
+        ),
+    ];
+
+    for (input, expected) in tests.iter() {
+        match run_vm(input) {
+            Ok(obj) => assert_eq!(obj.to_string(), *expected, "{:?}", input),
+            Err(err) => panic!("{:?} failed: {}", input, err),
+        }
+    }
+
+    for (input, error) in [
+        ("match (5) { 1 => 1, [a] => a }", "no match arm for 5"),
+        ("match (5) { x if x > 10 => x }", "no match arm for 5"),
+        ("match (5) { x => x }; x", "identifier not found: x"),
+        ("match (1) { x if y => x }", "identifier not found: y"),
+        (
+            "match ([1]) { n if n > 10 => n, _ => 0 }",
+            "type mismatch: ARRAY > INTEGER",
+        ),
+    ] {
+        match run_vm(input) {
+            Err(err) => assert_eq!(err, error, "{:?}", input),
+            other => panic!("{:?} should fail. got {:?}", input, other),
+        }
+    }
+}
+
+fn compile_vm(input: &str) -> VM {
+    let l = Box::new(lexer::Lexer::new(input.to_string()));
+    let program = parser::Parser::new(l).parse_program().unwrap();
+
+    let mut compiler = Compiler::new();
+    compiler.compile(&program).unwrap();
+
+    VM::new(compiler.bytecode())
+}
+
+#[test]
+fn test_collects_closures_stored_in_their_own_cells() {
+    let mut vm = compile_vm(
+        r#"
+let make = fn() { let mut f = null; f = fn(n) { if (n == 0) { 0 } else { f(n - 1) } }; f };
+let churn = fn(n) { if (n == 0) { 0 } else { make(); churn(n - 1) } };
+churn(100);
+"#,
+    );
+    vm.run().unwrap();
+
+    let before = vm.heap_stats();
+    let reclaimed = vm.collect_garbage();
+    let after = vm.heap_stats();
+
+    assert_eq!(before.live_objects, 100);
+    assert_eq!(reclaimed, 100);
+    assert_eq!(after.collections, before.collections + 1);
+    assert_eq!(after.reclaimed, before.reclaimed + reclaimed);
+    assert_eq!(after.live_objects, 0);
+}
+
+#[test]
+fn test_collects_cells_automatically() {
+    let mut vm = compile_vm(
+        r#"
+let make = fn(limit) {
+    let mut count = null;
+    count = fn(n) { if (n == limit) { n } else { count(n + 1) } };
+    [count]
+};
+let kept = make(10)[0];
+let churn = fn(n) { if (n == 0) { 0 } else { make(n); churn(n - 1) } };
+churn(1000);
+churn(1000);
+churn(1000);
+kept(0)
+"#,
+    );
+    vm.run().unwrap();
+
+    assert_eq!(vm.last_popped_stack_elem().to_string(), "10");
+    let stats = vm.heap_stats();
+    assert!(stats.collections > 0, "no collection ran");
+    assert!(stats.reclaimed > 0, "no cycles were freed");
+    assert!(
+        stats.live_objects < 3000,
+        "heap kept {} cells alive",
+        stats.live_objects
+    );
+}
+
+#[test]
+fn test_dropping_vm_frees_cycles() {
+    let mut vm = compile_vm("let make = fn() { let mut f = null; f = fn() { f }; f }; make()");
+    vm.run().unwrap();
+
+    let cell = match vm.last_popped_stack_elem() {
+        Object::Closure(closure) => match &closure.free[0] {
+            Object::Cell(cell) => std::rc::Rc::downgrade(cell),
+            other => panic!("free variable is not Cell. got {:#?}", other),
+        },
+        other => panic!("object is not Closure. got {:#?}", other),
+    };
+
+    drop(vm);
+    assert!(cell.upgrade().is_none(), "cell outlived the VM");
+}